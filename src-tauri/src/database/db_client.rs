@@ -1,10 +1,13 @@
-use crate::database::{DatabaseConfig, SSHConfig};
-use log::{error, info};
-use postgres_native_tls;
+use crate::database::{
+    lookup_pgpass, make_tls_connector, pg_ssl_mode, verify_host_key, DatabaseConfig, DbHost,
+    HostKeyInfo, HostKeyStatus, SSHConfig, SshTunnel, TargetSessionAttrs,
+};
+use log::{error, info, warn};
+use postgres_native_tls::MakeTlsConnector;
 use ssh2::Session;
 use std::error::Error;
 use std::fmt;
-use std::net::TcpStream;
+use std::net::{IpAddr, Ipv4Addr, TcpStream};
 use tokio::time::Duration;
 use tokio_postgres::{Client, Config as PgConfig};
 
@@ -13,6 +16,11 @@ pub enum DbError {
     Connection(String),
     Query(String),
     SSH(String),
+    /// SSH 主机密钥与 known_hosts 或固定指纹不一致
+    HostKeyMismatch(String),
+    Mask(String),
+    /// 试图在只读的源库连接上执行写操作
+    ReadOnly(String),
 }
 
 impl fmt::Display for DbError {
@@ -21,49 +29,252 @@ impl fmt::Display for DbError {
             DbError::Connection(msg) => write!(f, "Connection error: {}", msg),
             DbError::Query(msg) => write!(f, "Query error: {}", msg),
             DbError::SSH(msg) => write!(f, "SSH error: {}", msg),
+            DbError::HostKeyMismatch(msg) => write!(f, "SSH host key mismatch: {}", msg),
+            DbError::Mask(msg) => write!(f, "Mask error: {}", msg),
+            DbError::ReadOnly(msg) => write!(f, "Read-only violation: {}", msg),
         }
     }
 }
 
 impl Error for DbError {}
 
+impl DbError {
+    /// 不带错误类别前缀的错误信息
+    pub fn message(&self) -> &str {
+        match self {
+            DbError::Connection(msg)
+            | DbError::Query(msg)
+            | DbError::SSH(msg)
+            | DbError::HostKeyMismatch(msg)
+            | DbError::Mask(msg)
+            | DbError::ReadOnly(msg) => msg,
+        }
+    }
+}
+
+/// 未填写主机时依次查找的 Unix 套接字目录
+#[cfg(unix)]
+const DEFAULT_SOCKET_DIRS: [&str; 2] = ["/var/run/postgresql", "/tmp"];
+
+/// 主机在日志和错误信息中的写法，套接字连接显示套接字文件路径
+fn host_label(host: &DbHost) -> String {
+    if host.host.is_empty() || host.host.starts_with('/') {
+        format!("{}/.s.PGSQL.{}", host.host.trim_end_matches('/'), host.port)
+    } else {
+        format!("{}:{}", host.host, host.port)
+    }
+}
+
+/// Unix 套接字目录：以 `/` 开头的主机，或未填写主机时默认目录中存在套接字文件的那个
+fn socket_dir(host: &DbHost) -> Result<Option<String>, DbError> {
+    if !host.host.is_empty() && !host.host.starts_with('/') {
+        return Ok(None);
+    }
+
+    #[cfg(unix)]
+    {
+        if host.host.starts_with('/') {
+            return Ok(Some(host.host.clone()));
+        }
+        let socket_file = format!(".s.PGSQL.{}", host.port);
+        DEFAULT_SOCKET_DIRS
+            .iter()
+            .find(|dir| std::path::Path::new(dir).join(&socket_file).exists())
+            .map(|dir| Some(dir.to_string()))
+            .ok_or_else(|| {
+                DbError::Connection(format!(
+                    "未填写数据库主机，且在 {} 中找不到 Unix 套接字 {}",
+                    DEFAULT_SOCKET_DIRS.join("、"),
+                    socket_file
+                ))
+            })
+    }
+
+    #[cfg(not(unix))]
+    Err(DbError::Connection(
+        "数据库主机地址不能为空，当前系统不支持 Unix 套接字连接".to_string(),
+    ))
+}
+
+/// 在 SSH 错误前标明出错的是哪一跳，主机密钥不一致的错误保持原有类型
+fn hop_error(e: DbError, hop: &str) -> DbError {
+    match e {
+        DbError::HostKeyMismatch(msg) => DbError::HostKeyMismatch(format!("{}: {}", hop, msg)),
+        DbError::SSH(msg) | DbError::Connection(msg) => DbError::SSH(format!("{}: {}", hop, msg)),
+        other => other,
+    }
+}
+
+/// 测试连接的结果
+pub enum ConnectOutcome {
+    Connected(Box<DbClient>),
+    /// 遇到未被信任的 SSH 主机：握手后即停止，没有发送凭据，也没有建立隧道。
+    /// 包含已经过的各跳及该主机的密钥，最后一个为未被信任的主机
+    UntrustedHost(Vec<HostKeyInfo>),
+}
+
 pub struct DbClient {
     pub client: Client,
-    pub ssh_session: Option<Session>,
+    /// SSH 端口转发，随客户端一起释放；字段顺序保证先断开数据库连接再关闭隧道
+    pub ssh_tunnel: Option<SshTunnel>,
+    /// 经过 SSH 连接时各跳服务器的主机密钥，按跳板机顺序排列，最后一个为 SSH 主机
+    pub ssh_host_keys: Vec<HostKeyInfo>,
 }
 
 impl DbClient {
     pub async fn new(database_config: &DatabaseConfig) -> Result<Self, DbError> {
-        let (client, ssh_session) = match &database_config.ssh_config {
+        Self::open(database_config, false).await
+    }
+
+    /// 以 `default_transaction_read_only = on` 打开只读会话，用于源库
+    pub async fn new_read_only(database_config: &DatabaseConfig) -> Result<Self, DbError> {
+        Self::open(database_config, true).await
+    }
+
+    /// 测试连接：遇到未被信任的 SSH 主机时在握手后停止并返回其主机密钥，
+    /// 用户确认并固定指纹后再重新连接
+    pub async fn test_connect(database_config: &DatabaseConfig) -> Result<ConnectOutcome, DbError> {
+        Self::connect_outcome(database_config, true, false).await
+    }
+
+    async fn open(database_config: &DatabaseConfig, read_only: bool) -> Result<Self, DbError> {
+        match Self::connect_outcome(database_config, false, read_only).await? {
+            ConnectOutcome::Connected(client) => Ok(*client),
+            // 不接受未知主机时主机密钥校验已经返回错误，这里只是兜底
+            ConnectOutcome::UntrustedHost(_) => {
+                Err(DbError::SSH("SSH 主机尚未被信任".to_string()))
+            }
+        }
+    }
+
+    async fn connect_outcome(
+        database_config: &DatabaseConfig,
+        accept_unknown_host: bool,
+        read_only: bool,
+    ) -> Result<ConnectOutcome, DbError> {
+        match &database_config.ssh_config {
             Some(ssh_config) => {
-                let (client, session) =
-                    Self::connect_with_ssh(ssh_config, &database_config).await?;
-                (client, Some(session))
+                Self::connect_with_ssh(
+                    ssh_config,
+                    &database_config,
+                    accept_unknown_host,
+                    read_only,
+                )
+                .await
             }
             None => {
-                let client = Self::connect_db(&database_config).await?;
-                (client, None)
+                let client = Self::connect_db(&database_config, None, read_only).await?;
+                Ok(ConnectOutcome::Connected(Box::new(Self {
+                    client,
+                    ssh_tunnel: None,
+                    ssh_host_keys: Vec::new(),
+                })))
             }
-        };
-
-        Ok(Self {
-            client,
-            ssh_session,
-        })
+        }
     }
 
+    /// 依次经过跳板机和 SSH 主机建立隧道，再通过最后一跳的本地端口连接数据库
+    ///
+    /// 每一跳的 SSH 会话都建立在上一跳转发出的本地端口上，失败时错误信息会指出是哪一跳。
+    /// 每一跳都先校验主机密钥再认证，未被信任的主机不会收到任何凭据
     async fn connect_with_ssh(
         ssh_config: &SSHConfig,
         db_config: &DatabaseConfig,
-    ) -> Result<(Client, Session), DbError> {
+        accept_unknown_host: bool,
+        read_only: bool,
+    ) -> Result<ConnectOutcome, DbError> {
+        let hops: Vec<&SSHConfig> = ssh_config
+            .jump_hosts
+            .iter()
+            .chain(std::iter::once(ssh_config))
+            .collect();
+        if let Some(nested) = ssh_config.jump_hosts.iter().find(|h| !h.jump_hosts.is_empty()) {
+            return Err(DbError::SSH(format!(
+                "跳板机 {}:{} 不能再配置跳板机，请把所有跳板机按顺序写在同一个列表中",
+                nested.host, nested.port
+            )));
+        }
+
+        let db_hosts = db_config.hosts();
+        if let Some(host) = db_hosts
+            .iter()
+            .find(|h| h.host.is_empty() || h.host.starts_with('/'))
+        {
+            return Err(DbError::Connection(format!(
+                "经过 SSH 隧道时不支持 Unix 套接字连接: {}",
+                host_label(host)
+            )));
+        }
+
+        let mut upstream: Option<SshTunnel> = None;
+        let mut host_keys = Vec::with_capacity(hops.len());
+        for (i, hop) in hops.iter().enumerate() {
+            let label = if hops.len() == 1 {
+                format!("SSH 主机 {}@{}:{}", hop.username, hop.host, hop.port)
+            } else {
+                format!(
+                    "第 {}/{} 跳 {}@{}:{}",
+                    i + 1,
+                    hops.len(),
+                    hop.username,
+                    hop.host,
+                    hop.port
+                )
+            };
+
+            // 第一跳直接连接，之后的每一跳通过上一跳的隧道连接
+            let address = match &upstream {
+                Some(tunnel) => format!("127.0.0.1:{}", tunnel.local_port()),
+                None => format!("{}:{}", hop.host, hop.port),
+            };
+            let (session, host_key) = Self::open_session(hop, &address, accept_unknown_host)
+                .map_err(|e| hop_error(e, &label))?;
+            let trusted = host_key.status != HostKeyStatus::Unknown;
+            host_keys.push(host_key);
+            if !trusted {
+                warn!("{} 尚未被信任，握手后停止连接，未发送凭据", label);
+                return Ok(ConnectOutcome::UntrustedHost(host_keys));
+            }
+
+            info!("开始 SSH 认证");
+            Self::authenticate(&session, hop).map_err(|e| hop_error(e, &label))?;
+
+            // 本跳转发到下一跳，最后一跳转发到各个数据库主机
+            let targets: Vec<(&str, u16)> = match hops.get(i + 1) {
+                Some(next) => vec![(next.host.as_str(), next.port)],
+                None => db_hosts.iter().map(|h| (h.host.as_str(), h.port)).collect(),
+            };
+            info!("设置 {} 的端口转发", label);
+            let tunnel = SshTunnel::start(session, &targets, upstream.take())
+                .map_err(|e| hop_error(e, &label))?;
+            info!("使用本地端口: {}", tunnel.local_port());
+            upstream = Some(tunnel);
+        }
+        let tunnel = upstream.ok_or_else(|| DbError::SSH("SSH 隧道未建立".to_string()))?;
+
+        info!("通过 SSH 隧道连接数据库");
+        let client = Self::connect_db(db_config, Some(tunnel.local_ports()), read_only).await?;
+
+        info!("SSH 隧道和数据库连接都已建立成功");
+        Ok(ConnectOutcome::Connected(Box::new(Self {
+            client,
+            ssh_tunnel: Some(tunnel),
+            ssh_host_keys: host_keys,
+        })))
+    }
+
+    /// 连接到 `address` 并完成一跳的握手和主机密钥校验，认证由调用方在确认主机可信后进行
+    fn open_session(
+        ssh_config: &SSHConfig,
+        address: &str,
+        accept_unknown_host: bool,
+    ) -> Result<(Session, HostKeyInfo), DbError> {
         info!("开始建立 SSH 连接 {}:{}", ssh_config.host, ssh_config.port);
 
-        let tcp = TcpStream::connect(format!("{}:{}", ssh_config.host, ssh_config.port)).map_err(
-            |e| {
-                error!("SSH TCP 连接失败: {}", e);
-                DbError::SSH(e.to_string())
-            },
-        )?;
+        let tcp = TcpStream::connect(address).map_err(|e| {
+            error!("SSH TCP 连接失败: {}", e);
+            DbError::SSH(e.to_string())
+        })?;
 
         info!("创建 SSH 会话");
         let mut session = Session::new().map_err(|e| {
@@ -78,88 +289,155 @@ impl DbClient {
             DbError::SSH(e.to_string())
         })?;
 
-        // SSH认证
-        info!("开始 SSH 认证");
+        // 认证前校验主机密钥，避免把凭据发给冒充的服务器
+        info!("校验 SSH 主机密钥");
+        let host_key = verify_host_key(&session, ssh_config, accept_unknown_host).map_err(|e| {
+            error!("SSH 主机密钥校验失败: {}", e);
+            e
+        })?;
+
+        Ok((session, host_key))
+    }
+
+    /// 按配置的认证方式完成 SSH 认证
+    ///
+    /// - `password`: 密码认证
+    /// - `private_key`: 密钥认证，优先使用配置中内联的密钥内容，其次使用密钥文件路径，
+    ///   密钥有口令时使用 `passphrase`
+    /// - `agent`: 依次尝试 ssh-agent 中的每个身份
+    fn authenticate(session: &Session, ssh_config: &SSHConfig) -> Result<(), DbError> {
+        let username = ssh_config.username.as_str();
+        let passphrase = ssh_config.passphrase.as_deref().filter(|p| !p.is_empty());
+
         match ssh_config.auth_type.as_str() {
             "password" => {
                 info!("使用密码认证");
-                if let Some(password) = &ssh_config.password {
-                    info!("使用密码认证");
-                    session
-                        .userauth_password(&ssh_config.username, password)
-                        .map_err(|e| {
-                            error!("SSH 密码认证失败: {}", e);
-                            DbError::SSH(e.to_string())
-                        })?;
-                } else {
-                    return Err(DbError::SSH("密码不能为空".to_string()));
-                }
+                let password = ssh_config
+                    .password
+                    .as_deref()
+                    .ok_or_else(|| DbError::SSH("密码不能为空".to_string()))?;
+                session.userauth_password(username, password).map_err(|e| {
+                    error!("SSH 密码认证失败: {}", e);
+                    DbError::SSH(e.to_string())
+                })
             }
             "private_key" => {
-                if let Some(key_path) = &ssh_config.private_key_path {
-                    info!("使用密钥认证: {}", key_path);
-                    session
-                        .userauth_pubkey_file(
-                            &ssh_config.username,
-                            None,
-                            std::path::Path::new(key_path),
-                            None,
-                        )
-                        .map_err(|e| {
-                            error!("SSH 密钥认证失败: {}", e);
-                            DbError::SSH(e.to_string())
-                        })?;
-                } else {
-                    return Err(DbError::SSH("密钥路径不能为空".to_string()));
+                let inline_key = ssh_config.private_key.as_deref().filter(|k| !k.trim().is_empty());
+                let key_path = ssh_config.private_key_path.as_deref().filter(|p| !p.is_empty());
+                match (inline_key, key_path) {
+                    (Some(key), _) => {
+                        info!("使用内联密钥认证");
+                        Self::userauth_inline_key(session, username, key, passphrase)
+                    }
+                    (None, Some(key_path)) => {
+                        info!("使用密钥认证: {}", key_path);
+                        session
+                            .userauth_pubkey_file(
+                                username,
+                                None,
+                                std::path::Path::new(key_path),
+                                passphrase,
+                            )
+                            .map_err(|e| {
+                                error!("SSH 密钥认证失败: {}", e);
+                                DbError::SSH(e.to_string())
+                            })
+                    }
+                    (None, None) => Err(DbError::SSH("密钥内容和密钥路径不能同时为空".to_string())),
                 }
             }
-            _ => {
-                return Err(DbError::SSH("不支持的认证方式".to_string()));
+            "agent" => {
+                info!("使用 ssh-agent 认证");
+                Self::userauth_agent(session, username)
             }
+            _ => Err(DbError::SSH("不支持的认证方式".to_string())),
         }
+    }
+
+    /// 依次尝试 ssh-agent 中的身份，直到有一个被服务器接受
+    fn userauth_agent(session: &Session, username: &str) -> Result<(), DbError> {
+        let agent_error = |e: ssh2::Error| {
+            error!("SSH agent 认证失败: {}", e);
+            DbError::SSH(format!("ssh-agent 认证失败: {}", e))
+        };
+        let mut agent = session.agent().map_err(agent_error)?;
+        agent.connect().map_err(agent_error)?;
+        agent.list_identities().map_err(agent_error)?;
+
+        let mut result = Err(DbError::SSH("ssh-agent 中没有可用的身份".to_string()));
+        for identity in agent.identities().map_err(agent_error)? {
+            match agent.userauth(username, &identity) {
+                Ok(()) => {
+                    info!("ssh-agent 身份认证成功: {}", identity.comment());
+                    result = Ok(());
+                    break;
+                }
+                Err(e) => result = Err(agent_error(e)),
+            }
+        }
+        let _ = agent.disconnect();
+        result
+    }
 
-        info!("设置 SSH 端口转发");
-        let local_port = Self::find_available_port()?;
-        info!("使用本地端口: {}", local_port);
-        let _listener = session
-            .channel_forward_listen(local_port, None, None)
+    /// 使用内联的私钥内容认证
+    #[cfg(unix)]
+    fn userauth_inline_key(
+        session: &Session,
+        username: &str,
+        key: &str,
+        passphrase: Option<&str>,
+    ) -> Result<(), DbError> {
+        session
+            .userauth_pubkey_memory(username, None, key, passphrase)
             .map_err(|e| {
-                error!("设置端口转发失败: {}", e);
+                error!("SSH 密钥认证失败: {}", e);
                 DbError::SSH(e.to_string())
-            })?;
-
-        info!("通过 SSH 隧道连接数据库");
-        let mut db_config = db_config.clone();
-        db_config.host = "127.0.0.1".to_string();
-        db_config.port = local_port;
-        let client = Self::connect_db(&db_config).await?;
-
-        info!("SSH 隧道和数据库连接都已建立成功");
-        Ok((client, session))
+            })
     }
 
-    fn find_available_port() -> Result<u16, DbError> {
-        let listener = std::net::TcpListener::bind("127.0.0.1:0")
-            .map_err(|e| DbError::Connection(e.to_string()))?;
-        let port = listener
-            .local_addr()
-            .map_err(|e| DbError::Connection(e.to_string()))?
-            .port();
-        Ok(port)
+    /// 使用内联的私钥内容认证
+    ///
+    /// 没有 OpenSSL 的平台上 libssh2 不能从内存读取密钥，先写入仅当前用户可读的临时文件，
+    /// 认证结束后立即删除
+    #[cfg(not(unix))]
+    fn userauth_inline_key(
+        session: &Session,
+        username: &str,
+        key: &str,
+        passphrase: Option<&str>,
+    ) -> Result<(), DbError> {
+        use std::io::Write;
+
+        let temp_error = |e: std::io::Error| DbError::SSH(format!("写入临时密钥文件失败: {}", e));
+        let mut file = tempfile::NamedTempFile::new().map_err(temp_error)?;
+        file.write_all(key.as_bytes()).map_err(temp_error)?;
+        file.flush().map_err(temp_error)?;
+        session
+            .userauth_pubkey_file(username, None, file.path(), passphrase)
+            .map_err(|e| {
+                error!("SSH 密钥认证失败: {}", e);
+                DbError::SSH(e.to_string())
+            })
     }
 
-    async fn connect_db(config: &DatabaseConfig) -> Result<Client, DbError> {
+    /// 按顺序尝试各个主机，返回第一个满足 `target_session_attrs` 的连接
+    ///
+    /// `tunnel_ports` 为 SSH 隧道为各主机在本地监听的端口，顺序与 `DatabaseConfig::hosts` 一致；
+    /// `read_only` 为 true 时会话默认只读
+    async fn connect_db(
+        config: &DatabaseConfig,
+        tunnel_ports: Option<&[u16]>,
+        read_only: bool,
+    ) -> Result<Client, DbError> {
+        let hosts = config.hosts();
         info!(
-            "开始连接数据库 {}:{}/{}",
-            config.host, config.port, config.database
+            "开始连接数据库 {}/{}",
+            hosts.iter().map(host_label).collect::<Vec<_>>().join(","),
+            config.database
         );
-        info!("SSL 模式: {}", config.ssl_mode);
+        info!("SSL 模式: {}", config.ssl_mode.as_str());
 
         // 验证连接参数
-        if config.host.is_empty() {
-            return Err(DbError::Connection("数据库主机地址不能为空".to_string()));
-        }
-
         if config.database.is_empty() {
             return Err(DbError::Connection("数据库名不能为空".to_string()));
         }
@@ -168,57 +446,138 @@ impl DbClient {
             return Err(DbError::Connection("数据库用户名不能为空".to_string()));
         }
 
+        info!("创建 TLS 连接器");
+        let connector = make_tls_connector(config)?;
+
+        if hosts.len() == 1 {
+            let tunnel_port = tunnel_ports.and_then(|ports| ports.first().copied());
+            let client =
+                Self::connect_host(config, &hosts[0], tunnel_port, connector, read_only).await?;
+            if config.target_session_attrs == TargetSessionAttrs::ReadWrite
+                && !Self::is_writable(&client, read_only).await?
+            {
+                return Err(DbError::Connection(format!(
+                    "{} 为只读会话，不满足 read-write 要求",
+                    host_label(&hosts[0])
+                )));
+            }
+            return Ok(client);
+        }
+
+        info!("会话要求: {}", config.target_session_attrs.as_str());
+        let mut errors = Vec::new();
+        // prefer-standby 时先保留第一个可用的主库连接，没有备库可用时使用
+        let mut fallback = None;
+        for (i, host) in hosts.iter().enumerate() {
+            let label = host_label(host);
+            let tunnel_port = tunnel_ports.and_then(|ports| ports.get(i).copied());
+            let client =
+                match Self::connect_host(config, host, tunnel_port, connector.clone(), read_only)
+                    .await
+                {
+                    Ok(client) => client,
+                    Err(e) => {
+                        warn!("连接 {} 失败，尝试下一个主机: {}", label, e);
+                        errors.push(e.message().to_string());
+                        continue;
+                    }
+                };
+
+            match config.target_session_attrs {
+                TargetSessionAttrs::Any => return Ok(client),
+                TargetSessionAttrs::ReadWrite => match Self::is_writable(&client, read_only).await {
+                    Ok(true) => return Ok(client),
+                    Ok(false) => {
+                        info!("{} 为只读会话，尝试下一个主机", label);
+                        errors.push(format!("{} 为只读会话", label));
+                    }
+                    Err(e) => errors.push(e.message().to_string()),
+                },
+                TargetSessionAttrs::PreferStandby => match Self::is_standby(&client).await {
+                    Ok(true) => return Ok(client),
+                    Ok(false) => {
+                        info!("{} 不是备库，尝试下一个主机", label);
+                        if fallback.is_none() {
+                            fallback = Some((label, client));
+                        }
+                    }
+                    Err(e) => errors.push(e.message().to_string()),
+                },
+            }
+        }
+
+        if let Some((label, client)) = fallback {
+            info!("没有可用的备库，使用 {}", label);
+            return Ok(client);
+        }
+        Err(DbError::Connection(format!(
+            "没有满足 {} 要求的主机: {}",
+            config.target_session_attrs.as_str(),
+            errors.join("；")
+        )))
+    }
+
+    /// 连接单个主机，`tunnel_port` 为 SSH 隧道为该主机在本地监听的端口
+    async fn connect_host(
+        config: &DatabaseConfig,
+        host: &DbHost,
+        tunnel_port: Option<u16>,
+        connector: MakeTlsConnector,
+        read_only: bool,
+    ) -> Result<Client, DbError> {
+        let socket_dir = socket_dir(host)?;
+        let label = host_label(host);
+
+        // 未填写密码时按 libpq 的方式从密码文件中查找，套接字连接按 localhost 匹配
+        let password = if config.password.is_empty() {
+            let pgpass_host = if socket_dir.is_some() { "localhost" } else { host.host.as_str() };
+            lookup_pgpass(pgpass_host, host.port, &config.database, &config.username)
+                .unwrap_or_default()
+        } else {
+            config.password.clone()
+        };
+
         let mut pg_config = PgConfig::new();
         pg_config
-            .host(&config.host)
-            .port(config.port)
             .dbname(&config.database)
             .user(&config.username)
-            .password(&config.password)
+            .password(&password)
             .application_name("db_copier")
             .connect_timeout(Duration::from_secs(10))
             .keepalives(true)
-            .keepalives_idle(Duration::from_secs(30))
-            .ssl_mode(match config.ssl_mode.as_str() {
-                "require" => {
-                    info!("使用 SSL 加密连接");
-                    tokio_postgres::config::SslMode::Require
-                }
-                "disable" => {
-                    info!("禁用 SSL 连接");
-                    tokio_postgres::config::SslMode::Disable
-                }
-                _ => {
-                    info!("使用首选 SSL 连接");
-                    tokio_postgres::config::SslMode::Prefer
-                }
-            });
-
-        info!("创建 TLS 连接器");
-        let mut builder = native_tls::TlsConnector::builder();
-
-        // 如果启用了 SSL，允许自签名证书
-        if config.ssl_mode == "require" {
-            info!("允许自签名证书");
-            builder.danger_accept_invalid_certs(true);
+            .keepalives_idle(Duration::from_secs(30));
+        if read_only {
+            pg_config.options("-c default_transaction_read_only=on");
         }
 
-        let connector = builder
-            .build()
-            .map_err(|e| DbError::Connection(format!("创建 TLS 连接器失败: {}", e)))?;
-
-        let connector = postgres_native_tls::MakeTlsConnector::new(connector);
+        match (socket_dir, tunnel_port) {
+            // Unix 套接字不经过网络，与 libpq 一致不使用 SSL
+            (Some(dir), _) => {
+                pg_config
+                    .host(dir)
+                    .port(host.port)
+                    .ssl_mode(tokio_postgres::config::SslMode::Disable);
+            }
+            // 经过 SSH 隧道时连接本地转发端口，主机名仍用于 TLS 证书校验
+            (None, Some(local_port)) => {
+                pg_config
+                    .host(&host.host)
+                    .hostaddr(IpAddr::V4(Ipv4Addr::LOCALHOST))
+                    .port(local_port)
+                    .ssl_mode(pg_ssl_mode(config.ssl_mode));
+            }
+            (None, None) => {
+                pg_config
+                    .host(&host.host)
+                    .port(host.port)
+                    .ssl_mode(pg_ssl_mode(config.ssl_mode));
+            }
+        }
 
-        info!(
-            "建立数据库连接 {}:{}/{}",
-            config.host, config.port, config.database
-        );
+        info!("建立数据库连接 {}/{}", label, config.database);
         let (client, connection) = pg_config.connect(connector).await.map_err(|e| {
-            error!("连接数据库失败 ({}:{}): {}", config.host, config.port, e);
-            DbError::Connection(format!(
-                "连接数据库失败 ({}:{}): {}",
-                config.host, config.port, e
-            ))
+            error!("连接数据库失败 ({}): {}", label, e);
+            DbError::Connection(format!("连接数据库失败 ({}): {}", label, e))
         })?;
 
         tokio::spawn(async move {
@@ -227,13 +586,32 @@ impl DbClient {
             }
         });
 
-        info!(
-            "数据库连接成功 {}:{}/{}",
-            config.host, config.port, config.database
-        );
+        info!("数据库连接成功 {}/{}", label, config.database);
         Ok(client)
     }
 
+    /// 会话是否可写，与 libpq 的 read-write 判断方式一致；
+    /// 只读会话的 `transaction_read_only` 总是 on，此时改为判断服务器是否为主库
+    async fn is_writable(client: &Client, read_only: bool) -> Result<bool, DbError> {
+        if read_only {
+            return Ok(!Self::is_standby(client).await?);
+        }
+        let row = client
+            .query_one("SHOW transaction_read_only", &[])
+            .await
+            .map_err(|e| DbError::Query(e.to_string()))?;
+        Ok(row.get::<_, String>(0) != "on")
+    }
+
+    /// 服务器是否为处于恢复状态的备库
+    async fn is_standby(client: &Client) -> Result<bool, DbError> {
+        let row = client
+            .query_one("SELECT pg_is_in_recovery()", &[])
+            .await
+            .map_err(|e| DbError::Query(e.to_string()))?;
+        Ok(row.get(0))
+    }
+
     pub async fn get_tables(&self) -> Result<Vec<String>, DbError> {
         let rows = self
             .client
@@ -267,4 +645,59 @@ impl DbClient {
 
         Ok(columns)
     }
+
+    /// 获取列名及其类型名称，自定义类型（如 citext）返回其类型名
+    pub async fn get_column_types(&self, table_name: &str) -> Result<Vec<(String, String)>, DbError> {
+        let query = "
+            SELECT column_name,
+                   CASE WHEN data_type = 'USER-DEFINED' THEN udt_name ELSE data_type END
+            FROM information_schema.columns
+            WHERE table_name = $1
+            ORDER BY ordinal_position
+        ";
+
+        let rows = self
+            .client
+            .query(query, &[&table_name])
+            .await
+            .map_err(|e| DbError::Query(format!("获取列类型失败: {}", e)))?;
+
+        Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+    }
+
+    /// 抽样读取若干列的非空值，统一转为文本，按列顺序返回
+    pub async fn sample_column_values(
+        &self,
+        table_name: &str,
+        columns: &[String],
+        limit: i64,
+    ) -> Result<Vec<Vec<String>>, DbError> {
+        if columns.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let select = columns
+            .iter()
+            .map(|c| format!("\"{}\"::text", c))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let query = format!("SELECT {} FROM \"{}\" LIMIT $1", select, table_name);
+
+        let rows = self
+            .client
+            .query(&query, &[&limit])
+            .await
+            .map_err(|e| DbError::Query(format!("抽样读取表 {} 失败: {}", table_name, e)))?;
+
+        let mut samples = vec![Vec::new(); columns.len()];
+        for row in &rows {
+            for (i, values) in samples.iter_mut().enumerate() {
+                if let Some(value) = row.get::<_, Option<String>>(i) {
+                    values.push(value);
+                }
+            }
+        }
+
+        Ok(samples)
+    }
 }
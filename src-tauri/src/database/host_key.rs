@@ -0,0 +1,143 @@
+// SSH 主机密钥校验
+//
+// 配置中固定了指纹时只接受该指纹；否则按 `~/.ssh/known_hosts` 校验。
+// 指纹格式与 OpenSSH 一致：`SHA256:` 加密钥的 SHA-256 摘要的无填充 base64。
+
+use crate::database::{DbError, SSHConfig};
+use base64::engine::general_purpose::STANDARD_NO_PAD as BASE64;
+use base64::Engine;
+use log::{info, warn};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use ssh2::{CheckResult, HostKeyType, KnownHostFileKind, Session};
+use std::path::PathBuf;
+
+/// 主机密钥的信任来源
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub enum HostKeyStatus {
+    /// 与配置中固定的指纹一致
+    #[serde(rename = "pinned")]
+    Pinned,
+    /// 与 known_hosts 中的记录一致
+    #[serde(rename = "known_hosts")]
+    KnownHosts,
+    /// 首次连接，尚未被信任
+    #[serde(rename = "unknown")]
+    Unknown,
+}
+
+/// SSH 服务器的主机密钥信息
+#[derive(Debug, Serialize, Clone)]
+pub struct HostKeyInfo {
+    pub host: String,
+    pub port: u16,
+    pub key_type: String,
+    pub fingerprint: String,
+    pub status: HostKeyStatus,
+}
+
+/// 握手完成后校验服务器的主机密钥
+///
+/// 指纹不一致时返回 `DbError::HostKeyMismatch`；未被信任的主机只有在
+/// `accept_unknown` 为 true（测试连接时）才允许继续，以便用户确认指纹
+pub fn verify_host_key(
+    session: &Session,
+    ssh_config: &SSHConfig,
+    accept_unknown: bool,
+) -> Result<HostKeyInfo, DbError> {
+    let (key, key_type) = session
+        .host_key()
+        .ok_or_else(|| DbError::SSH("无法获取服务器主机密钥".to_string()))?;
+    let mut info = HostKeyInfo {
+        host: ssh_config.host.clone(),
+        port: ssh_config.port,
+        key_type: key_type_name(key_type).to_string(),
+        fingerprint: fingerprint(key),
+        status: HostKeyStatus::Unknown,
+    };
+
+    let pinned = ssh_config
+        .host_key_fingerprint
+        .as_deref()
+        .filter(|f| !f.trim().is_empty());
+    if let Some(pinned) = pinned {
+        if normalize(pinned) != normalize(&info.fingerprint) {
+            return Err(DbError::HostKeyMismatch(format!(
+                "{}:{} 的主机密钥指纹为 {}，与配置中固定的 {} 不一致，可能存在中间人攻击",
+                info.host, info.port, info.fingerprint, pinned
+            )));
+        }
+        info!("主机密钥与固定指纹一致: {}", info.fingerprint);
+        info.status = HostKeyStatus::Pinned;
+        return Ok(info);
+    }
+
+    match check_known_hosts(session, &info.host, info.port, key) {
+        Some(CheckResult::Match) => {
+            info!("主机密钥与 known_hosts 一致: {}", info.fingerprint);
+            info.status = HostKeyStatus::KnownHosts;
+            Ok(info)
+        }
+        Some(CheckResult::Mismatch) => Err(DbError::HostKeyMismatch(format!(
+            "{}:{} 的主机密钥（{} {}）与 known_hosts 中的记录不一致，可能存在中间人攻击",
+            info.host, info.port, info.key_type, info.fingerprint
+        ))),
+        _ if accept_unknown => {
+            warn!(
+                "主机 {}:{} 未被信任，指纹 {}",
+                info.host, info.port, info.fingerprint
+            );
+            Ok(info)
+        }
+        _ => Err(DbError::SSH(format!(
+            "主机 {}:{} 不在 known_hosts 中且未固定指纹，请通过测试连接确认指纹 {} 后在配置中固定",
+            info.host, info.port, info.fingerprint
+        ))),
+    }
+}
+
+/// 在 known_hosts 中查找主机，文件不存在或无法读取时返回 None
+fn check_known_hosts(session: &Session, host: &str, port: u16, key: &[u8]) -> Option<CheckResult> {
+    let path = known_hosts_path()?;
+    if !path.exists() {
+        return None;
+    }
+
+    let mut known_hosts = session.known_hosts().ok()?;
+    if let Err(e) = known_hosts.read_file(&path, KnownHostFileKind::OpenSSH) {
+        warn!("读取 {:?} 失败: {}", path, e);
+        return None;
+    }
+    Some(known_hosts.check_port(host, port, key))
+}
+
+fn known_hosts_path() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(".ssh").join("known_hosts"))
+}
+
+fn fingerprint(key: &[u8]) -> String {
+    format!("SHA256:{}", BASE64.encode(Sha256::digest(key)))
+}
+
+/// 去掉 `SHA256:` 前缀和 base64 填充后比较
+fn normalize(fingerprint: &str) -> &str {
+    let fingerprint = fingerprint.trim();
+    fingerprint
+        .strip_prefix("SHA256:")
+        .unwrap_or(fingerprint)
+        .trim_end_matches('=')
+}
+
+fn key_type_name(key_type: HostKeyType) -> &'static str {
+    match key_type {
+        HostKeyType::Rsa => "ssh-rsa",
+        HostKeyType::Dss => "ssh-dss",
+        HostKeyType::Ecdsa256 => "ecdsa-sha2-nistp256",
+        HostKeyType::Ecdsa384 => "ecdsa-sha2-nistp384",
+        HostKeyType::Ecdsa521 => "ecdsa-sha2-nistp521",
+        HostKeyType::Ed25519 => "ssh-ed25519",
+        HostKeyType::Unknown => "unknown",
+    }
+}
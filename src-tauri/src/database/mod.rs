@@ -2,16 +2,31 @@
 
 // 子模块
 mod models;
+mod conn_string;
 mod db_client;
+mod host_key;
+mod pg_value;
+mod pgpass;
+mod source_client;
+mod ssh_tunnel;
+mod tls;
 
+pub use conn_string::*;
 pub use db_client::*;
+pub use host_key::*;
 pub use models::*;
+pub use pg_value::*;
+pub use pgpass::*;
+pub use source_client::*;
+pub use ssh_tunnel::*;
+pub use tls::*;
 
 // 数据库连接器
 pub mod connection {
     use super::*;
     
     pub use db_client::DbClient;
+    pub use source_client::SourceClient;
 }
 
 // 数据库模型
@@ -20,11 +35,30 @@ pub mod model {
     
     pub use models::Config;
     pub use models::DatabaseConfig;
+    pub use models::DbHost;
+    pub use models::TargetSessionAttrs;
     pub use models::SSHConfig;
+    pub use models::SslMode;
     pub use models::TableConfig;
     pub use models::MaskRule;
     pub use models::MaskRuleType;
+    pub use models::HashOptions;
+    pub use models::HashEncoding;
+    pub use models::FakeLocale;
+    pub use models::PerturbOptions;
+    pub use models::JsonMaskOptions;
+    pub use models::MaskSuggestion;
+    pub use models::PiiKind;
+    pub use models::MaskPolicy;
+    pub use models::PolicyRule;
+    pub use models::ShuffleOptions;
+    pub use models::TokenOptions;
+    pub use models::FieldMaskRule;
     pub use models::TaskStatus;
     pub use models::TaskState;
     pub use models::Progress;
+    pub use models::MaskAuditReport;
+    pub use models::AuditTable;
+    pub use models::AuditColumn;
+    pub use models::UnmaskedColumn;
 }
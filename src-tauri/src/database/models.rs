@@ -5,22 +5,151 @@ pub struct SSHConfig {
     pub host: String,
     pub port: u16,
     pub username: String,
+    /// 认证方式：password、private_key 或 agent
     pub auth_type: String,
     pub private_key_path: Option<String>,
     pub password: Option<String>,
+    /// 私钥口令
+    #[serde(default)]
+    pub passphrase: Option<String>,
+    /// 内联的私钥内容（PEM/OpenSSH 格式），随配置一起保存，优先于 `private_key_path`
+    #[serde(default)]
+    pub private_key: Option<String>,
+    /// 固定的主机密钥指纹（`SHA256:...`），设置后不再查找 known_hosts
+    #[serde(default)]
+    pub host_key_fingerprint: Option<String>,
+    /// 按顺序经过的跳板机（类似 OpenSSH 的 `ProxyJump`），第一个直接连接，
+    /// 之后每一跳都通过上一跳转发；每一跳使用自己的认证方式和主机密钥设置
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub jump_hosts: Vec<SSHConfig>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DatabaseConfig {
+    /// 主机名或 IP；以 `/` 开头时为 Unix 套接字所在目录，为空时使用默认的套接字目录
     pub host: String,
     pub port: u16,
+    /// 在 `host`/`port` 之后依次尝试的其他主机，用于主备切换或读写分离
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_hosts: Vec<DbHost>,
+    /// 对所连接会话的要求，不满足时尝试下一个主机
+    #[serde(default)]
+    pub target_session_attrs: TargetSessionAttrs,
     pub database: String,
     pub username: String,
     pub password: String,
-    pub ssl_mode: String,
+    #[serde(default)]
+    pub ssl_mode: SslMode,
+    /// 根证书（CA）文件路径，用于校验服务器证书；设置后不再使用系统根证书
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssl_root_cert: Option<String>,
+    /// 客户端证书文件路径（PEM），服务器要求双向认证时与 `ssl_key` 一起使用
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssl_cert: Option<String>,
+    /// 客户端私钥文件路径（PKCS#8 PEM）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssl_key: Option<String>,
     pub ssh_config: Option<SSHConfig>,
 }
 
+impl DatabaseConfig {
+    /// 按尝试顺序排列的所有主机
+    pub fn hosts(&self) -> Vec<DbHost> {
+        std::iter::once(DbHost {
+            host: self.host.clone(),
+            port: self.port,
+        })
+        .chain(self.extra_hosts.iter().cloned())
+        .collect()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DbHost {
+    /// 主机名或 IP；以 `/` 开头时为 Unix 套接字所在目录
+    pub host: String,
+    pub port: u16,
+}
+
+/// 会话要求，与 libpq 的 `target_session_attrs` 含义一致
+#[derive(Default, Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum TargetSessionAttrs {
+    /// 连接第一个可用的主机
+    #[default]
+    #[serde(rename = "any")]
+    Any,
+    /// 只连接可写的主机（主库）
+    #[serde(rename = "read-write")]
+    ReadWrite,
+    /// 优先连接备库，所有备库都不可用时连接其他主机
+    #[serde(rename = "prefer-standby")]
+    PreferStandby,
+}
+
+impl TargetSessionAttrs {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TargetSessionAttrs::Any => "any",
+            TargetSessionAttrs::ReadWrite => "read-write",
+            TargetSessionAttrs::PreferStandby => "prefer-standby",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "any" => Some(TargetSessionAttrs::Any),
+            "read-write" => Some(TargetSessionAttrs::ReadWrite),
+            "prefer-standby" => Some(TargetSessionAttrs::PreferStandby),
+            _ => None,
+        }
+    }
+}
+
+/// SSL 模式，与 libpq 的 `sslmode` 含义一致
+#[derive(Default, Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum SslMode {
+    /// 不使用 SSL
+    #[serde(rename = "disable")]
+    Disable,
+    /// 服务器支持时使用 SSL，不校验证书
+    #[default]
+    #[serde(rename = "prefer")]
+    Prefer,
+    /// 必须使用 SSL，不校验证书；配置了根证书时与 verify-ca 相同
+    #[serde(rename = "require")]
+    Require,
+    /// 必须使用 SSL，并校验证书由受信任的 CA 签发
+    #[serde(rename = "verify-ca")]
+    VerifyCa,
+    /// 在 verify-ca 的基础上校验证书中的主机名
+    #[serde(rename = "verify-full")]
+    VerifyFull,
+}
+
+impl SslMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SslMode::Disable => "disable",
+            SslMode::Prefer => "prefer",
+            SslMode::Require => "require",
+            SslMode::VerifyCa => "verify-ca",
+            SslMode::VerifyFull => "verify-full",
+        }
+    }
+
+    /// 按 libpq 的写法解析，不认识的模式返回 None
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "disable" => Some(SslMode::Disable),
+            "prefer" => Some(SslMode::Prefer),
+            "require" => Some(SslMode::Require),
+            "verify-ca" => Some(SslMode::VerifyCa),
+            "verify-full" => Some(SslMode::VerifyFull),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum TableInfoChangeStatus {
     Added,
@@ -32,6 +161,60 @@ pub struct ColumnConfig {
     pub mask_rule: Option<MaskRule>,
     pub selected: bool,
     pub status: Option<TableInfoChangeStatus>,
+    /// 敏感数据检测给出的建议规则，由界面决定是否采纳
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<MaskSuggestion>,
+    /// 复合类型（或复合类型数组）列按字段配置的脱敏规则，与 `mask_rule` 二选一
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub field_rules: Vec<FieldMaskRule>,
+}
+
+impl ColumnConfig {
+    /// 列是否启用了脱敏：选中且配置了列规则或字段规则
+    pub fn is_masked(&self) -> bool {
+        self.selected && (self.mask_rule.is_some() || !self.field_rules.is_empty())
+    }
+}
+
+/// 复合类型字段的脱敏规则
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FieldMaskRule {
+    /// 字段名，嵌套的复合类型字段用 `.` 分隔，如 `address.city`
+    pub field: String,
+    pub rule: MaskRule,
+}
+
+/// 检测到的敏感数据类别
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum PiiKind {
+    #[serde(rename = "email")]
+    Email,
+    #[serde(rename = "phone")]
+    Phone,
+    #[serde(rename = "id_card")]
+    IdCard,
+    #[serde(rename = "bank_card")]
+    BankCard,
+    #[serde(rename = "name")]
+    Name,
+    #[serde(rename = "address")]
+    Address,
+    #[serde(rename = "credential")]
+    Credential,
+    #[serde(rename = "ip_address")]
+    IpAddress,
+    #[serde(rename = "birth_date")]
+    BirthDate,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MaskSuggestion {
+    pub kind: PiiKind,
+    pub rule: MaskRule,
+    /// 置信度，取值 0 到 1
+    pub confidence: f64,
+    /// 判定依据，如命中的列名关键字和样本匹配比例
+    pub reasons: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -44,13 +227,108 @@ pub struct TableConfig {
     pub status: Option<TableInfoChangeStatus>,
 }
 
-#[derive(Default, Debug, Serialize, Deserialize, Clone)]
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct MaskRule {
     pub rule_type: MaskRuleType,
     pub pattern: Option<String>,
+    /// 哈希类规则的输出格式
+    #[serde(default)]
+    pub hash_options: Option<HashOptions>,
+    /// 脱敏域，同一配置中同域的列对相同输入产生相同输出
+    #[serde(default)]
+    pub domain: Option<String>,
+    /// 假数据规则使用的语言区域
+    #[serde(default)]
+    pub locale: Option<FakeLocale>,
+    /// 日期与数值扰动规则的参数
+    #[serde(default)]
+    pub perturb_options: Option<PerturbOptions>,
+    /// JSON 路径脱敏规则的参数
+    #[serde(default)]
+    pub json_options: Option<JsonMaskOptions>,
+    /// 洗牌规则的参数
+    #[serde(default)]
+    pub shuffle_options: Option<ShuffleOptions>,
+    /// 令牌化规则的参数
+    #[serde(default)]
+    pub token_options: Option<TokenOptions>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TokenOptions {
+    /// 令牌前缀，默认 `tok_`
+    #[serde(default)]
+    pub prefix: Option<String>,
+    /// 前缀之后随机部分的长度，默认 16
+    #[serde(default)]
+    pub length: Option<usize>,
+}
+
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ShuffleOptions {
+    /// 分组列，只在该列取值相同的行之间交换
+    #[serde(default)]
+    pub group_by: Option<String>,
+}
+
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct JsonMaskOptions {
+    /// JSON 路径，如 `$.contact.phone`、`$.items[*].address`
+    pub paths: Vec<String>,
+    /// 对匹配到的值应用的脱敏规则
+    pub rule: Box<MaskRule>,
+}
+
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PerturbOptions {
+    /// 日期平移的最大天数，默认 30
+    #[serde(default)]
+    pub days: Option<u32>,
+    /// 数值噪声的最大偏移百分比，默认 10
+    #[serde(default)]
+    pub percent: Option<f64>,
+    /// 取整步长，默认 1
+    #[serde(default)]
+    pub step: Option<f64>,
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+    /// 用同一行另一列（如主键）的原值决定偏移量，使同一行的多个日期平移相同天数
+    #[serde(default)]
+    pub seed_column: Option<String>,
+}
+
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum FakeLocale {
+    #[default]
+    #[serde(rename = "zh-CN")]
+    ZhCn,
+    #[serde(rename = "en-US")]
+    EnUs,
+}
+
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HashOptions {
+    #[serde(default)]
+    pub encoding: HashEncoding,
+    /// 输出截断长度（字符数），用于适配目标列长度
+    #[serde(default)]
+    pub length: Option<usize>,
+}
+
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum HashEncoding {
+    #[default]
+    #[serde(rename = "hex")]
+    Hex,
+    #[serde(rename = "base64")]
+    Base64,
+    #[serde(rename = "numeric")]
+    Numeric,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum MaskRuleType {
     #[serde(rename = "hash")]
     Hash,
@@ -58,6 +336,54 @@ pub enum MaskRuleType {
     Fixed,
     #[serde(rename = "pattern")]
     Pattern,
+    /// 使用配置密钥的 HMAC-SHA256
+    #[serde(rename = "hmac")]
+    Hmac,
+    /// 保留域名的邮箱，`pattern` 可指定替换域名
+    #[serde(rename = "email")]
+    Email,
+    /// 大陆手机号
+    #[serde(rename = "phone")]
+    Phone,
+    /// 通过 Luhn 校验的银行卡号
+    #[serde(rename = "bank_card")]
+    BankCard,
+    /// 校验位正确的 18 位身份证号
+    #[serde(rename = "id_card")]
+    IdCard,
+    /// 假姓名
+    #[serde(rename = "fake_name")]
+    FakeName,
+    /// 假地址
+    #[serde(rename = "fake_address")]
+    FakeAddress,
+    /// 假公司名称
+    #[serde(rename = "fake_company")]
+    FakeCompany,
+    /// 与原文等长的假文本
+    #[serde(rename = "fake_text")]
+    FakeText,
+    /// 日期随机平移
+    #[serde(rename = "date_shift")]
+    DateShift,
+    /// 数值按比例加噪声
+    #[serde(rename = "noise")]
+    Noise,
+    /// 数值取整分桶
+    #[serde(rename = "round")]
+    Round,
+    /// 数值限制范围
+    #[serde(rename = "clamp")]
+    Clamp,
+    /// 按 JSON 路径对 json/jsonb 中的值应用内层规则
+    #[serde(rename = "json_path")]
+    JsonPath,
+    /// 在表内（或分组内）随机交换各行的值，保留取值分布
+    #[serde(rename = "shuffle")]
+    Shuffle,
+    /// 替换为随机令牌，令牌与原值的映射加密保存在本地令牌库中
+    #[serde(rename = "token")]
+    Token,
 }
 
 impl Default for MaskRuleType {
@@ -72,6 +398,40 @@ pub struct Config {
     pub source_db: DatabaseConfig,
     pub target_db: DatabaseConfig,
     pub tables: Vec<TableConfig>,
+    /// HMAC 脱敏密钥的作用域，多个配置使用同一作用域即共享密钥；为空时使用配置名
+    #[serde(default)]
+    pub mask_secret_scope: Option<String>,
+    /// 引用的脱敏策略名称，按顺序匹配，列上单独配置的规则优先
+    #[serde(default)]
+    pub mask_policies: Vec<String>,
+}
+
+/// 可在多个配置间复用的脱敏策略
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MaskPolicy {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// 按顺序匹配，第一条匹配的规则生效
+    pub rules: Vec<PolicyRule>,
+}
+
+/// 策略中的一条规则：表名和列名都匹配时应用 `rule`
+///
+/// 模式不区分大小写，支持 `*` 匹配任意字符串、`?` 匹配单个字符
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PolicyRule {
+    /// 表名模式，为空时匹配所有表
+    #[serde(default)]
+    pub table_pattern: Option<String>,
+    pub column_pattern: String,
+    pub rule: MaskRule,
+}
+
+impl Config {
+    pub fn mask_secret_scope(&self) -> &str {
+        self.mask_secret_scope.as_deref().unwrap_or(&self.name)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -89,6 +449,50 @@ pub struct TaskStatus {
     pub end_time: Option<String>,
     pub message: Option<String>,
     pub progress: Option<Progress>,
+    /// 任务结束时生成的脱敏审计报告
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audit_report: Option<MaskAuditReport>,
+}
+
+/// 脱敏审计报告，记录一次复制任务中每一列的脱敏方式
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MaskAuditReport {
+    pub task_id: String,
+    pub config_name: String,
+    pub generated_at: String,
+    /// 所有表都复制完成；任务中途失败时为 false，未复制的表行数为空
+    pub completed: bool,
+    pub tables: Vec<AuditTable>,
+    /// 列名看起来包含敏感数据但未脱敏的列
+    pub unmasked_sensitive: Vec<UnmaskedColumn>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AuditTable {
+    pub table: String,
+    pub structure_only: bool,
+    /// 实际复制的行数
+    pub rows: Option<u64>,
+    pub columns: Vec<AuditColumn>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AuditColumn {
+    /// 列名，复合类型的字段规则为 `列.字段`
+    pub column: String,
+    /// 实际生效的脱敏规则，为空表示原样复制
+    pub rule: Option<MaskRule>,
+    /// 规则来源策略，为空表示列上单独配置的规则
+    pub policy: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UnmaskedColumn {
+    pub table: String,
+    pub column: String,
+    pub kind: PiiKind,
+    pub confidence: f64,
+    pub reason: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
@@ -101,4 +505,4 @@ pub enum TaskState {
     Completed,
     #[serde(rename = "failed")]
     Failed,
-} 
\ No newline at end of file
+}
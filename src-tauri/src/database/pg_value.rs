@@ -0,0 +1,317 @@
+use bytes::BytesMut;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use std::error::Error;
+use tokio_postgres::types::{to_sql_checked, FromSql, IsNull, Kind, ToSql, Type};
+
+/// 以二进制线格式原样承载任意 PostgreSQL 类型的列值
+///
+/// COPY BINARY 输出的每个字段就是该类型的 `send` 格式，直接转交给目标库的
+/// `receive` 即可，无需理解具体类型。NULL 由外层的 `Option` 表示。
+#[derive(Debug, Clone)]
+pub struct RawValue {
+    pub ty: Type,
+    pub bytes: Vec<u8>,
+}
+
+impl RawValue {
+    pub fn new(ty: Type, bytes: Vec<u8>) -> Self {
+        Self { ty, bytes }
+    }
+
+    /// 将 Rust 值按列类型编码为线格式
+    pub fn encode<T: ToSql>(ty: &Type, value: T) -> Result<Self, String> {
+        let mut buf = BytesMut::new();
+        value.to_sql(ty, &mut buf).map_err(|e| e.to_string())?;
+        Ok(Self::new(ty.clone(), buf.to_vec()))
+    }
+
+    /// 按列类型解码为 Rust 值
+    pub fn decode<'a, T: FromSql<'a>>(&'a self) -> Result<T, String> {
+        T::from_sql(&self.ty, &self.bytes).map_err(|e| e.to_string())
+    }
+
+    /// 文本类列的值，其线格式即 UTF-8 字节
+    pub fn as_text(&self) -> Result<&str, String> {
+        std::str::from_utf8(&self.bytes).map_err(|e| e.to_string())
+    }
+
+    pub fn from_text(ty: &Type, text: &str) -> Self {
+        Self::new(ty.clone(), text.as_bytes().to_vec())
+    }
+}
+
+impl<'a> FromSql<'a> for RawValue {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(Self::new(ty.clone(), raw.to_vec()))
+    }
+
+    fn accepts(_ty: &Type) -> bool {
+        true
+    }
+}
+
+impl ToSql for RawValue {
+    fn to_sql(
+        &self,
+        _ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        out.extend_from_slice(&self.bytes);
+        Ok(IsNull::No)
+    }
+
+    fn accepts(_ty: &Type) -> bool {
+        true
+    }
+
+    to_sql_checked!();
+}
+
+/// 根据 `pg_attribute.atttypid` 得到列类型
+///
+/// 内置类型直接使用 tokio-postgres 的定义；枚举、复合类型、扩展类型等
+/// 用户自定义类型的 OID 各库不同，这里只保留名称和 OID，按原始字节透传。
+pub fn resolve_type(oid: u32, name: &str) -> Type {
+    Type::from_oid(oid)
+        .unwrap_or_else(|| Type::new(name.to_string(), oid, Kind::Simple, "public".to_string()))
+}
+
+/// 线格式为 UTF-8 文本的类型
+pub fn is_text_type(ty: &Type) -> bool {
+    [
+        Type::TEXT,
+        Type::VARCHAR,
+        Type::BPCHAR,
+        Type::NAME,
+        Type::UNKNOWN,
+    ]
+    .contains(ty)
+        || ty.name() == "citext"
+}
+
+/// 将文本字面量编码为指定列类型的值，用于固定值脱敏等需要写入非文本列的场景
+pub fn encode_literal(ty: &Type, literal: &str) -> Result<RawValue, String> {
+    let literal = literal.trim();
+    let invalid = |e: &dyn std::fmt::Display| format!("无法将 '{}' 转换为 {}: {}", literal, ty, e);
+
+    if is_text_type(ty) {
+        return Ok(RawValue::from_text(ty, literal));
+    }
+
+    match ty {
+        t if *t == Type::INT2 => {
+            RawValue::encode(t, literal.parse::<i16>().map_err(|e| invalid(&e))?)
+        }
+        t if *t == Type::INT4 => {
+            RawValue::encode(t, literal.parse::<i32>().map_err(|e| invalid(&e))?)
+        }
+        t if *t == Type::INT8 => {
+            RawValue::encode(t, literal.parse::<i64>().map_err(|e| invalid(&e))?)
+        }
+        t if *t == Type::FLOAT4 => {
+            RawValue::encode(t, literal.parse::<f32>().map_err(|e| invalid(&e))?)
+        }
+        t if *t == Type::FLOAT8 => {
+            RawValue::encode(t, literal.parse::<f64>().map_err(|e| invalid(&e))?)
+        }
+        t if *t == Type::BOOL => {
+            let value = match literal.to_ascii_lowercase().as_str() {
+                "t" | "true" | "1" | "yes" | "on" => true,
+                "f" | "false" | "0" | "no" | "off" => false,
+                _ => return Err(invalid(&"不是有效的布尔值")),
+            };
+            RawValue::encode(t, value)
+        }
+        t if *t == Type::NUMERIC => Ok(RawValue::new(t.clone(), encode_numeric(literal)?)),
+        t if *t == Type::DATE => RawValue::encode(
+            t,
+            NaiveDate::parse_from_str(literal, "%Y-%m-%d").map_err(|e| invalid(&e))?,
+        ),
+        t if *t == Type::TIMESTAMP => {
+            RawValue::encode(t, parse_timestamp(literal).map_err(|e| invalid(&e))?)
+        }
+        t if *t == Type::TIMESTAMPTZ => {
+            let value = DateTime::parse_from_rfc3339(literal)
+                .map(|v| v.with_timezone(&Utc))
+                .or_else(|_| parse_timestamp(literal).map(|v| v.and_utc()))
+                .map_err(|e| invalid(&e))?;
+            RawValue::encode(t, value)
+        }
+        t if *t == Type::UUID => Ok(RawValue::new(
+            t.clone(),
+            parse_uuid(literal)
+                .ok_or_else(|| invalid(&"不是有效的 UUID"))?
+                .to_vec(),
+        )),
+        t if *t == Type::JSON || *t == Type::JSONB => {
+            let value: serde_json::Value = serde_json::from_str(literal)
+                .unwrap_or_else(|_| serde_json::Value::String(literal.to_string()));
+            RawValue::encode(t, value)
+        }
+        t if *t == Type::BYTEA => Ok(RawValue::new(t.clone(), literal.as_bytes().to_vec())),
+        _ => Err(format!("不支持将字面量写入 {} 类型的列", ty)),
+    }
+}
+
+fn parse_timestamp(literal: &str) -> Result<NaiveDateTime, chrono::ParseError> {
+    NaiveDateTime::parse_from_str(literal, "%Y-%m-%d %H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(literal, "%Y-%m-%dT%H:%M:%S%.f"))
+        .or_else(|e| {
+            NaiveDate::parse_from_str(literal, "%Y-%m-%d")
+                .map(|d| d.and_hms_opt(0, 0, 0).unwrap_or_default())
+                .map_err(|_| e)
+        })
+}
+
+fn parse_uuid(literal: &str) -> Option<[u8; 16]> {
+    let hex: String = literal.chars().filter(|c| *c != '-').collect();
+    if hex.len() != 32 {
+        return None;
+    }
+    let mut bytes = [0u8; 16];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(bytes)
+}
+
+const NUMERIC_POS: u16 = 0x0000;
+const NUMERIC_NEG: u16 = 0x4000;
+const NUMERIC_NAN: u16 = 0xC000;
+
+/// 将 numeric 的二进制格式（以 10000 为基数的数字组）解码为十进制字符串
+pub fn decode_numeric(raw: &[u8]) -> Result<String, String> {
+    let read_u16 = |i: usize| -> Result<u16, String> {
+        raw.get(i..i + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+            .ok_or_else(|| "numeric 数据长度不足".to_string())
+    };
+
+    let ndigits = read_u16(0)? as usize;
+    let weight = read_u16(2)? as i16 as i32;
+    let sign = read_u16(4)?;
+    let dscale = read_u16(6)? as usize;
+    let digits = (0..ndigits)
+        .map(|i| read_u16(8 + i * 2))
+        .collect::<Result<Vec<u16>, String>>()?;
+
+    match sign {
+        NUMERIC_NAN => return Ok("NaN".to_string()),
+        0xD000 => return Ok("Infinity".to_string()),
+        0xF000 => return Ok("-Infinity".to_string()),
+        _ => {}
+    }
+
+    let digit_at = |i: i32| -> u16 {
+        if i < 0 {
+            0
+        } else {
+            digits.get(i as usize).copied().unwrap_or(0)
+        }
+    };
+
+    let mut result = String::new();
+    if sign == NUMERIC_NEG {
+        result.push('-');
+    }
+
+    if weight < 0 {
+        result.push('0');
+    } else {
+        for i in 0..=weight {
+            if i == 0 {
+                result.push_str(&digit_at(i).to_string());
+            } else {
+                result.push_str(&format!("{:04}", digit_at(i)));
+            }
+        }
+    }
+
+    if dscale > 0 {
+        let mut fraction = String::with_capacity(dscale + 4);
+        let mut i = weight + 1;
+        while fraction.len() < dscale {
+            fraction.push_str(&format!("{:04}", digit_at(i)));
+            i += 1;
+        }
+        fraction.truncate(dscale);
+        result.push('.');
+        result.push_str(&fraction);
+    }
+
+    Ok(result)
+}
+
+/// 将十进制字符串编码为 numeric 的二进制格式
+pub fn encode_numeric(literal: &str) -> Result<Vec<u8>, String> {
+    let literal = literal.trim();
+    let invalid = || format!("无法将 '{}' 转换为 numeric", literal);
+
+    let mut out = Vec::new();
+    if literal.eq_ignore_ascii_case("nan") {
+        for v in [0u16, 0, NUMERIC_NAN, 0] {
+            out.extend_from_slice(&v.to_be_bytes());
+        }
+        return Ok(out);
+    }
+
+    let (sign, unsigned) = match literal.strip_prefix('-') {
+        Some(rest) => (NUMERIC_NEG, rest),
+        None => (NUMERIC_POS, literal.strip_prefix('+').unwrap_or(literal)),
+    };
+    let (int_part, frac_part) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    if (int_part.is_empty() && frac_part.is_empty())
+        || !int_part
+            .chars()
+            .chain(frac_part.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+
+    let int_part = int_part.trim_start_matches('0');
+    let dscale = frac_part.len();
+
+    // 整数部分左侧补零、小数部分右侧补零到 4 位一组
+    let int_padded = format!("{}{}", "0".repeat((4 - int_part.len() % 4) % 4), int_part);
+    let frac_padded = format!("{}{}", frac_part, "0".repeat((4 - frac_part.len() % 4) % 4));
+
+    let mut groups: Vec<u16> = int_padded
+        .as_bytes()
+        .chunks(4)
+        .chain(frac_padded.as_bytes().chunks(4))
+        .map(|chunk| {
+            std::str::from_utf8(chunk)
+                .unwrap_or("0")
+                .parse::<u16>()
+                .unwrap_or(0)
+        })
+        .collect();
+    let mut weight = (int_padded.len() / 4) as i32 - 1;
+
+    // 去掉首尾的零组
+    let leading = groups.iter().take_while(|g| **g == 0).count();
+    groups.drain(..leading);
+    weight -= leading as i32;
+    while groups.last() == Some(&0) {
+        groups.pop();
+    }
+
+    let (weight, sign) = if groups.is_empty() {
+        (0, NUMERIC_POS)
+    } else {
+        (weight, sign)
+    };
+    for v in [
+        groups.len() as u16,
+        weight as i16 as u16,
+        sign,
+        dscale as u16,
+    ] {
+        out.extend_from_slice(&v.to_be_bytes());
+    }
+    for group in groups {
+        out.extend_from_slice(&group.to_be_bytes());
+    }
+    Ok(out)
+}
@@ -0,0 +1,135 @@
+// 读取 libpq 的密码文件
+//
+// 文件位置优先取环境变量 `PGPASSFILE`，否则为 `~/.pgpass`
+// （Windows 下为 `%APPDATA%\postgresql\pgpass.conf`）。
+// 每行格式为 `hostname:port:database:username:password`，前四个字段可以写 `*`，
+// 字段中的 `:` 和 `\` 需要用 `\` 转义，`#` 开头的行为注释。
+
+use log::{info, warn};
+use std::fs;
+use std::path::PathBuf;
+
+/// 在密码文件中查找第一条匹配的密码，文件不存在或没有匹配时返回 None
+pub fn lookup_pgpass(host: &str, port: u16, database: &str, username: &str) -> Option<String> {
+    let path = pgpass_path()?;
+    if !path.is_file() {
+        return None;
+    }
+
+    // 与 libpq 一致：组或其他用户可读写的密码文件会被忽略
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&path).ok()?.permissions().mode();
+        if mode & 0o077 != 0 {
+            warn!("密码文件 {:?} 的权限过宽（应为 0600），已忽略", path);
+            return None;
+        }
+    }
+
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) => {
+            warn!("读取密码文件 {:?} 失败: {}", path, e);
+            return None;
+        }
+    };
+
+    let port = port.to_string();
+    let password = find_password(&content, [host, port.as_str(), database, username]);
+    if password.is_some() {
+        info!("使用密码文件 {:?} 中的密码", path);
+    }
+    password
+}
+
+fn pgpass_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("PGPASSFILE") {
+        return Some(PathBuf::from(path));
+    }
+    if cfg!(windows) {
+        std::env::var_os("APPDATA")
+            .map(|dir| PathBuf::from(dir).join("postgresql").join("pgpass.conf"))
+    } else {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".pgpass"))
+    }
+}
+
+/// 返回第一条主机、端口、数据库名、用户名都匹配的记录中的密码
+fn find_password(content: &str, wanted: [&str; 4]) -> Option<String> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(split_fields)
+        .find(|fields| {
+            fields.len() == 5
+                && fields[..4]
+                    .iter()
+                    .zip(wanted)
+                    .all(|(field, value)| field == "*" || field == value)
+        })
+        .map(|mut fields| fields.swap_remove(4))
+}
+
+/// 按未转义的 `:` 拆分一行，最后一个字段（密码）可以包含任意字符
+fn split_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => fields.last_mut().unwrap().extend(chars.next()),
+            ':' if fields.len() < 5 => fields.push(String::new()),
+            _ => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WANTED: [&str; 4] = ["db.example.com", "5432", "sales", "alice"];
+
+    #[test]
+    fn matches_first_entry() {
+        let content = "\
+# 注释行:*:*:*:commented
+
+db.example.com:5432:sales:bob:wrong-user
+db.example.com:5433:sales:alice:wrong-port
+db.example.com:5432:sales:alice:first
+*:*:*:*:fallback
+";
+        assert_eq!(find_password(content, WANTED).as_deref(), Some("first"));
+        assert_eq!(
+            find_password(content, ["other", "5432", "sales", "alice"]).as_deref(),
+            Some("fallback")
+        );
+    }
+
+    #[test]
+    fn matches_wildcards() {
+        let content = "*:5432:*:alice:p1\n";
+        assert_eq!(find_password(content, WANTED).as_deref(), Some("p1"));
+        assert_eq!(find_password(content, ["h", "5433", "d", "alice"]), None);
+    }
+
+    #[test]
+    fn ignores_short_lines() {
+        assert_eq!(
+            find_password("db.example.com:5432:sales:alice\n", WANTED),
+            None
+        );
+        assert_eq!(find_password("*:*:*\n", WANTED), None);
+    }
+
+    #[test]
+    fn unescapes_fields() {
+        let content = "\\/tmp\\:x:5432:sales:alice:a\\\\b\\:c:d\n";
+        assert_eq!(
+            find_password(content, ["/tmp:x", "5432", "sales", "alice"]).as_deref(),
+            Some("a\\b:c:d")
+        );
+    }
+}
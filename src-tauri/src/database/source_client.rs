@@ -0,0 +1,385 @@
+// 只读的源库连接
+//
+// 源库通常是生产库，复制过程中绝不能对其写入。这里分三层保证：
+// - 会话以 `default_transaction_read_only = on` 打开，服务器拒绝任何写操作；
+// - 复制任务的所有读取在同一个 `REPEATABLE READ READ ONLY DEFERRABLE` 事务中完成；
+// - 只提供查询和 `COPY ... TO STDOUT`，每条语句执行前检查第一个关键字，
+//   明显的 DDL/DML 在发送到服务器之前就会被拒绝。
+//
+// 并行复制时，协调会话用 `pg_export_snapshot()` 导出快照，每个工作会话开启事务后
+// 用 `SET TRANSACTION SNAPSHOT` 导入，所有会话看到同一时间点的数据。
+
+use crate::database::{DatabaseConfig, DbClient, DbError};
+use log::info;
+use tokio_postgres::types::{ToSql, Type};
+use tokio_postgres::{CopyOutStream, Row, Statement};
+
+/// 源库只读事务的开启语句
+const BEGIN_READ_ONLY: &str = "BEGIN ISOLATION LEVEL REPEATABLE READ READ ONLY DEFERRABLE";
+
+/// 只读语句允许的第一个关键字
+const READ_ONLY_STATEMENTS: [&str; 6] = ["SELECT", "WITH", "SHOW", "VALUES", "TABLE", "COPY"];
+
+pub struct SourceClient {
+    inner: DbClient,
+}
+
+impl SourceClient {
+    /// 以只读会话连接源库
+    pub async fn connect(config: &DatabaseConfig) -> Result<Self, DbError> {
+        let inner = DbClient::new_read_only(config).await?;
+        Ok(Self { inner })
+    }
+
+    /// 开启只读的可重复读事务，之后的所有读取看到同一时间点的数据
+    ///
+    /// 事务在连接关闭时结束，源库上没有需要提交的修改
+    pub async fn begin(&self) -> Result<(), DbError> {
+        self.inner
+            .client
+            .batch_execute(BEGIN_READ_ONLY)
+            .await
+            .map_err(|e| DbError::Query(format!("开启源库只读事务失败: {}", e)))?;
+        info!("源库只读事务已开启");
+        Ok(())
+    }
+
+    /// 导出当前事务的快照，返回快照 ID，需在 `begin` 之后调用
+    ///
+    /// 快照只在本事务结束前可以被导入，导出快照的会话需要保持到所有工作会话开启事务之后
+    pub async fn export_snapshot(&self) -> Result<String, DbError> {
+        let row = self
+            .inner
+            .client
+            .query_one("SELECT pg_export_snapshot()", &[])
+            .await
+            .map_err(|e| DbError::Query(format!("导出源库快照失败: {}", e)))?;
+        let snapshot: String = row.get(0);
+        info!("源库快照已导出: {}", snapshot);
+        Ok(snapshot)
+    }
+
+    /// 开启只读的可重复读事务并导入 `export_snapshot` 导出的快照，
+    /// 之后的读取与导出快照的会话看到同一时间点的数据
+    pub async fn begin_with_snapshot(&self, snapshot: &str) -> Result<(), DbError> {
+        // 快照 ID 由服务器生成，只包含十六进制数字和 `-`，拼接到语句前先校验
+        if snapshot.is_empty() || !snapshot.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+            return Err(DbError::Query(format!("无效的快照 ID: {}", snapshot)));
+        }
+        self.inner
+            .client
+            .batch_execute(&format!(
+                "{}; SET TRANSACTION SNAPSHOT '{}'",
+                BEGIN_READ_ONLY, snapshot
+            ))
+            .await
+            .map_err(|e| DbError::Query(format!("导入源库快照 {} 失败: {}", snapshot, e)))?;
+        info!("源库只读事务已开启，使用快照 {}", snapshot);
+        Ok(())
+    }
+
+    pub async fn query(
+        &self,
+        sql: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<Row>, DbError> {
+        check_read_only(sql)?;
+        self.inner
+            .client
+            .query(sql, params)
+            .await
+            .map_err(|e| DbError::Query(e.to_string()))
+    }
+
+    pub async fn query_one(
+        &self,
+        sql: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Row, DbError> {
+        check_read_only(sql)?;
+        self.inner
+            .client
+            .query_one(sql, params)
+            .await
+            .map_err(|e| DbError::Query(e.to_string()))
+    }
+
+    /// 执行由 `prepare`/`prepare_typed` 预编译的语句，语句已在预编译时检查过
+    pub async fn query_one_prepared(
+        &self,
+        statement: &Statement,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Row, DbError> {
+        self.inner
+            .client
+            .query_one(statement, params)
+            .await
+            .map_err(|e| DbError::Query(e.to_string()))
+    }
+
+    pub async fn prepare(&self, sql: &str) -> Result<Statement, DbError> {
+        self.prepare_typed(sql, &[]).await
+    }
+
+    pub async fn prepare_typed(&self, sql: &str, types: &[Type]) -> Result<Statement, DbError> {
+        check_read_only(sql)?;
+        self.inner
+            .client
+            .prepare_typed(sql, types)
+            .await
+            .map_err(|e| DbError::Query(e.to_string()))
+    }
+
+    /// 执行 `COPY ... TO STDOUT`
+    pub async fn copy_out(&self, sql: &str) -> Result<CopyOutStream, DbError> {
+        check_read_only(sql)?;
+        self.inner
+            .client
+            .copy_out(sql)
+            .await
+            .map_err(|e| DbError::Query(e.to_string()))
+    }
+
+    pub async fn get_tables(&self) -> Result<Vec<String>, DbError> {
+        self.inner.get_tables().await
+    }
+
+    pub async fn get_table_columns(&self, table_name: &str) -> Result<Vec<String>, DbError> {
+        self.inner.get_table_columns(table_name).await
+    }
+
+    /// 获取列名及其类型名称，见 `DbClient::get_column_types`
+    pub async fn get_column_types(
+        &self,
+        table_name: &str,
+    ) -> Result<Vec<(String, String)>, DbError> {
+        self.inner.get_column_types(table_name).await
+    }
+
+    /// 抽样读取若干列的非空值，见 `DbClient::sample_column_values`
+    pub async fn sample_column_values(
+        &self,
+        table_name: &str,
+        columns: &[String],
+        limit: i64,
+    ) -> Result<Vec<Vec<String>>, DbError> {
+        self.inner
+            .sample_column_values(table_name, columns, limit)
+            .await
+    }
+}
+
+/// 检查语句是否只读，不是时返回 `DbError::ReadOnly`
+///
+/// 会话本身已是只读的，这里只做发送前的快速拒绝：第一个关键字必须是查询类语句，
+/// `COPY` 只能是 `COPY ... TO STDOUT`。语句其余部分不检查关键字，
+/// 列名、别名等标识符不会被误判，真正的写操作由服务器拒绝。
+pub fn check_read_only(sql: &str) -> Result<(), DbError> {
+    let tokens = tokens(sql);
+    let reject = |reason: &str| {
+        Err(DbError::ReadOnly(format!(
+            "{}，拒绝在源库上执行: {}",
+            reason,
+            sql.trim()
+        )))
+    };
+
+    let Some((first, _)) = tokens.first() else {
+        return reject("空语句");
+    };
+    if !READ_ONLY_STATEMENTS.contains(&first.as_str()) {
+        return reject("不是只读语句");
+    }
+    if first == "COPY" {
+        // 只看括号外的部分，`COPY (SELECT ...) TO STDOUT` 中的查询不参与判断
+        let outer: Vec<&str> = tokens
+            .iter()
+            .filter(|(_, depth)| *depth == 0)
+            .map(|(word, _)| word.as_str())
+            .collect();
+        let to_stdout = outer.windows(2).any(|w| w == ["TO", "STDOUT"]);
+        if !to_stdout || outer.contains(&"FROM") {
+            return reject("COPY 只能导出到 STDOUT");
+        }
+    }
+    Ok(())
+}
+
+/// 将语句切分为大写的关键字/标识符及其所在的括号层数
+///
+/// 注释被跳过；字符串、美元引用和引号标识符各记为一个 `'` 或 `"` 占位，
+/// 其中的内容不参与判断。
+fn tokens(sql: &str) -> Vec<(String, usize)> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut tokens = Vec::new();
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '(' => {
+                depth += 1;
+                i += 1;
+            }
+            ')' => {
+                depth = depth.saturating_sub(1);
+                i += 1;
+            }
+            '\'' | '"' => {
+                i = skip_quoted(&chars, i, false);
+                tokens.push((c.to_string(), depth));
+            }
+            '-' if chars.get(i + 1) == Some(&'-') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                i += 2;
+            }
+            '$' => {
+                // `$tag$ ... $tag$` 美元引用，`$1` 等参数占位符按普通字符跳过
+                let tag_end = chars[i + 1..]
+                    .iter()
+                    .position(|c| !(c.is_alphanumeric() || *c == '_'))
+                    .map(|n| i + 1 + n);
+                match tag_end {
+                    Some(end)
+                        if chars[end] == '$'
+                            && !chars.get(i + 1).is_some_and(|c| c.is_ascii_digit()) =>
+                    {
+                        let tag: Vec<char> = chars[i..=end].to_vec();
+                        i = end + 1;
+                        while i < chars.len() && !chars[i..].starts_with(&tag) {
+                            i += 1;
+                        }
+                        i += tag.len();
+                        tokens.push(("'".to_string(), depth));
+                    }
+                    _ => i += 1,
+                }
+            }
+            // `E'...'` 转义字符串中 `\'` 不结束字符串
+            'E' | 'e' if chars.get(i + 1) == Some(&'\'') => {
+                i = skip_quoted(&chars, i + 1, true);
+                tokens.push(("'".to_string(), depth));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '$')
+                {
+                    i += 1;
+                }
+                tokens.push((
+                    chars[start..i].iter().collect::<String>().to_uppercase(),
+                    depth,
+                ));
+            }
+            _ => i += 1,
+        }
+    }
+    tokens
+}
+
+/// 跳过从 `start` 处引号开始的字符串或引号标识符，返回其后的位置
+///
+/// 连续两个引号表示引号本身，`backslash` 为真时 `\` 转义下一个字符
+fn skip_quoted(chars: &[char], start: usize, backslash: bool) -> usize {
+    let quote = chars[start];
+    let mut i = start + 1;
+    while i < chars.len() {
+        if backslash && chars[i] == '\\' {
+            i += 2;
+        } else if chars[i] == quote {
+            if chars.get(i + 1) == Some(&quote) {
+                i += 2;
+            } else {
+                return i + 1;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_read_only;
+
+    fn allowed(sql: &str) -> bool {
+        check_read_only(sql).is_ok()
+    }
+
+    #[test]
+    fn accepts_queries() {
+        assert!(allowed("SELECT * FROM orders"));
+        assert!(allowed("  select 1"));
+        assert!(allowed("(SELECT 1) UNION (SELECT 2)"));
+        assert!(allowed("WITH t AS (SELECT 1) SELECT * FROM t"));
+        assert!(allowed("SHOW server_version"));
+        assert!(allowed("VALUES (1), (2)"));
+        assert!(allowed("TABLE orders"));
+        assert!(allowed("SELECT $1::text"));
+    }
+
+    #[test]
+    fn identifiers_are_not_keywords() {
+        assert!(allowed("SELECT comment, lock, security, do FROM notes"));
+        assert!(allowed("SELECT \"delete\", update_time FROM t"));
+        assert!(allowed(
+            "WITH into AS (SELECT 1 AS insert) SELECT * FROM into"
+        ));
+        assert!(allowed(
+            "SELECT pg_catalog.obj_description(c.oid) AS comment FROM pg_class c"
+        ));
+    }
+
+    #[test]
+    fn skips_strings_and_comments() {
+        assert!(allowed("SELECT 'DROP TABLE t'"));
+        assert!(allowed("SELECT 'it''s'"));
+        assert!(allowed("SELECT E'\\'; DROP TABLE t; \\''"));
+        assert!(allowed("SELECT $$ DELETE FROM t $$, $tag$ x $tag$"));
+        assert!(allowed("-- DELETE FROM t\nSELECT 1"));
+        assert!(allowed("/* INSERT */ SELECT 1"));
+        assert!(!allowed("/* SELECT */ DELETE FROM t"));
+        assert!(!allowed("-- SELECT\nUPDATE t SET a = 1"));
+        assert!(!allowed("'SELECT' DELETE FROM t"));
+    }
+
+    #[test]
+    fn rejects_writes() {
+        assert!(!allowed(""));
+        assert!(!allowed("  -- only a comment"));
+        assert!(!allowed("INSERT INTO t VALUES (1)"));
+        assert!(!allowed("update t set a = 1"));
+        assert!(!allowed("DELETE FROM t"));
+        assert!(!allowed("TRUNCATE t"));
+        assert!(!allowed("CREATE TABLE t (a int)"));
+        assert!(!allowed("DROP TABLE t"));
+        assert!(!allowed("LOCK TABLE t"));
+        assert!(!allowed("DO $$ BEGIN END $$"));
+        assert!(!allowed("CALL p()"));
+        assert!(!allowed("SET default_transaction_read_only = off"));
+    }
+
+    #[test]
+    fn copy_only_to_stdout() {
+        assert!(allowed("COPY \"orders\" TO STDOUT WITH (FORMAT binary)"));
+        assert!(allowed("COPY t (a, b) TO STDOUT"));
+        assert!(allowed(
+            "copy (SELECT * FROM t WHERE a IN (SELECT a FROM s)) to stdout"
+        ));
+        assert!(!allowed("COPY t FROM STDIN"));
+        assert!(!allowed("COPY t TO '/tmp/t.csv'"));
+        assert!(!allowed("COPY t TO PROGRAM 'cat > /tmp/t'"));
+        assert!(!allowed("COPY t FROM '/tmp/stdout'"));
+        assert!(!allowed("COPY (SELECT 'TO STDOUT') TO '/tmp/t'"));
+    }
+}
@@ -0,0 +1,338 @@
+// SSH 本地端口转发
+//
+// 为每个转发目标在 127.0.0.1 的随机端口上监听，每个接入的连接通过 SSH 会话
+// 打开一个 direct-tcpip 通道连到对应的目标地址，数据在两端之间双向转发。
+//
+// libssh2 的会话不能被多个线程同时阻塞读写，因此所有连接都在同一个后台线程中
+// 以非阻塞方式轮询转发。隧道被丢弃时只通知该线程退出而不等待其结束，
+// `DbClient` 常在异步任务中被丢弃，等待会占住 tokio 的工作线程；
+// 断开会话等收尾工作由转发线程自己完成。
+//
+// 经过多级跳板机时，后一跳的 SSH 会话建立在前一跳隧道的本地端口上，
+// 前一跳隧道作为 `upstream` 由后一跳的转发线程持有，在后一跳的会话断开之后才关闭。
+
+use crate::database::DbError;
+use log::{error, info, warn};
+use ssh2::{Channel, Session};
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 单次读取的缓冲区大小
+const BUFFER_SIZE: usize = 32 * 1024;
+
+/// 没有任何数据可转发时的等待间隔
+const IDLE_INTERVAL: Duration = Duration::from_millis(2);
+
+/// 打开 SSH 通道的超时时间
+const OPEN_CHANNEL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// SSH 保活间隔（秒），避免长时间复制时连接被中间设备断开
+const KEEPALIVE_INTERVAL: u32 = 30;
+
+pub struct SshTunnel {
+    /// 与转发目标一一对应的本地端口
+    local_ports: Vec<u16>,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl SshTunnel {
+    /// 在已认证的会话上启动到各个 `(host, port)` 目标的本地端口转发
+    ///
+    /// `upstream` 为会话所经过的上一跳隧道，直接连接时为 None
+    pub fn start(
+        session: Session,
+        targets: &[(&str, u16)],
+        upstream: Option<SshTunnel>,
+    ) -> Result<Self, DbError> {
+        let mut local_ports = Vec::with_capacity(targets.len());
+        let mut forward_targets = Vec::with_capacity(targets.len());
+        for &(host, port) in targets {
+            let listener = TcpListener::bind("127.0.0.1:0")
+                .map_err(|e| DbError::SSH(format!("绑定本地转发端口失败: {}", e)))?;
+            let local_port = listener
+                .local_addr()
+                .map_err(|e| DbError::SSH(e.to_string()))?
+                .port();
+            listener
+                .set_nonblocking(true)
+                .map_err(|e| DbError::SSH(e.to_string()))?;
+            local_ports.push(local_port);
+            forward_targets.push(Target {
+                listener,
+                host: host.to_string(),
+                port,
+            });
+        }
+        let first_port = *local_ports
+            .first()
+            .ok_or_else(|| DbError::SSH("没有需要转发的目标".to_string()))?;
+
+        session.set_keepalive(false, KEEPALIVE_INTERVAL);
+
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let forwarder = Forwarder {
+            session,
+            targets: forward_targets,
+            shutdown: shutdown.clone(),
+            connections: Vec::new(),
+            local_ports: local_ports.clone(),
+            upstream,
+        };
+        // 不保留线程句柄，隧道丢弃时线程自行退出
+        thread::Builder::new()
+            .name(format!("ssh-tunnel-{}", first_port))
+            .spawn(move || forwarder.run())
+            .map_err(|e| DbError::SSH(format!("启动 SSH 转发线程失败: {}", e)))?;
+
+        for (&(host, port), local_port) in targets.iter().zip(&local_ports) {
+            info!(
+                "SSH 端口转发已启动: 127.0.0.1:{} -> {}:{}",
+                local_port, host, port
+            );
+        }
+        Ok(Self {
+            local_ports,
+            shutdown,
+        })
+    }
+
+    /// 第一个转发目标的本地端口
+    pub fn local_port(&self) -> u16 {
+        self.local_ports[0]
+    }
+
+    /// 与 `start` 传入的目标顺序一致的本地端口
+    pub fn local_ports(&self) -> &[u16] {
+        &self.local_ports
+    }
+}
+
+impl Drop for SshTunnel {
+    fn drop(&mut self) {
+        // 转发线程最多在一次轮询或打开通道的超时之后退出，这里不等待
+        self.shutdown.store(true, Ordering::SeqCst);
+    }
+}
+
+/// 一个本地连接与对应 SSH 通道之间的转发状态
+struct Connection {
+    stream: TcpStream,
+    channel: Channel,
+    /// 已从本地读取、尚未写入通道的数据
+    to_remote: Vec<u8>,
+    /// 已从通道读取、尚未写入本地的数据
+    to_local: Vec<u8>,
+    local_eof: bool,
+    remote_eof: bool,
+    eof_sent: bool,
+}
+
+impl Connection {
+    /// 转发一轮数据，返回是否有进展
+    fn pump(&mut self, buffer: &mut [u8]) -> io::Result<bool> {
+        let mut progressed = false;
+
+        // 本地 -> 远端
+        if self.to_remote.is_empty() && !self.local_eof {
+            match self.stream.read(buffer) {
+                Ok(0) => {
+                    self.local_eof = true;
+                    progressed = true;
+                }
+                Ok(n) => {
+                    self.to_remote.extend_from_slice(&buffer[..n]);
+                    progressed = true;
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e),
+            }
+        }
+        if !self.to_remote.is_empty() {
+            match self.channel.write(&self.to_remote) {
+                Ok(n) => {
+                    self.to_remote.drain(..n);
+                    progressed = true;
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e),
+            }
+        }
+        if self.local_eof && self.to_remote.is_empty() && !self.eof_sent {
+            match self.channel.send_eof() {
+                Ok(()) => {
+                    self.eof_sent = true;
+                    progressed = true;
+                }
+                Err(e) => {
+                    let e = io::Error::from(e);
+                    if e.kind() != ErrorKind::WouldBlock {
+                        return Err(e);
+                    }
+                }
+            }
+        }
+
+        // 远端 -> 本地
+        if self.to_local.is_empty() && !self.remote_eof {
+            match self.channel.read(buffer) {
+                Ok(0) => {
+                    if self.channel.eof() {
+                        self.remote_eof = true;
+                        let _ = self.stream.shutdown(Shutdown::Write);
+                        progressed = true;
+                    }
+                }
+                Ok(n) => {
+                    self.to_local.extend_from_slice(&buffer[..n]);
+                    progressed = true;
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e),
+            }
+        }
+        if !self.to_local.is_empty() {
+            match self.stream.write(&self.to_local) {
+                Ok(n) => {
+                    self.to_local.drain(..n);
+                    progressed = true;
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(progressed)
+    }
+
+    fn finished(&self) -> bool {
+        self.eof_sent && self.remote_eof && self.to_local.is_empty()
+    }
+}
+
+/// 一个转发目标及其本地监听端口
+struct Target {
+    listener: TcpListener,
+    host: String,
+    port: u16,
+}
+
+struct Forwarder {
+    session: Session,
+    targets: Vec<Target>,
+    shutdown: Arc<AtomicBool>,
+    connections: Vec<Connection>,
+    local_ports: Vec<u16>,
+    /// 承载本隧道 SSH 会话的上一跳隧道
+    upstream: Option<SshTunnel>,
+}
+
+impl Forwarder {
+    fn run(mut self) {
+        self.session.set_blocking(false);
+        let mut buffer = vec![0u8; BUFFER_SIZE];
+
+        while !self.shutdown.load(Ordering::SeqCst) {
+            let mut progressed = false;
+            for i in 0..self.targets.len() {
+                progressed |= self.accept(i);
+            }
+
+            let mut i = 0;
+            while i < self.connections.len() {
+                let connection = &mut self.connections[i];
+                match connection.pump(&mut buffer) {
+                    Ok(pumped) if !connection.finished() => {
+                        progressed |= pumped;
+                        i += 1;
+                    }
+                    Ok(_) => {
+                        self.connections.swap_remove(i);
+                    }
+                    Err(e) => {
+                        warn!("SSH 转发连接中断: {}", e);
+                        self.connections.swap_remove(i);
+                    }
+                }
+            }
+
+            let _ = self.session.keepalive_send();
+            if !progressed {
+                thread::sleep(IDLE_INTERVAL);
+            }
+        }
+
+        for connection in &mut self.connections {
+            let _ = connection.stream.shutdown(Shutdown::Both);
+        }
+        self.connections.clear();
+        self.session.set_blocking(true);
+        let _ = self.session.disconnect(None, "tunnel closed", None);
+        info!("SSH 端口转发已关闭: 127.0.0.1:{:?}", self.local_ports);
+        // 本跳的会话断开后再关闭上一跳
+        drop(self.upstream.take());
+    }
+
+    /// 接受第 `index` 个目标的新本地连接并为其打开 SSH 通道，返回是否接受了连接
+    fn accept(&mut self, index: usize) -> bool {
+        let target = &self.targets[index];
+        let stream = match target.listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => return false,
+            Err(e) => {
+                error!("接受本地转发连接失败: {}", e);
+                return false;
+            }
+        };
+
+        match self.open_channel(&target.host, target.port) {
+            Ok(channel) => {
+                if let Err(e) = stream.set_nonblocking(true) {
+                    error!("设置本地转发连接失败: {}", e);
+                    return true;
+                }
+                let _ = stream.set_nodelay(true);
+                self.connections.push(Connection {
+                    stream,
+                    channel,
+                    to_remote: Vec::new(),
+                    to_local: Vec::new(),
+                    local_eof: false,
+                    remote_eof: false,
+                    eof_sent: false,
+                });
+            }
+            Err(e) => {
+                error!(
+                    "打开到 {}:{} 的 SSH 通道失败: {}",
+                    target.host, target.port, e
+                );
+                let _ = stream.shutdown(Shutdown::Both);
+            }
+        }
+        true
+    }
+
+    /// 非阻塞模式下打开通道需要反复重试直到完成
+    fn open_channel(&self, host: &str, port: u16) -> io::Result<Channel> {
+        let deadline = Instant::now() + OPEN_CHANNEL_TIMEOUT;
+        loop {
+            match self.session.channel_direct_tcpip(host, port, None) {
+                Ok(channel) => return Ok(channel),
+                Err(e) => {
+                    let e = io::Error::from(e);
+                    if e.kind() != ErrorKind::WouldBlock {
+                        return Err(e);
+                    }
+                    if Instant::now() >= deadline || self.shutdown.load(Ordering::SeqCst) {
+                        return Err(io::Error::new(ErrorKind::TimedOut, "打开通道超时"));
+                    }
+                    thread::sleep(IDLE_INTERVAL);
+                }
+            }
+        }
+    }
+}
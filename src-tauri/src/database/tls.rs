@@ -0,0 +1,113 @@
+// 数据库连接的 TLS 设置
+//
+// 按 libpq 的 sslmode 语义配置 native-tls：prefer/require 只加密不校验证书，
+// verify-ca 校验证书链，verify-full 另外校验证书中的主机名。
+// 配置了根证书时只信任该文件中的 CA，配置了客户端证书和私钥时用于双向认证。
+
+use crate::database::{DatabaseConfig, DbError, SslMode};
+use log::info;
+use native_tls::{Certificate, Identity, TlsConnector};
+use postgres_native_tls::MakeTlsConnector;
+use std::fs;
+
+const PEM_CERT_END: &str = "-----END CERTIFICATE-----";
+
+/// 对应到 tokio-postgres 的 SSL 模式，校验证书由 TLS 连接器负责
+pub fn pg_ssl_mode(mode: SslMode) -> tokio_postgres::config::SslMode {
+    match mode {
+        SslMode::Disable => tokio_postgres::config::SslMode::Disable,
+        SslMode::Prefer => tokio_postgres::config::SslMode::Prefer,
+        SslMode::Require | SslMode::VerifyCa | SslMode::VerifyFull => {
+            tokio_postgres::config::SslMode::Require
+        }
+    }
+}
+
+/// 根据数据库配置创建 TLS 连接器
+pub fn make_tls_connector(config: &DatabaseConfig) -> Result<MakeTlsConnector, DbError> {
+    let root_cert = non_empty(&config.ssl_root_cert);
+    // 与 libpq 一致：require 模式下配置了根证书时按 verify-ca 校验
+    let mode = match (config.ssl_mode, root_cert) {
+        (SslMode::Require, Some(_)) => SslMode::VerifyCa,
+        (mode, _) => mode,
+    };
+
+    let mut builder = TlsConnector::builder();
+    match mode {
+        SslMode::Disable | SslMode::Prefer | SslMode::Require => {
+            info!("不校验服务器证书");
+            builder.danger_accept_invalid_certs(true);
+        }
+        SslMode::VerifyCa => {
+            info!("校验服务器证书链，不校验主机名");
+            builder.danger_accept_invalid_hostnames(true);
+        }
+        SslMode::VerifyFull => {
+            info!("校验服务器证书链和主机名");
+        }
+    }
+
+    if let Some(path) = root_cert {
+        let certs = read_certificates(path)?;
+        info!("使用根证书 {}（{} 个）", path, certs.len());
+        builder.disable_built_in_roots(true);
+        for cert in certs {
+            builder.add_root_certificate(cert);
+        }
+    }
+
+    match (non_empty(&config.ssl_cert), non_empty(&config.ssl_key)) {
+        (Some(cert_path), Some(key_path)) => {
+            info!("使用客户端证书 {}", cert_path);
+            let cert = read_file(cert_path, "客户端证书")?;
+            let key = read_file(key_path, "客户端私钥")?;
+            let identity = Identity::from_pkcs8(&cert, &key).map_err(|e| {
+                DbError::Connection(format!(
+                    "加载客户端证书失败（私钥需为 PKCS#8 PEM 格式）: {}",
+                    e
+                ))
+            })?;
+            builder.identity(identity);
+        }
+        (None, None) => {}
+        _ => {
+            return Err(DbError::Connection(
+                "客户端证书和客户端私钥需要同时配置".to_string(),
+            ))
+        }
+    }
+
+    let connector = builder
+        .build()
+        .map_err(|e| DbError::Connection(format!("创建 TLS 连接器失败: {}", e)))?;
+    Ok(MakeTlsConnector::new(connector))
+}
+
+/// 读取 PEM 文件中的所有证书，支持包含多个 CA 的证书包
+fn read_certificates(path: &str) -> Result<Vec<Certificate>, DbError> {
+    let pem = read_file(path, "根证书")?;
+    let pem = String::from_utf8_lossy(&pem);
+    let certs = pem
+        .split_inclusive(PEM_CERT_END)
+        .filter(|block| block.contains(PEM_CERT_END))
+        .map(|block| {
+            Certificate::from_pem(block.trim().as_bytes())
+                .map_err(|e| DbError::Connection(format!("解析根证书 {} 失败: {}", path, e)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if certs.is_empty() {
+        return Err(DbError::Connection(format!(
+            "根证书 {} 中没有 PEM 格式的证书",
+            path
+        )));
+    }
+    Ok(certs)
+}
+
+fn read_file(path: &str, what: &str) -> Result<Vec<u8>, DbError> {
+    fs::read(path).map_err(|e| DbError::Connection(format!("读取{} {} 失败: {}", what, path, e)))
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
@@ -60,14 +60,30 @@ fn main() {
             commands::delete_config,
             commands::import_config,
             commands::merge_table_config,
+            commands::preview_mask,
+            commands::set_mask_secret,
+            commands::get_config_summary,
+
+            // 脱敏策略相关命令
+            commands::save_mask_policy,
+            commands::list_mask_policies,
+            commands::delete_mask_policy,
+            commands::test_mask_pattern,
+
+            // 令牌库相关命令
+            commands::lookup_tokens,
             // commands::export_config, // 暂时注释掉未实现的命令
             
             // 连接相关命令
             commands::test_connection,
+            commands::parse_connection_string,
+            commands::format_connection_string,
             
             // 任务相关命令
             commands::start_copy,
             commands::get_task_status,
+            commands::get_task_report,
+            commands::export_task_report,
             // commands::stop_task, // 暂时注释掉未实现的命令
             
         ])
@@ -1,12 +1,18 @@
 // 命令服务模块
 
 // 导出所有命令函数
-pub use crate::services::commands::connection::test_connection;
+pub use crate::services::commands::connection::{
+    test_connection,
+    parse_connection_string,
+    format_connection_string,
+};
 
 pub use crate::services::commands::task::{
     start_copy,
     get_task_status,
     get_all_tasks,
+    get_task_report,
+    export_task_report,
 };
 
 pub use crate::services::commands::config::{
@@ -17,9 +23,26 @@ pub use crate::services::commands::config::{
     import_config,
     get_config_summary,
     merge_table_config,
+    preview_mask,
+    set_mask_secret,
+};
+
+pub use crate::services::commands::policy::{
+    save_mask_policy,
+    list_mask_policies,
+    delete_mask_policy,
+    test_mask_pattern,
+};
+
+pub use crate::services::commands::token::{
+    lookup_tokens,
+    TokenLookup,
 };
 
 pub use crate::services::commands::types::{
     TaskStore,
     ConfigSummary,
+    ConnectionTestResult,
+    MaskPreview,
+    ReportFormat,
 }; 
\ No newline at end of file
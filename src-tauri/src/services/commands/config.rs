@@ -1,14 +1,29 @@
 // 配置相关命令
 
-use crate::database::{ColumnConfig, Config, DatabaseConfig, TableInfoChangeStatus};
-use crate::db::{DbClient, TableConfig};
+use crate::database::{ColumnConfig, Config, DatabaseConfig, SourceClient, TableInfoChangeStatus};
+use crate::db::TableConfig;
 use crate::services::Storage;
-use crate::services::commands::types::ConfigSummary;
+use crate::services::commands::policy::resolve_mask_policies;
+use crate::services::commands::types::{
+    ColumnPreview, ConfigSummary, MaskPreview, PreviewFlag, ValuePreview,
+};
+use crate::services::db_copier::{resolve_column_types, resolve_mask_domains, TableInfo};
+use crate::services::masking::{detect_pii, ColumnMasker, ColumnShuffler, MaskContext};
+use crate::database::{MaskRule, MaskRuleType, RawValue};
 use std::sync::Arc;
 use tauri::State;
-use log::{info, error, debug};
+use log::{info, error, debug, warn};
 use std::fs;
 use std::collections::{HashMap, HashSet};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+
+/// 敏感数据检测时每个表抽样的行数
+const PII_SAMPLE_ROWS: i64 = 100;
+
+/// 脱敏预览默认和最多读取的行数
+const DEFAULT_PREVIEW_ROWS: i64 = 20;
+const MAX_PREVIEW_ROWS: i64 = 1000;
 
 /// 保存配置
 #[tauri::command]
@@ -89,9 +104,34 @@ pub async fn import_config(
     }
 }
 
+/// 设置脱敏密钥，未提供密钥时重新生成随机密钥
+///
+/// 密钥以 base64 传入；更换密钥后 HMAC 脱敏结果会随之改变
+#[tauri::command]
+pub async fn set_mask_secret(
+    scope: String,
+    secret: Option<String>,
+    storage: State<'_, Arc<Storage>>,
+) -> Result<(), String> {
+    let secret = match secret {
+        Some(secret) => Some(
+            BASE64.decode(secret.trim())
+                .map_err(|e| format!("密钥不是有效的 base64: {}", e))?
+        ),
+        None => None,
+    };
+
+    storage.set_mask_secret(&scope, secret.as_deref())
+        .await
+        .map_err(|e| {
+            error!("设置脱敏密钥失败: {}", e);
+            format!("设置脱敏密钥失败: {}", e)
+        })
+}
+
 /// 合并列配置
 async fn merge_columns(
-    client: &DbClient,
+    client: &SourceClient,
     table_name: &str,
     existing_columns: &[ColumnConfig],
 ) -> Result<Vec<ColumnConfig>, String> {
@@ -129,9 +169,13 @@ async fn merge_columns(
                 mask_rule: None,
                 selected: false,
                 status: Some(TableInfoChangeStatus::Added),
+                suggestion: None,
+                field_rules: Vec::new(),
             });
         }
     }
+
+    suggest_mask_rules(client, table_name, &mut merged_columns).await?;
     
     // 处理已删除的列
     for column in existing_columns {
@@ -146,6 +190,55 @@ async fn merge_columns(
     Ok(merged_columns)
 }
 
+/// 为新增列检测敏感数据并填充建议规则
+///
+/// 抽样失败时只记录警告，仍按列名给出建议，不影响配置合并。
+async fn suggest_mask_rules(
+    client: &SourceClient,
+    table_name: &str,
+    columns: &mut [ColumnConfig],
+) -> Result<(), String> {
+    let added: Vec<String> = columns
+        .iter()
+        .filter(|c| matches!(c.status, Some(TableInfoChangeStatus::Added)) && c.mask_rule.is_none())
+        .map(|c| c.name.clone())
+        .collect();
+    if added.is_empty() {
+        return Ok(());
+    }
+
+    let data_types: HashMap<String, String> = client.get_column_types(table_name)
+        .await
+        .map_err(|e| {
+            error!("Failed to get column types for table {}: {}", table_name, e);
+            e.to_string()
+        })?
+        .into_iter()
+        .collect();
+
+    let samples = client.sample_column_values(table_name, &added, PII_SAMPLE_ROWS)
+        .await
+        .unwrap_or_else(|e| {
+            warn!("抽样读取表 {} 失败，仅按列名检测敏感数据: {}", table_name, e);
+            vec![Vec::new(); added.len()]
+        });
+    let samples: HashMap<_, _> = added.iter().zip(samples).collect();
+
+    for column in columns.iter_mut() {
+        let Some(values) = samples.get(&column.name) else {
+            continue;
+        };
+        let data_type = data_types.get(&column.name).map(String::as_str).unwrap_or("");
+        column.suggestion = detect_pii(&column.name, data_type, values);
+        if let Some(suggestion) = &column.suggestion {
+            debug!("Suggested mask rule for {}.{}: {:?} ({:.2})",
+                table_name, column.name, suggestion.rule.rule_type, suggestion.confidence);
+        }
+    }
+
+    Ok(())
+}
+
 /// 合并表配置
 #[tauri::command]
 pub async fn merge_table_config(
@@ -154,8 +247,8 @@ pub async fn merge_table_config(
 ) -> Result<Vec<TableConfig>, String> {
     info!("Starting table configuration merge");
     
-    // 创建数据库客户端
-    let client = DbClient::new(&database_config)
+    // 以只读会话连接源库，抽样检测敏感数据时也不会写入
+    let client = SourceClient::connect(&database_config)
         .await
         .map_err(|e| {
             error!("Failed to create database client: {}", e);
@@ -221,10 +314,221 @@ pub async fn merge_table_config(
     Ok(merged_tables)
 }
 
+/// 预览表的脱敏效果
+///
+/// 从源库读取若干行，按配置中选中列的脱敏规则逐值脱敏，返回脱敏前后的对比。
+/// 脱敏域和密钥与实际复制时相同。原值和脱敏结果都由源库按列类型转换为文本，
+/// 长度也按源表的列定义检查；目标表按源表结构创建，两者的列定义一致。
+#[tauri::command]
+pub async fn preview_mask(
+    mut config: Config,
+    table_name: String,
+    rows: Option<i64>,
+    storage: State<'_, Arc<Storage>>,
+) -> Result<MaskPreview, String> {
+    let limit = rows.unwrap_or(DEFAULT_PREVIEW_ROWS).clamp(1, MAX_PREVIEW_ROWS);
+    info!("预览表 {} 的脱敏效果, 抽样 {} 行", table_name, limit);
+
+    resolve_mask_policies(&mut config, &storage).await?;
+
+    let table = config.tables.iter()
+        .find(|t| t.name == table_name)
+        .ok_or_else(|| format!("配置中不存在表 {}", table_name))?;
+
+    let client = SourceClient::connect(&config.source_db)
+        .await
+        .map_err(|e| {
+            error!("Failed to create database client: {}", e);
+            e.to_string()
+        })?;
+
+    let secret = storage.get_or_create_mask_secret(config.mask_secret_scope())
+        .await
+        .map_err(|e| format!("读取脱敏密钥失败: {}", e))?;
+    let domains = resolve_mask_domains(&client, &config.tables)
+        .await
+        .map_err(|e| format!("脱敏配置无效: {}", e))?;
+    let context = MaskContext::new().with_secret(secret).with_domains(domains);
+
+    let table_info = TableInfo::load(&client, &table.name)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // 读取配置中的所有列，种子列可能不是脱敏列
+    let mut types = Vec::with_capacity(table.columns.len());
+    for column in &table.columns {
+        types.push(table_info.column_type(&column.name).map_err(|e| e.to_string())?);
+    }
+    let select = table.columns.iter()
+        .map(|c| format!("\"{}\"", c.name))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let sample_rows = client
+        .query(&format!("SELECT {} FROM \"{}\" LIMIT $1", select, table.name), &[&limit])
+        .await
+        .map_err(|e| format!("读取表 {} 失败: {}", table.name, e.message()))?;
+    let mut samples = Vec::with_capacity(sample_rows.len());
+    for row in &sample_rows {
+        let mut values = Vec::with_capacity(types.len());
+        for i in 0..types.len() {
+            values.push(row.try_get::<_, Option<RawValue>>(i).map_err(|e| e.to_string())?);
+        }
+        samples.push(values);
+    }
+
+    // 数组和复合类型的脱敏需要元素类型和字段定义
+    let masked_columns: Vec<&str> = table.columns.iter()
+        .filter(|c| c.is_masked())
+        .map(|c| c.name.as_str())
+        .collect();
+    let masked_types: HashMap<&str, _> = masked_columns.iter()
+        .copied()
+        .zip(resolve_column_types(&client, &table.name, &masked_columns)
+            .await
+            .map_err(|e| e.to_string())?)
+        .collect();
+
+    let mut columns = Vec::new();
+    for (index, column) in table.columns.iter().enumerate() {
+        let masked_type = match masked_types.get(column.name.as_str()) {
+            Some(masked_type) => masked_type,
+            None => continue,
+        };
+        let mut preview = ColumnPreview {
+            column: column.name.clone(),
+            rule: column.mask_rule.clone(),
+            field_rules: column.field_rules.clone(),
+            source_max_length: table_info.column_max_length(&column.name),
+            error: None,
+            not_previewable: None,
+            values: Vec::new(),
+        };
+
+        // 预览不解锁令牌库，也不能为抽样的值生成新令牌
+        if uses_token_rule(column) {
+            preview.not_previewable = Some("令牌化规则需要令牌库，不支持预览".to_string());
+            columns.push(preview);
+            continue;
+        }
+
+        let masker = match ColumnMasker::for_column(column, masked_type, &context) {
+            Ok(Some(masker)) => masker,
+            Ok(None) => continue,
+            Err(e) => {
+                preview.error = Some(e.to_string());
+                columns.push(preview);
+                continue;
+            }
+        };
+        // 种子列和洗牌的分组列都必须在读取的列中
+        let reference = match masker.seed_column().or(masker.group_column()) {
+            Some(name) => match table.columns.iter().position(|c| c.name == name) {
+                Some(reference) => Some(reference),
+                None => {
+                    preview.error = Some(format!("列 {} 不在复制的列中", name));
+                    columns.push(preview);
+                    continue;
+                }
+            },
+            None => None,
+        };
+
+        // 洗牌只在抽样的行之间进行
+        let mut shuffler = None;
+        if masker.is_shuffle() {
+            let mut rows = ColumnShuffler::new(&column.name, &types[index], usize::MAX);
+            for values in &samples {
+                let group = reference.and_then(|g| values[g].as_ref());
+                rows.push(group, values[index].clone()).map_err(|e| e.to_string())?;
+            }
+            shuffler = Some(rows);
+        }
+
+        // 由源库把原值和脱敏结果转换为文本，脱敏结果的线格式不合法时会在这里报错
+        let render = client
+            .prepare_typed("SELECT $1::text", &[types[index].clone()])
+            .await
+            .map_err(|e| format!("准备预览查询失败: {}", e.message()))?;
+
+        for values in &samples {
+            let original = &values[index];
+            let mut value_preview = ValuePreview {
+                original: None,
+                masked: None,
+                flags: Vec::new(),
+                error: None,
+            };
+
+            value_preview.original = match client.query_one_prepared(&render, &[original]).await {
+                Ok(row) => row.get(0),
+                Err(e) => return Err(format!("读取列 {} 的原值失败: {}", column.name, e.message())),
+            };
+
+            let reference_value = reference.and_then(|r| values[r].as_ref());
+            let masked = match &mut shuffler {
+                Some(shuffler) => shuffler.next(reference_value),
+                None => masker.mask_with_seed(original.clone(), reference_value),
+            };
+            let masked = match masked {
+                Ok(masked) => masked,
+                Err(e) => {
+                    value_preview.flags.push(PreviewFlag::Error);
+                    value_preview.error = Some(e.to_string());
+                    preview.values.push(value_preview);
+                    continue;
+                }
+            };
+
+            match client.query_one_prepared(&render, &[&masked]).await {
+                Ok(row) => value_preview.masked = row.get(0),
+                Err(e) => {
+                    value_preview.flags.push(PreviewFlag::Error);
+                    value_preview.error = Some(format!("源库无法解析脱敏结果: {}", e.message()));
+                }
+            }
+
+            if let (Some(max_length), Some(masked)) = (preview.source_max_length, &value_preview.masked) {
+                if masked.chars().count() > max_length {
+                    value_preview.flags.push(PreviewFlag::SourceLengthOverflow);
+                }
+            }
+            if value_preview.original.is_some() && value_preview.masked == value_preview.original {
+                value_preview.flags.push(PreviewFlag::Unchanged);
+            }
+
+            preview.values.push(value_preview);
+        }
+
+        columns.push(preview);
+    }
+
+    info!("表 {} 的脱敏预览完成: {} 行, {} 个脱敏列", table.name, samples.len(), columns.len());
+    Ok(MaskPreview {
+        table: table.name.clone(),
+        row_count: samples.len(),
+        columns,
+    })
+}
+
+/// 列规则、字段规则或 JSON 路径的内层规则中是否有令牌化规则
+fn uses_token_rule(column: &ColumnConfig) -> bool {
+    fn is_token(rule: &MaskRule) -> bool {
+        rule.rule_type == MaskRuleType::Token
+            || rule.json_options.as_ref().is_some_and(|o| is_token(&o.rule))
+    }
+    column.mask_rule.iter().any(is_token)
+        || column.field_rules.iter().any(|f| is_token(&f.rule))
+}
+
 /// 获取配置摘要
 #[tauri::command]
-pub fn get_config_summary(config: Config) -> Result<ConfigSummary, String> {
+pub async fn get_config_summary(
+    mut config: Config,
+    storage: State<'_, Arc<Storage>>,
+) -> Result<ConfigSummary, String> {
     info!("生成配置摘要信息");
+
+    let mask_rules = resolve_mask_policies(&mut config, &storage).await?;
     
     // 计算所有表的列总数
     let total_columns = config.tables.iter()
@@ -248,6 +552,8 @@ pub fn get_config_summary(config: Config) -> Result<ConfigSummary, String> {
         total_columns,
         has_source_ssh: config.source_db.ssh_config.is_some(),
         has_target_ssh: config.target_db.ssh_config.is_some(),
+        mask_policies: config.mask_policies.clone(),
+        mask_rules,
     };
 
     info!("配置摘要: 源库 {}, 目标库 {}, {} 个表, {} 个列", 
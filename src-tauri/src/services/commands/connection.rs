@@ -1,38 +1,70 @@
 // 数据库连接相关命令
 
-use crate::{database::DbClient, db::DatabaseConfig};
+use crate::{database::{self, ConnectOutcome, ConnectionStringFormat, DbClient, HostKeyStatus}, db::DatabaseConfig};
+use crate::services::commands::types::ConnectionTestResult;
 use log::{info, error};
 
 /// 测试数据库连接
 #[tauri::command]
-pub async fn test_connection(config: DatabaseConfig) -> Result<String, String> {
+pub async fn test_connection(config: DatabaseConfig) -> Result<ConnectionTestResult, String> {
     // 记录详细的连接信息
     info!("测试数据库连接请求:");
     
-    // 检查是否测试
-    let test = !config.host.is_empty() && 
-                      !config.database.is_empty() && 
+    // 检查是否测试，主机为空时使用本机默认的 Unix 套接字
+    let test = !config.database.is_empty() && 
                       !config.username.is_empty();
     
     // 记录测试信息
     if test {
         info!("测试数据库: {}:{}/{}, 用户: {}, SSL: {}", 
             config.host, config.port, config.database,
-            config.username, config.ssl_mode);
+            config.username, config.ssl_mode.as_str());
         
-        if config.ssh_config.is_some() {
+        if !config.extra_hosts.is_empty() {
+            info!("共 {} 个候选主机，会话要求: {}",
+                config.extra_hosts.len() + 1, config.target_session_attrs.as_str());
+        }
+
+        if let Some(ssh_config) = &config.ssh_config {
             info!("使用SSH隧道连接源数据库");
+            if !ssh_config.jump_hosts.is_empty() {
+                info!("经过 {} 个跳板机", ssh_config.jump_hosts.len());
+            }
         }
     } else {
         error!("数据库配置无效");
         return Err("请提供有效的数据库配置".to_string());
     }
     
-    // 测试源数据库连接
-    match DbClient::new(&config).await {
-        Ok(_) => {
+    // 测试源数据库连接，遇到未被信任的 SSH 主机时只返回其指纹供用户确认，不发送凭据
+    match DbClient::test_connect(&config).await {
+        Ok(ConnectOutcome::Connected(client)) => {
             info!("数据库连接测试成功");
-            return Ok("数据库连接成功".to_string());
+            return Ok(ConnectionTestResult {
+                connected: true,
+                message: "数据库连接成功".to_string(),
+                ssh_host_keys: client.ssh_host_keys.clone(),
+            });
+        },
+        Ok(ConnectOutcome::UntrustedHost(ssh_host_keys)) => {
+            let untrusted = ssh_host_keys
+                .iter()
+                .filter(|host_key| host_key.status == HostKeyStatus::Unknown)
+                .map(|host_key| format!(
+                    "{}:{}（{}）",
+                    host_key.host, host_key.port, host_key.fingerprint
+                ))
+                .collect::<Vec<String>>()
+                .join("，");
+            info!("SSH 主机尚未被信任，未进行认证: {}", untrusted);
+            return Ok(ConnectionTestResult {
+                connected: false,
+                message: format!(
+                    "SSH 主机 {} 尚未被信任，请确认其主机密钥指纹并固定后重新测试连接",
+                    untrusted
+                ),
+                ssh_host_keys,
+            });
         },
         Err(e) => {
             error!("数据库连接测试失败: {}", e);
@@ -42,3 +74,21 @@ pub async fn test_connection(config: DatabaseConfig) -> Result<String, String> {
     
 }
 
+/// 解析 URI（`postgresql://...`）或键值对格式的连接串
+#[tauri::command]
+pub async fn parse_connection_string(connection_string: String) -> Result<DatabaseConfig, String> {
+    database::parse_connection_string(&connection_string).map_err(|e| {
+        error!("解析连接串失败: {}", e);
+        e.to_string()
+    })
+}
+
+/// 将数据库配置生成为连接串，`include_password` 为 false 时省略密码
+#[tauri::command]
+pub async fn format_connection_string(
+    config: DatabaseConfig,
+    format: ConnectionStringFormat,
+    include_password: bool,
+) -> Result<String, String> {
+    Ok(database::format_connection_string(&config, format, include_password))
+}
@@ -5,9 +5,13 @@
 pub mod connection;
 pub mod task;
 pub mod config;
+pub mod policy;
 pub mod types;
+pub mod token;
 
 pub use connection::*;
 pub use task::*;
 pub use config::*;
-pub use types::*; 
\ No newline at end of file
+pub use policy::*;
+pub use types::*;
+pub use token::*; 
\ No newline at end of file
@@ -0,0 +1,75 @@
+// 脱敏策略相关命令
+
+use crate::database::{Config, MaskPolicy};
+use crate::services::masking::{apply_mask_policies, pattern_matches, EffectiveMaskRule};
+use crate::services::Storage;
+use std::sync::Arc;
+use tauri::State;
+use log::{info, error};
+
+/// 保存脱敏策略，同名策略会被覆盖
+#[tauri::command]
+pub async fn save_mask_policy(
+    policy: MaskPolicy,
+    storage: State<'_, Arc<Storage>>,
+) -> Result<(), String> {
+    info!("保存脱敏策略: {}, 规则数量: {}", policy.name, policy.rules.len());
+
+    if policy.name.trim().is_empty() {
+        return Err("脱敏策略名称不能为空".to_string());
+    }
+    if let Some(rule) = policy.rules.iter().find(|r| r.column_pattern.trim().is_empty()) {
+        return Err(format!("脱敏策略 {} 中存在空的列名模式: {:?}", policy.name, rule.table_pattern));
+    }
+
+    storage.save_mask_policy(&policy)
+        .await
+        .map_err(|e| {
+            error!("保存脱敏策略失败: {}", e);
+            format!("保存脱敏策略失败: {}", e)
+        })
+}
+
+/// 列出所有脱敏策略
+#[tauri::command]
+pub async fn list_mask_policies(
+    storage: State<'_, Arc<Storage>>,
+) -> Result<Vec<MaskPolicy>, String> {
+    storage.list_mask_policies()
+        .await
+        .map_err(|e| format!("获取脱敏策略列表失败: {}", e))
+}
+
+/// 删除脱敏策略
+#[tauri::command]
+pub async fn delete_mask_policy(
+    name: String,
+    storage: State<'_, Arc<Storage>>,
+) -> Result<(), String> {
+    match storage.delete_mask_policy(&name).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err("脱敏策略不存在".to_string()),
+        Err(e) => Err(format!("删除脱敏策略失败: {}", e)),
+    }
+}
+
+/// 测试列名是否匹配模式，便于编辑策略时校验
+#[tauri::command]
+pub fn test_mask_pattern(pattern: String, name: String) -> bool {
+    pattern_matches(&pattern, &name)
+}
+
+/// 加载配置引用的脱敏策略并应用到表配置，返回各脱敏列最终生效的规则
+pub async fn resolve_mask_policies(
+    config: &mut Config,
+    storage: &Storage,
+) -> Result<Vec<EffectiveMaskRule>, String> {
+    let policies = storage.load_mask_policies(&config.mask_policies)
+        .await
+        .map_err(|e| format!("加载脱敏策略失败: {}", e))?;
+
+    let effective = apply_mask_policies(&mut config.tables, &policies);
+    let from_policy = effective.iter().filter(|r| r.policy.is_some()).count();
+    info!("脱敏规则解析完成: {} 个脱敏列, 其中 {} 个来自策略", effective.len(), from_policy);
+    Ok(effective)
+}
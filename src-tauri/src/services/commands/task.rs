@@ -1,14 +1,17 @@
 // 任务相关命令
 
-use crate::database::{Config, TaskStatus, TaskState, Progress};
-use crate::db_copier::DbCopier;
-use crate::services::Storage;
-use crate::services::commands::types::TaskStore;
+use crate::database::{Config, MaskRuleType, TaskStatus, TaskState, Progress};
+use crate::db_copier::{CopyEvent, DbCopier};
+use crate::services::masking::{build_audit_report, render_audit_html, MaskContext};
+use crate::services::{Storage, TokenVault};
+use crate::services::commands::policy::resolve_mask_policies;
+use crate::services::commands::types::{ReportFormat, TaskStore};
 use std::sync::Arc;
 use chrono::Local;
 use tauri::State;
 use log::{info, error};
 use tokio;
+use std::fs;
 use std::collections::HashMap;
 use std::sync::Mutex;
 
@@ -16,6 +19,7 @@ use std::sync::Mutex;
 #[tauri::command]
 pub async fn start_copy(
     config: Config,
+    vault_key: Option<String>,
     task_store: State<'_, TaskStore>,
     storage: State<'_, Arc<Storage>>,
 ) -> Result<String, String> {
@@ -33,6 +37,7 @@ pub async fn start_copy(
             total: config.tables.len(),
             table_name: String::new(),
         }),
+        audit_report: None,
     };
 
     // 保存初始任务状态
@@ -81,57 +86,197 @@ pub async fn start_copy(
             Ok(())
         }
 
+        // 解析配置引用的脱敏策略，得到各列实际使用的规则
+        let mut config = config;
+        let mask_rules = match resolve_mask_policies(&mut config, &storage_clone).await {
+            Ok(mask_rules) => mask_rules,
+            Err(e) => {
+                error!("Failed to resolve mask policies: {}", e);
+                let _ = update_task(
+                    &task_id_clone,
+                    &task_store_clone,
+                    &storage_clone,
+                    |task| {
+                        task.status = TaskState::Failed;
+                        task.end_time = Some(Local::now().format("%Y-%m-%d %H:%M:%S").to_string());
+                        task.message = Some(e);
+                    }
+                ).await;
+                return;
+            }
+        };
+
+        // 使用令牌化规则时解锁令牌库
+        let uses_token = mask_rules.iter().any(|r| r.rule.rule_type == MaskRuleType::Token);
+        let vault = match (uses_token, vault_key.as_deref()) {
+            (false, _) => Ok(None),
+            // 密钥派生是耗时的阻塞操作
+            (true, Some(vault_key)) => {
+                let vault_key = vault_key.to_string();
+                tokio::task::spawn_blocking(move || TokenVault::open(&vault_key))
+                    .await
+                    .map_err(|e| format!("打开令牌库失败: {}", e))
+                    .and_then(|vault| vault.map(Some))
+            }
+            (true, None) => Err("配置中使用了令牌化规则，需要提供令牌库密钥".to_string()),
+        };
+        let vault = match vault {
+            Ok(vault) => vault,
+            Err(e) => {
+                error!("Failed to open token vault: {}", e);
+                let _ = update_task(
+                    &task_id_clone,
+                    &task_store_clone,
+                    &storage_clone,
+                    |task| {
+                        task.status = TaskState::Failed;
+                        task.end_time = Some(Local::now().format("%Y-%m-%d %H:%M:%S").to_string());
+                        task.message = Some(e);
+                    }
+                ).await;
+                return;
+            }
+        };
+
+        // 读取 HMAC 脱敏密钥
+        let mask_context = match storage_clone.get_or_create_mask_secret(config.mask_secret_scope()).await {
+            Ok(secret) => {
+                let context = MaskContext::new().with_secret(secret);
+                match vault {
+                    Some(vault) => context.with_vault(vault),
+                    None => context,
+                }
+            }
+            Err(e) => {
+                error!("Failed to load mask secret: {}", e);
+                let _ = update_task(
+                    &task_id_clone,
+                    &task_store_clone,
+                    &storage_clone,
+                    |task| {
+                        task.status = TaskState::Failed;
+                        task.end_time = Some(Local::now().format("%Y-%m-%d %H:%M:%S").to_string());
+                        task.message = Some(format!("读取脱敏密钥失败: {}", e));
+                    }
+                ).await;
+                return;
+            }
+        };
+
         match DbCopier::new(&config).await {
             Ok(copier) => {
-                for (i, table) in config.tables.iter().enumerate() {
-                    info!("Copying table {}/{}: {}", i + 1, config.tables.len(), table.name);
-                    
-                    // 更新进度
+                let mut copier = copier.with_mask_context(mask_context);
+                if let Err(e) = copier.prepare_masking(&config.tables).await {
+                    error!("Failed to prepare masking: {}", e);
                     let _ = update_task(
                         &task_id_clone,
                         &task_store_clone,
                         &storage_clone,
                         |task| {
-                            if let Some(progress) = &mut task.progress {
-                                progress.current = i;
-                                progress.table_name = table.name.clone();
-                            }
+                            task.status = TaskState::Failed;
+                            task.end_time = Some(Local::now().format("%Y-%m-%d %H:%M:%S").to_string());
+                            task.message = Some(format!("脱敏配置无效: {}", e));
                         }
                     ).await;
+                    return;
+                }
 
-                    match copier.copy_table(table).await {
-                        Ok(_) => {
-                            info!("Successfully copied table: {}", table.name);
-                        }
-                        Err(e) => {
-                            error!("Failed to copy table {}: {}", table.name, e);
-                            let _ = update_task(
-                                &task_id_clone,
-                                &task_store_clone,
-                                &storage_clone,
-                                |task| {
-                                    task.status = TaskState::Failed;
-                                    task.end_time = Some(Local::now().format("%Y-%m-%d %H:%M:%S").to_string());
-                                    task.message = Some(format!("复制表 {} 失败: {}", table.name, e));
-                                }
-                            ).await;
-                            return;
+                // 所有表都在同一个源库快照中读取
+                info!("Reading source snapshot: {}", copier.snapshot());
+
+                // 多个工作会话并行复制，各表开始和完成时更新进度
+                let (events, mut event_rx) = tokio::sync::mpsc::unbounded_channel();
+                let copy = copier.copy_tables(&config.tables, events);
+                let progress = async {
+                    // 各表复制的行数，用于生成审计报告
+                    let mut copied_rows = HashMap::new();
+                    while let Some(event) = event_rx.recv().await {
+                        match event {
+                            CopyEvent::Started { table } => {
+                                info!("Copying table {}/{}: {}", copied_rows.len() + 1, config.tables.len(), table);
+                                let _ = update_task(
+                                    &task_id_clone,
+                                    &task_store_clone,
+                                    &storage_clone,
+                                    |task| {
+                                        if let Some(progress) = &mut task.progress {
+                                            progress.table_name = table;
+                                        }
+                                    }
+                                ).await;
+                            }
+                            CopyEvent::Finished { table, rows } => {
+                                info!("Successfully copied table: {}", table);
+                                copied_rows.insert(table, rows);
+                                let current = copied_rows.len();
+                                let _ = update_task(
+                                    &task_id_clone,
+                                    &task_store_clone,
+                                    &storage_clone,
+                                    |task| {
+                                        if let Some(progress) = &mut task.progress {
+                                            progress.current = current;
+                                        }
+                                    }
+                                ).await;
+                            }
                         }
                     }
+                    copied_rows
+                };
+                let (result, copied_rows) = tokio::join!(copy, progress);
+
+                if let Err(e) = result {
+                    let message = match &e.table {
+                        Some(table) => format!("复制表 {} 失败: {}", table, e.error),
+                        None => format!("建立复制会话失败: {}", e.error),
+                    };
+                    error!("{}", message);
+                    let end_time = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+                    let report = build_audit_report(
+                        &task_id_clone,
+                        &config,
+                        &mask_rules,
+                        &copied_rows,
+                        false,
+                        end_time.clone(),
+                    );
+                    let _ = update_task(
+                        &task_id_clone,
+                        &task_store_clone,
+                        &storage_clone,
+                        |task| {
+                            task.status = TaskState::Failed;
+                            task.end_time = Some(end_time);
+                            task.message = Some(message);
+                            task.audit_report = Some(report);
+                        }
+                    ).await;
+                    return;
                 }
 
-                // 更新完成状态
+                // 更新完成状态，同时保存审计报告
+                let end_time = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+                let report = build_audit_report(
+                    &task_id_clone,
+                    &config,
+                    &mask_rules,
+                    &copied_rows,
+                    true,
+                    end_time.clone(),
+                );
                 let _ = update_task(
                     &task_id_clone,
                     &task_store_clone,
                     &storage_clone,
                     |task| {
                         task.status = TaskState::Completed;
-                        task.end_time = Some(Local::now().format("%Y-%m-%d %H:%M:%S").to_string());
+                        task.end_time = Some(end_time);
                         task.message = Some("复制完成".to_string());
                         if let Some(progress) = &mut task.progress {
                             progress.current = config.tables.len();
                         }
+                        task.audit_report = Some(report);
                     }
                 ).await;
                 info!("Copy task completed: {}", task_id_clone);
@@ -208,4 +353,39 @@ pub async fn get_all_tasks(
     });
 
     Ok(tasks)
-}
\ No newline at end of file
+}
+
+/// 获取任务的脱敏审计报告，按指定格式返回报告内容
+#[tauri::command]
+pub async fn get_task_report(
+    task_id: String,
+    format: ReportFormat,
+    task_store: State<'_, TaskStore>,
+    storage: State<'_, Arc<Storage>>,
+) -> Result<String, String> {
+    let task = get_task_status(task_id.clone(), task_store, storage).await?;
+    let report = task.audit_report
+        .ok_or_else(|| format!("任务 {} 没有审计报告", task_id))?;
+
+    match format {
+        ReportFormat::Json => serde_json::to_string_pretty(&report)
+            .map_err(|e| format!("序列化审计报告失败: {}", e)),
+        ReportFormat::Html => Ok(render_audit_html(&report)),
+    }
+}
+
+/// 导出任务的脱敏审计报告到文件
+#[tauri::command]
+pub async fn export_task_report(
+    task_id: String,
+    format: ReportFormat,
+    file_path: String,
+    task_store: State<'_, TaskStore>,
+    storage: State<'_, Arc<Storage>>,
+) -> Result<(), String> {
+    let content = get_task_report(task_id.clone(), format, task_store, storage).await?;
+    fs::write(&file_path, content)
+        .map_err(|e| format!("写入审计报告失败: {}", e))?;
+    info!("任务 {} 的审计报告已导出到 {}", task_id, file_path);
+    Ok(())
+}
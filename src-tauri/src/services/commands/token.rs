@@ -0,0 +1,39 @@
+// 令牌库相关命令
+
+use crate::services::TokenVault;
+use serde::Serialize;
+use log::{info, error};
+
+/// 令牌查询结果，令牌不存在时 original 为空
+#[derive(Debug, Serialize)]
+pub struct TokenLookup {
+    pub token: String,
+    pub original: Option<String>,
+}
+
+/// 使用令牌库密钥查询令牌对应的原值
+#[tauri::command]
+pub async fn lookup_tokens(
+    vault_key: String,
+    tokens: Vec<String>,
+) -> Result<Vec<TokenLookup>, String> {
+    info!("查询令牌原值: {} 个令牌", tokens.len());
+
+    // 密钥派生和 SQLite 查询都是阻塞操作
+    tokio::task::spawn_blocking(move || {
+        let vault = TokenVault::open(&vault_key).map_err(|e| {
+            error!("打开令牌库失败: {}", e);
+            e
+        })?;
+
+        tokens
+            .into_iter()
+            .map(|token| {
+                let original = vault.detokenize(token.trim())?;
+                Ok(TokenLookup { token, original })
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| format!("查询令牌失败: {}", e))?
+}
@@ -2,13 +2,25 @@
 
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
-use serde::Serialize;
-use crate::database::TaskStatus;
+use serde::{Deserialize, Serialize};
+use crate::database::{FieldMaskRule, HostKeyInfo, MaskRule, TaskStatus};
+use crate::services::masking::EffectiveMaskRule;
 
 /// 任务存储，用于在内存中跟踪任务状态
 #[derive(Clone)]
 pub struct TaskStore(pub Arc<Mutex<HashMap<String, TaskStatus>>>);
 
+/// 测试连接的结果
+#[derive(Debug, Serialize)]
+pub struct ConnectionTestResult {
+    /// 是否已连接到数据库；遇到未被信任的 SSH 主机时为 false，此时没有进行认证
+    pub connected: bool,
+    pub message: String,
+    /// 经过 SSH 连接时各跳服务器的主机密钥，按跳板机顺序排列，最后一个为 SSH 主机；
+    /// 未连接成功时只包含到第一个未被信任的主机为止，状态为 unknown 时需要用户确认
+    pub ssh_host_keys: Vec<HostKeyInfo>,
+}
+
 /// 配置摘要信息
 #[derive(Debug, Serialize)]
 pub struct ConfigSummary {
@@ -18,4 +30,65 @@ pub struct ConfigSummary {
     pub total_columns: usize,
     pub has_source_ssh: bool,
     pub has_target_ssh: bool,
-} 
\ No newline at end of file
+    /// 引用的脱敏策略
+    pub mask_policies: Vec<String>,
+    /// 各脱敏列最终生效的规则
+    pub mask_rules: Vec<EffectiveMaskRule>,
+}
+
+/// 单个值的脱敏预览标记
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub enum PreviewFlag {
+    /// 脱敏出错，或源库无法按列类型解析脱敏结果
+    #[serde(rename = "error")]
+    Error,
+    /// 脱敏结果超出源表列的最大长度，目标表按源表结构创建，复制时同样会超长
+    #[serde(rename = "source_length_overflow")]
+    SourceLengthOverflow,
+    /// 脱敏结果与原值相同
+    #[serde(rename = "unchanged")]
+    Unchanged,
+}
+
+/// 单个值的脱敏前后对比，值均为 PostgreSQL 文本形式
+#[derive(Debug, Serialize)]
+pub struct ValuePreview {
+    pub original: Option<String>,
+    pub masked: Option<String>,
+    pub flags: Vec<PreviewFlag>,
+    pub error: Option<String>,
+}
+
+/// 单列的脱敏预览
+#[derive(Debug, Serialize)]
+pub struct ColumnPreview {
+    pub column: String,
+    pub rule: Option<MaskRule>,
+    /// 复合类型列的字段规则
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub field_rules: Vec<FieldMaskRule>,
+    /// 源表列的最大字符长度
+    pub source_max_length: Option<usize>,
+    /// 规则本身无效时的错误，此时没有逐值预览
+    pub error: Option<String>,
+    /// 无法预览的原因，此时没有逐值预览，但不表示规则有误
+    pub not_previewable: Option<String>,
+    pub values: Vec<ValuePreview>,
+}
+
+/// 表的脱敏预览
+#[derive(Debug, Serialize)]
+pub struct MaskPreview {
+    pub table: String,
+    pub row_count: usize,
+    pub columns: Vec<ColumnPreview>,
+}
+
+/// 审计报告的输出格式
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub enum ReportFormat {
+    #[serde(rename = "json")]
+    Json,
+    #[serde(rename = "html")]
+    Html,
+}
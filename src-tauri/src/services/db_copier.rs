@@ -1,19 +1,41 @@
-use crate::database::{Config, DbError, MaskRule, MaskRuleType, TableConfig};
+use crate::database::{
+    is_text_type, resolve_type, Config, DatabaseConfig, DbError, MaskRuleType, RawValue,
+    SourceClient, TableConfig,
+};
 use crate::db::DbClient;
-use futures::stream::{self, StreamExt};
+use crate::services::masking::{
+    build_domains, check_foreign_keys, embeds_foreign_oids, field_type, hash_text_length,
+    remap_oids, value_type, ColumnMasker, ColumnShuffler, DomainColumn, ForeignKeyColumn,
+    MaskContext, MaskDomain,
+};
+use bytes::Bytes;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use futures::pin_mut;
+use log::{info, warn};
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use tokio::sync::Semaphore;
-use tokio_postgres::types::ToSql;
-use tokio_postgres::Client;
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio::sync::{Mutex, RwLock};
+use tokio_postgres::binary_copy::{BinaryCopyInWriter, BinaryCopyOutStream};
+use tokio_postgres::types::{ToSql, Type};
+
+/// COPY 管道中允许同时缓冲的行数
+const COPY_CHANNEL_CAPACITY: usize = 1024;
+
+/// 并行复制时的最大工作会话数
+const MAX_COPY_WORKERS: usize = 4;
+
+/// 每个洗牌列在内存中缓冲的最大字节数，超过后写入临时文件
+const SHUFFLE_MEMORY_LIMIT: usize = 64 * 1024 * 1024;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ColumnInfo {
     name: String,
     data_type: String,
+    /// `format_type` 格式化后的完整类型，如 `character varying(64)`、`integer[]`
+    column_type: String,
+    type_oid: u32,
     is_nullable: bool,
     column_default: Option<String>,
     character_maximum_length: Option<i32>,
@@ -27,243 +49,471 @@ pub struct TableInfo {
     constraints: Vec<String>,
 }
 
+impl TableInfo {
+    /// 按列名查找列类型
+    pub fn column_type(&self, column: &str) -> Result<Type, DbError> {
+        self.columns
+            .iter()
+            .find(|c| c.name == column)
+            .map(|c| resolve_type(c.type_oid, &c.column_type))
+            .ok_or_else(|| DbError::Query(format!("表 {} 中不存在列 {}", self.name, column)))
+    }
+
+    /// 按列名查找列的最大字符长度，仅 varchar/char 等有长度限制的列有值
+    pub fn column_max_length(&self, column: &str) -> Option<usize> {
+        self.columns
+            .iter()
+            .find(|c| c.name == column)
+            .and_then(|c| c.character_maximum_length)
+            .map(|length| length as usize)
+    }
+
+    /// 从数据库读取表结构
+    pub async fn load(client: &SourceClient, table_name: &str) -> Result<Self, DbError> {
+        let rows = client
+            .query(
+                "SELECT c.column_name, c.data_type, c.is_nullable, c.column_default, c.character_maximum_length,
+                        format_type(a.atttypid, a.atttypmod) AS column_type,
+                        CASE WHEN t.typtype = 'd' THEN t.typbasetype ELSE a.atttypid END AS type_oid
+                 FROM information_schema.columns c
+                 JOIN pg_attribute a
+                   ON a.attrelid = format('%I.%I', c.table_schema, c.table_name)::regclass
+                  AND a.attname = c.column_name
+                 JOIN pg_type t ON t.oid = a.atttypid
+                 WHERE c.table_schema = 'public' AND c.table_name = $1
+                 ORDER BY c.ordinal_position",
+                &[&table_name],
+            )
+            .await?;
+
+        let mut columns = Vec::new();
+        for row in rows {
+            let is_nullable: String = row.get("is_nullable");
+            columns.push(ColumnInfo {
+                name: row.get("column_name"),
+                data_type: row.get("data_type"),
+                column_type: row.get("column_type"),
+                type_oid: row.get("type_oid"),
+                is_nullable: is_nullable.eq_ignore_ascii_case("YES"),
+                column_default: row.get("column_default"),
+                character_maximum_length: row.get("character_maximum_length"),
+            });
+        }
+
+        // 获取索引信息
+        let indexes = get_table_indexes(client, table_name).await?;
+
+        // 获取约束信息
+        let constraints = get_table_constraints(client, table_name).await?;
+
+        Ok(TableInfo {
+            name: table_name.to_string(),
+            columns,
+            indexes,
+            constraints,
+        })
+    }
+}
+
+/// 并行复制中各表的进度
+#[derive(Debug, Clone)]
+pub enum CopyEvent {
+    Started { table: String },
+    Finished { table: String, rows: u64 },
+}
+
+/// 并行复制失败的原因
+#[derive(Debug)]
+pub struct CopyTablesError {
+    /// 出错的表，工作会话建立失败时为 None
+    pub table: Option<String>,
+    pub error: DbError,
+}
+
 pub struct DbCopier {
-    source_client: DbClient,
+    /// 源库只读连接，类型上只能查询和导出，DDL/DML 只会发往目标库
+    ///
+    /// 同时作为协调会话：其事务在复制期间保持打开，并行复制的工作会话导入它导出的快照
+    source_client: SourceClient,
+    source_config: DatabaseConfig,
+    /// 协调会话导出的快照 ID，本次复制的所有表都读取这一时间点的数据
+    snapshot: String,
     target_client: DbClient,
+    target_config: DatabaseConfig,
     table_info_cache: Arc<RwLock<HashMap<String, TableInfo>>>,
+    mask_context: MaskContext,
 }
 
 impl DbCopier {
     pub async fn new(config: &Config) -> Result<Self, DbError> {
-        // 检查源数据库配置是否有效
-        let source_valid = !config.source_db.host.is_empty()
-            && !config.source_db.database.is_empty()
+        // 检查源数据库配置是否有效，主机为空时使用本机默认的 Unix 套接字
+        let source_valid = !config.source_db.database.is_empty()
             && !config.source_db.username.is_empty();
 
         // 检查目标数据库配置是否有效
-        let target_valid = !config.target_db.host.is_empty()
-            && !config.target_db.database.is_empty()
+        let target_valid = !config.target_db.database.is_empty()
             && !config.target_db.username.is_empty();
 
         if !source_valid || !target_valid {
             return Err(DbError::Connection("数据库配置无效".to_string()));
         }
 
-        let source_client = DbClient::new(&config.source_db).await?;
+        // 源库以只读会话连接，所有读取在同一个只读事务中完成
+        let source_client = SourceClient::connect(&config.source_db).await?;
+        source_client.begin().await?;
+        let snapshot = source_client.export_snapshot().await?;
         let target_client = DbClient::new(&config.target_db).await?;
 
         Ok(Self {
             source_client,
+            source_config: config.source_db.clone(),
+            snapshot,
             target_client,
+            target_config: config.target_db.clone(),
             table_info_cache: Arc::new(RwLock::new(HashMap::new())),
+            mask_context: MaskContext::new(),
         })
     }
 
-    #[allow(dead_code)]
-    fn apply_mask_rule(&self, value: &str, rule: &MaskRule) -> String {
-        match rule.rule_type {
-            MaskRuleType::Hash => {
-                let mut hasher = Sha256::new();
-                hasher.update(value.as_bytes());
-                format!("{:x}", hasher.finalize())
-            }
-            MaskRuleType::Fixed => rule.pattern.clone().unwrap_or_else(|| "****".to_string()),
-            MaskRuleType::Pattern => {
-                if let Some(pattern) = &rule.pattern {
-                    let mut result = String::with_capacity(value.len());
-                    let mut chars = value.chars();
-                    for p in pattern.chars() {
-                        match p {
-                            '#' => {
-                                if let Some(c) = chars.next() {
-                                    result.push(c);
-                                }
-                            }
-                            '*' => {
-                                if chars.next().is_some() {
-                                    result.push('*');
-                                }
-                            }
-                            _ => result.push(p),
-                        }
-                    }
-                    result
-                } else {
-                    value.to_string()
-                }
-            }
-        }
+    /// 本次复制读取的源库快照 ID
+    pub fn snapshot(&self) -> &str {
+        &self.snapshot
     }
 
-    #[allow(dead_code)]
-    async fn execute_batch_insert(
+    /// 设置脱敏上下文（如 HMAC 密钥）
+    pub fn with_mask_context(mut self, mask_context: MaskContext) -> Self {
+        self.mask_context = mask_context;
+        self
+    }
+
+    /// 为整个配置准备脱敏上下文：收集各表列上的脱敏域，并检查外键两端的脱敏配置
+    ///
+    /// 必须在复制任何表之前调用，脱敏域需要看到所有表的列类型才能确定输出形态
+    pub async fn prepare_masking(&mut self, tables: &[TableConfig]) -> Result<(), DbError> {
+        let domains = resolve_mask_domains(&self.source_client, tables).await?;
+        self.mask_context = self.mask_context.clone().with_domains(domains);
+        Ok(())
+    }
+
+    /// 复制一张表，返回复制的行数，只复制结构时为 0
+    pub async fn copy_table(&self, table: &TableConfig) -> Result<u64, DbError> {
+        self.copy_table_with(&self.source_client, &self.target_client, table)
+            .await
+    }
+
+    /// 通过指定的源库会话和目标库连接复制一张表，源库会话必须处于本次复制的快照中
+    async fn copy_table_with(
         &self,
+        source_client: &SourceClient,
+        target_client: &DbClient,
         table: &TableConfig,
-        batch_values: &mut Vec<String>,
-        batch_params: &mut Vec<String>,
-    ) -> Result<(), DbError> {
-        if batch_values.is_empty() {
-            return Ok(());
+    ) -> Result<u64, DbError> {
+        // 获取并同步表结构
+        let table_info = self.get_table_info(&table.name).await?;
+        self.sync_structure_on(target_client, &table_info, table.ignore_foreign_keys)
+            .await?;
+
+        // 如果只复制结构，则直接返回
+        if table.structure_only {
+            return Ok(0);
         }
 
-        let insert_sql = format!(
-            "INSERT INTO {} ({}) VALUES {}",
-            table.name,
+        // 使用 COPY BINARY 流式复制数据
+        let rows = self
+            .copy_table_data(source_client, target_client, table, &table_info)
+            .await?;
+        info!("表 {} 复制完成, 共 {} 行", table.name, rows);
+
+        Ok(rows)
+    }
+
+    /// 通过 `COPY ... TO STDOUT` / `COPY ... FROM STDIN` 以二进制格式流式传输表数据，
+    /// 源端与目标端之间只保留有限数量的行，内存占用与表大小无关
+    ///
+    /// 每个字段按 `get_table_info` 中的类型 OID 以原始线格式透传，NULL 保持为 NULL；
+    /// 选中且配置了脱敏规则的列在写入目标库前先经过脱敏，自定义数组和复合类型的值
+    /// 中嵌入的类型 OID 改写为目标库的 OID
+    async fn copy_table_data(
+        &self,
+        source_client: &SourceClient,
+        target_client: &DbClient,
+        table: &TableConfig,
+        table_info: &TableInfo,
+    ) -> Result<u64, DbError> {
+        let column_index = |name: &str, purpose: &str, column: &str| {
             table
                 .columns
                 .iter()
-                .map(|c| c.name.clone())
-                .collect::<Vec<String>>()
-                .join(", "),
-            batch_values.join(", ")
-        );
+                .position(|c| c.name == name)
+                .ok_or_else(|| {
+                    DbError::Mask(format!(
+                        "列 {} 的{} {} 不在复制的列中",
+                        column, purpose, name
+                    ))
+                })
+        };
 
-        self.target_client
-            .client
-            .execute(
-                &insert_sql,
-                &batch_params.iter().map(|s| s as _).collect::<Vec<_>>(),
-            )
-            .await
-            .map_err(|e| DbError::Query(e.to_string()))?;
+        // 数组和复合类型的脱敏需要元素类型和字段定义
+        let masked_columns: Vec<&str> = table
+            .columns
+            .iter()
+            .filter(|c| c.is_masked())
+            .map(|c| c.name.as_str())
+            .collect();
+        let masked_types: HashMap<&str, Type> = masked_columns
+            .iter()
+            .copied()
+            .zip(resolve_column_types(source_client, &table.name, &masked_columns).await?)
+            .collect();
 
-        batch_values.clear();
-        batch_params.clear();
+        let mut types = Vec::with_capacity(table.columns.len());
+        let mut maskers = Vec::with_capacity(table.columns.len());
+        let mut shuffled = Vec::new();
+        for (i, column) in table.columns.iter().enumerate() {
+            let ty = table_info.column_type(&column.name)?;
+            let masker = match masked_types.get(column.name.as_str()) {
+                Some(masked_type) => {
+                    ColumnMasker::for_column(column, masked_type, &self.mask_context)?
+                }
+                None => None,
+            };
+            let masker = match masker {
+                Some(masker) if masker.is_shuffle() => {
+                    let group = match masker.group_column() {
+                        Some(group) => Some(column_index(group, "分组列", &column.name)?),
+                        None => None,
+                    };
+                    shuffled.push((i, group));
+                    None
+                }
+                Some(masker) => {
+                    // 扰动规则可以引用同一行中另一列的原值作为种子
+                    let seed = match masker.seed_column() {
+                        Some(seed) => Some(column_index(seed, "种子列", &column.name)?),
+                        None => None,
+                    };
+                    Some((masker, seed))
+                }
+                None => None,
+            };
+            types.push(ty);
+            maskers.push(masker);
+        }
 
-        Ok(())
-    }
-    #[allow(dead_code)]
-    async fn copy_table_data_stream(&self, table: &TableConfig) -> Result<(), DbError> {
-        const BATCH_SIZE: usize = 1000;
-        let mut values: Vec<String> = Vec::new();
-        let mut batch_params: Vec<String> = Vec::new();
-        let mut value_count = 0;
+        // 洗牌列需要先完整读取一遍
+        let mut shufflers = self.collect_shuffles(source_client, table, &types, &shuffled).await?;
+
+        // 用户自定义的数组和复合类型在线格式中嵌入了元素/字段类型 OID，需改写为目标库的 OID
+        let custom: Vec<(usize, &str)> = table
+            .columns
+            .iter()
+            .zip(&types)
+            .enumerate()
+            .filter(|(_, (_, ty))| Type::from_oid(ty.oid()).is_none())
+            .map(|(i, (c, _))| (i, c.name.as_str()))
+            .collect();
+        let mut remaps = Vec::new();
+        if !custom.is_empty() {
+            let custom_columns: Vec<&str> = custom.iter().map(|(_, name)| *name).collect();
+            let source_types = resolve_column_types(source_client, &table.name, &custom_columns).await?;
+            let target_types = target_client
+                .client
+                .prepare(&column_types_query(&table.name, &custom_columns))
+                .await
+                .map_err(|e| DbError::Query(format!("读取目标表 {} 的列类型失败: {}", table.name, e)))?;
+            for (((i, _), source_type), target_column) in custom
+                .iter()
+                .zip(source_types)
+                .zip(target_types.columns())
+            {
+                let target_type = target_column.type_().clone();
+                if embeds_foreign_oids(&source_type, &target_type) {
+                    remaps.push((*i, source_type, target_type));
+                }
+            }
+        }
 
-        // 构建查询语句
         let columns = table
             .columns
             .iter()
-            .map(|c| c.name.clone())
+            .map(|c| format!("\"{}\"", c.name))
             .collect::<Vec<String>>()
             .join(", ");
-        let select_sql = format!("SELECT {} FROM {}", columns, table.name);
+        let copy_out_sql = format!(
+            "COPY \"{}\" ({}) TO STDOUT (FORMAT binary)",
+            table.name, columns
+        );
+        let copy_in_sql = format!(
+            "COPY \"{}\" ({}) FROM STDIN (FORMAT binary)",
+            table.name, columns
+        );
 
-        // 执行查询
-        let source_rows = self
-            .source_client
+        let source = source_client
+            .copy_out(&copy_out_sql)
+            .await
+            .map_err(|e| DbError::Query(format!("读取源表 {} 失败: {}", table.name, e.message())))?;
+        let sink = target_client
             .client
-            .query(&select_sql, &[])
+            .copy_in::<_, Bytes>(&copy_in_sql)
             .await
-            .map_err(|e| DbError::Query(e.to_string()))?;
-
-        // 构建插入语句
-        let insert_sql = format!("INSERT INTO {} ({}) VALUES", table.name, columns);
-
-        let client = &self.target_client.client;
+            .map_err(|e| DbError::Query(format!("写入目标表 {} 失败: {}", table.name, e)))?;
+
+        let (tx, mut rx) = mpsc::channel::<Vec<Option<RawValue>>>(COPY_CHANNEL_CAPACITY);
+
+        // 读取端: 源库 COPY 输出 -> 按列解析并脱敏 -> 有界通道
+        let reader_types = types.clone();
+        let table_name = table.name.as_str();
+        let table_columns: Vec<&str> = table.columns.iter().map(|c| c.name.as_str()).collect();
+        let read = async move {
+            let rows = BinaryCopyOutStream::new(source, &reader_types);
+            pin_mut!(rows);
+            while let Some(row) = rows.next().await {
+                let row = row.map_err(|e| DbError::Query(e.to_string()))?;
+                let mut values = Vec::with_capacity(reader_types.len());
+                for i in 0..reader_types.len() {
+                    let value = row
+                        .try_get::<Option<RawValue>>(i)
+                        .map_err(|e| DbError::Query(e.to_string()))?;
+                    values.push(value);
+                }
 
-        // 处理每一行数据
-        for row in source_rows {
-            let mut row_placeholders = Vec::new();
-            for column in table.columns.iter() {
-                value_count += 1;
-                let value: String = row.get(column.name.as_str());
-                values.push(value);
-                row_placeholders.push(format!("${}", value_count));
+                // 先基于原值计算所有脱敏结果，保证种子列和分组列读到的是脱敏前的值
+                let mut masked = Vec::new();
+                for (i, masker) in maskers.iter().enumerate() {
+                    if let Some((masker, seed)) = masker {
+                        let seed = seed.and_then(|s| values[s].as_ref());
+                        masked.push((i, masker.mask_with_seed(values[i].clone(), seed)?));
+                    }
+                }
+                for (i, group, shuffler) in shufflers.iter_mut() {
+                    let group = group.and_then(|g| values[g].as_ref());
+                    masked.push((*i, shuffler.next(group)?));
+                }
+                for (i, value) in masked {
+                    values[i] = value;
+                }
+                for (i, source_type, target_type) in &remaps {
+                    if let Some(value) = &mut values[*i] {
+                        value.bytes = remap_oids(&value.bytes, source_type, target_type).map_err(|e| {
+                            DbError::Query(format!("改写列 {} 的类型 OID 失败: {}", table_columns[*i], e))
+                        })?;
+                    }
+                }
+                if tx.send(values).await.is_err() {
+                    // 写入端已退出，错误由写入端返回
+                    break;
+                }
             }
-            batch_params.push(format!("({})", row_placeholders.join(", ")));
-
-            if batch_params.len() >= BATCH_SIZE {
-                let full_insert_sql = format!("{} {}", insert_sql, batch_params.join(", "));
-                let params: Vec<&(dyn ToSql + Sync)> =
-                    values.iter().map(|v| v as &(dyn ToSql + Sync)).collect();
-                client
-                    .execute(&full_insert_sql, &params[..])
+            // 新令牌先于目标库的 COPY 提交，目标库中的令牌都能在令牌库中查到原值
+            self.mask_context.flush_vault()?;
+            for (i, _, shuffler) in &shufflers {
+                if shuffler.remaining() > 0 {
+                    warn!(
+                        "表 {} 列 {} 洗牌后剩余 {} 个未使用的值，表数据可能在复制过程中发生了变化",
+                        table_name,
+                        table_columns[*i],
+                        shuffler.remaining()
+                    );
+                }
+            }
+            Ok::<_, DbError>(())
+        };
+
+        // 写入端: 有界通道 -> 目标库 COPY 输入
+        // 任意一端出错时 try_join 会直接丢弃 writer，未 finish 的 COPY 会被目标库回滚
+        let write = async move {
+            let writer = BinaryCopyInWriter::new(sink, &types);
+            pin_mut!(writer);
+            while let Some(values) = rx.recv().await {
+                let params: Vec<&(dyn ToSql + Sync)> = values
+                    .iter()
+                    .map(|v| v as &(dyn ToSql + Sync))
+                    .collect();
+                writer
+                    .as_mut()
+                    .write(&params)
                     .await
                     .map_err(|e| DbError::Query(e.to_string()))?;
-
-                values.clear();
-                batch_params.clear();
-                value_count = 0;
             }
-        }
-
-        // 处理剩余的数据
-        if !batch_params.is_empty() {
-            let full_insert_sql = format!("{} {}", insert_sql, batch_params.join(", "));
-            let params: Vec<&(dyn ToSql + Sync)> =
-                values.iter().map(|v| v as &(dyn ToSql + Sync)).collect();
-            client
-                .execute(&full_insert_sql, &params[..])
+            writer
+                .finish()
                 .await
-                .map_err(|e| DbError::Query(e.to_string()))?;
-        }
+                .map_err(|e| DbError::Query(e.to_string()))
+        };
 
-        Ok(())
+        let (_, rows) = tokio::try_join!(read, write)?;
+        Ok(rows)
     }
 
-    pub async fn copy_table(&self, table: &TableConfig) -> Result<(), DbError> {
-        // 获取并同步表结构
-        let table_info = self.get_table_info(&table.name).await?;
-        self.sync_table_structure(&table_info, table.ignore_foreign_keys)
-            .await?;
+    /// 洗牌的第一遍：读取洗牌列及其分组列的全部取值
+    ///
+    /// 每列的缓冲（含分组键）超过 `SHUFFLE_MEMORY_LIMIT` 后排序写入临时文件，内存中最终只保留每个分组的摘要
+    async fn collect_shuffles(
+        &self,
+        source_client: &SourceClient,
+        table: &TableConfig,
+        types: &[Type],
+        shuffled: &[(usize, Option<usize>)],
+    ) -> Result<Vec<(usize, Option<usize>, ColumnShuffler)>, DbError> {
+        if shuffled.is_empty() {
+            return Ok(Vec::new());
+        }
 
-        // 如果只复制结构，则直接返回
-        if table.structure_only {
-            return Ok(());
+        // 只读取需要的列，记录每列在读取结果中的位置
+        let mut needed: Vec<usize> = Vec::new();
+        for (i, group) in shuffled {
+            for index in std::iter::once(*i).chain(*group) {
+                if !needed.contains(&index) {
+                    needed.push(index);
+                }
+            }
         }
+        let position = |index: usize| needed.iter().position(|n| *n == index).unwrap_or(0);
 
-        // 使用流式处理复制数据
-        let columns = table
-            .columns
+        let columns = needed
             .iter()
-            .map(|c| c.name.clone())
+            .map(|i| format!("\"{}\"", table.columns[*i].name))
             .collect::<Vec<String>>()
-            .join("\", \"");
-        let select_sql = format!("SELECT \"{}\" FROM \"{}\"", columns, table.name);
-
-        // 执行查询并插入数据
-        let rows = self
-            .source_client
-            .client
-            .query(&select_sql, &[])
+            .join(", ");
+        let read_types: Vec<Type> = needed.iter().map(|i| types[*i].clone()).collect();
+        let source = source_client
+            .copy_out(&format!(
+                "COPY \"{}\" ({}) TO STDOUT (FORMAT binary)",
+                table.name, columns
+            ))
             .await
-            .map_err(|e| DbError::Query(e.to_string()))?;
+            .map_err(|e| DbError::Query(format!("读取源表 {} 失败: {}", table.name, e.message())))?;
 
-        if !rows.is_empty() {
-            let insert_sql = format!("INSERT INTO \"{}\" (\"{}\") VALUES", table.name, columns);
-
-            // 构建批量插入的值
-            let mut values = Vec::new();
-            let mut params = Vec::new();
-            let mut param_count = 1;
-
-            for row in rows {
-                let mut row_values = Vec::new();
-                for column in &table.columns {
-                    let value: String = row.get(column.name.as_str());
-                    params.push(value);
-                    row_values.push(format!("${}", param_count));
-                    param_count += 1;
-                }
-                values.push(format!("({})", row_values.join(", ")));
-            }
+        let mut shufflers: Vec<_> = shuffled
+            .iter()
+            .map(|(i, group)| {
+                let shuffler =
+                    ColumnShuffler::new(&table.columns[*i].name, &types[*i], SHUFFLE_MEMORY_LIMIT);
+                (*i, *group, shuffler)
+            })
+            .collect();
 
-            // 执行批量插入
-            let full_insert_sql = format!("{} {}", insert_sql, values.join(", "));
-            self.target_client
-                .client
-                .execute(
-                    &full_insert_sql,
-                    &params
-                        .iter()
-                        .map(|s| s as &(dyn ToSql + Sync))
-                        .collect::<Vec<_>>(),
-                )
-                .await
-                .map_err(|e| DbError::Query(e.to_string()))?;
+        let rows = BinaryCopyOutStream::new(source, &read_types);
+        pin_mut!(rows);
+        let mut count = 0u64;
+        while let Some(row) = rows.next().await {
+            let row = row.map_err(|e| DbError::Query(e.to_string()))?;
+            let mut values = Vec::with_capacity(read_types.len());
+            for i in 0..read_types.len() {
+                let value = row
+                    .try_get::<Option<RawValue>>(i)
+                    .map_err(|e| DbError::Query(e.to_string()))?;
+                values.push(value);
+            }
+            for (i, group, shuffler) in shufflers.iter_mut() {
+                let group = group.and_then(|g| values[position(g)].as_ref());
+                shuffler.push(group, values[position(*i)].clone())?;
+            }
+            count += 1;
         }
 
-        Ok(())
+        info!("表 {} 洗牌列读取完成, 共 {} 行", table.name, count);
+        Ok(shufflers)
     }
 
     #[allow(dead_code)]
@@ -273,12 +523,7 @@ impl DbCopier {
             table.name
         );
 
-        let row = self
-            .source_client
-            .client
-            .query_one(&sql, &[])
-            .await
-            .map_err(|e| DbError::Query(e.to_string()))?;
+        let row = self.source_client.query_one(&sql, &[]).await?;
 
         let create_sql: String = row.get("create_sql");
         Ok(create_sql)
@@ -295,41 +540,7 @@ impl DbCopier {
         }
 
         // 如果缓存中没有，则从数据库获取
-        let rows = self.source_client.client
-            .query(
-                "SELECT column_name, data_type, is_nullable, column_default, character_maximum_length
-                 FROM information_schema.columns 
-                 WHERE table_schema = 'public' AND table_name = $1
-                 ORDER BY ordinal_position",
-                &[&table_name],
-            )
-            .await
-            .map_err(|e| DbError::Query(e.to_string()))?;
-
-        let mut columns = Vec::new();
-        for row in rows {
-            let is_nullable: String = row.get("is_nullable");
-            columns.push(ColumnInfo {
-                name: row.get("column_name"),
-                data_type: row.get("data_type"),
-                is_nullable: is_nullable.eq_ignore_ascii_case("YES"),
-                column_default: row.get("column_default"),
-                character_maximum_length: row.get("character_maximum_length"),
-            });
-        }
-
-        // 获取索引信息
-        let indexes = self.get_table_indexes(table_name).await?;
-
-        // 获取约束信息
-        let constraints = self.get_table_constraints(table_name).await?;
-
-        let info = TableInfo {
-            name: table_name.to_string(),
-            columns,
-            indexes,
-            constraints,
-        };
+        let info = TableInfo::load(&self.source_client, table_name).await?;
 
         // 更新缓存
         self.table_info_cache
@@ -340,43 +551,23 @@ impl DbCopier {
         Ok(info)
     }
 
-    async fn get_table_indexes(&self, table_name: &str) -> Result<Vec<String>, DbError> {
-        let rows = self
-            .source_client
-            .client
-            .query(
-                "SELECT indexdef FROM pg_indexes WHERE tablename = $1",
-                &[&table_name],
-            )
-            .await
-            .map_err(|e| DbError::Query(e.to_string()))?;
-
-        Ok(rows.iter().map(|row| row.get(0)).collect())
-    }
-
-    async fn get_table_constraints(&self, table_name: &str) -> Result<Vec<String>, DbError> {
-        let rows = self.source_client.client
-            .query(
-                "SELECT pg_get_constraintdef(c.oid) as constraint_def
-                 FROM pg_constraint c
-                 JOIN pg_namespace n ON n.oid = c.connamespace
-                 WHERE conrelid = (SELECT oid FROM pg_class WHERE relname = $1 AND relnamespace = (SELECT oid FROM pg_namespace WHERE nspname = 'public'))
-                 AND n.nspname = 'public'",
-                &[&table_name],
-            )
+    pub async fn sync_table_structure(
+        &self,
+        table_info: &TableInfo,
+        ignore_foreign_keys: bool,
+    ) -> Result<(), DbError> {
+        self.sync_structure_on(&self.target_client, table_info, ignore_foreign_keys)
             .await
-            .map_err(|e| DbError::Query(e.to_string()))?;
-
-        Ok(rows.iter().map(|row| row.get(0)).collect())
     }
 
-    pub async fn sync_table_structure(
+    async fn sync_structure_on(
         &self,
+        target_client: &DbClient,
         table_info: &TableInfo,
         ignore_foreign_keys: bool,
     ) -> Result<(), DbError> {
         // 删除目标表(如果存在)
-        self.target_client
+        target_client
             .client
             .execute(
                 &format!("DROP TABLE IF EXISTS \"{}\" CASCADE", table_info.name),
@@ -393,7 +584,7 @@ impl DbCopier {
             .columns
             .iter()
             .map(|col| {
-                let mut def = format!("    \"{}\" {}", col.name, col.data_type);
+                let mut def = format!("    \"{}\" {}", col.name, col.column_type);
                 if !col.is_nullable {
                     def.push_str(" NOT NULL");
                 }
@@ -407,7 +598,7 @@ impl DbCopier {
         create_table.push_str(&column_defs.join(",\n"));
         create_table.push_str("\n)");
 
-        self.target_client
+        target_client
             .client
             .execute(&create_table, &[])
             .await
@@ -418,7 +609,7 @@ impl DbCopier {
             for constraint in &table_info.constraints {
                 // 跳过外键约束
                 if !constraint.contains("FOREIGN KEY") {
-                    self.target_client
+                    target_client
                         .client
                         .execute(
                             &format!("ALTER TABLE \"{}\" ADD {}", table_info.name, constraint),
@@ -432,7 +623,7 @@ impl DbCopier {
 
         // 添加索引
         for index in &table_info.indexes {
-            self.target_client
+            target_client
                 .client
                 .execute(index, &[])
                 .await
@@ -442,64 +633,269 @@ impl DbCopier {
         Ok(())
     }
 
-    #[allow(dead_code)]
-    pub async fn copy_tables(&self, tables: &[TableConfig]) -> Result<(), DbError> {
-        // 限制并发数
-        let semaphore = Arc::new(Semaphore::new(4));
-
-        let results = stream::iter(tables)
-            .map(|table| {
-                let sem = semaphore.clone();
-                let table = table.clone();
+    /// 并行复制多张表，出错时停止其余表的复制并返回出错的表名
+    ///
+    /// 每个工作会话使用独立的目标库连接，源库会话导入协调会话的快照，
+    /// 所有表读取同一时间点的数据。各表开始和完成时通过 `events` 通知进度。
+    pub async fn copy_tables(
+        &self,
+        tables: &[TableConfig],
+        events: UnboundedSender<CopyEvent>,
+    ) -> Result<(), CopyTablesError> {
+        // 每个工作会话从共享队列中依次取表复制
+        let queue = Mutex::new(tables.iter());
+        let workers = MAX_COPY_WORKERS.min(tables.len());
+        let events = &events;
+
+        stream::iter(0..workers)
+            .map(|worker| {
+                let queue = &queue;
                 async move {
-                    let _permit = sem.acquire().await.unwrap();
-                    self.copy_table(&table).await
+                    let connect_error = |error| CopyTablesError { table: None, error };
+                    let source_client = SourceClient::connect(&self.source_config)
+                        .await
+                        .map_err(connect_error)?;
+                    source_client
+                        .begin_with_snapshot(&self.snapshot)
+                        .await
+                        .map_err(connect_error)?;
+                    let target_client = DbClient::new(&self.target_config)
+                        .await
+                        .map_err(connect_error)?;
+                    info!("复制工作会话 {} 已就绪", worker + 1);
+
+                    loop {
+                        let Some(table) = queue.lock().await.next() else {
+                            break;
+                        };
+                        let _ = events.send(CopyEvent::Started {
+                            table: table.name.clone(),
+                        });
+                        let rows = self
+                            .copy_table_with(&source_client, &target_client, table)
+                            .await
+                            .map_err(|error| CopyTablesError {
+                                table: Some(table.name.clone()),
+                                error,
+                            })?;
+                        let _ = events.send(CopyEvent::Finished {
+                            table: table.name.clone(),
+                            rows,
+                        });
+                    }
+                    Ok(())
                 }
             })
-            .buffer_unordered(4) // 最多4个并发任务
-            .collect::<Vec<_>>()
-            .await;
-
-        // 检查是否有错误
-        for result in results {
-            if let Err(e) = result {
-                return Err(e);
-            }
-        }
+            .buffer_unordered(workers.max(1))
+            .try_collect::<Vec<()>>()
+            .await?;
 
         Ok(())
     }
+}
 
-    #[allow(dead_code)]
-    pub async fn copy_table_data(
-        source_client: &Client,
-        target_client: &Client,
-        table_name: &str,
-        columns: &[String],
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let select_sql = format!("SELECT {} FROM {}", columns.join(", "), table_name);
-        let source_rows = source_client.query(&select_sql, &[]).await?;
-
-        let insert_sql = format!(
-            "INSERT INTO {} ({}) VALUES ({})",
-            table_name,
-            columns.join(", "),
-            (1..=columns.len())
-                .map(|i| format!("${}", i))
-                .collect::<Vec<_>>()
-                .join(", ")
-        );
+async fn get_table_indexes(client: &SourceClient, table_name: &str) -> Result<Vec<String>, DbError> {
+    let rows = client
+        .query(
+            "SELECT indexdef FROM pg_indexes WHERE tablename = $1",
+            &[&table_name],
+        )
+        .await?;
+
+    Ok(rows.iter().map(|row| row.get(0)).collect())
+}
+
+async fn get_table_constraints(client: &SourceClient, table_name: &str) -> Result<Vec<String>, DbError> {
+    let rows = client
+        .query(
+            "SELECT pg_get_constraintdef(c.oid) as constraint_def
+             FROM pg_constraint c
+             JOIN pg_namespace n ON n.oid = c.connamespace
+             WHERE conrelid = (SELECT oid FROM pg_class WHERE relname = $1 AND relnamespace = (SELECT oid FROM pg_namespace WHERE nspname = 'public'))
+             AND n.nspname = 'public'",
+            &[&table_name],
+        )
+        .await?;
+
+    Ok(rows.iter().map(|row| row.get(0)).collect())
+}
+
+/// 有长度限制的列（varchar(n)、char(n) 等）的最大字符数
+async fn get_column_max_lengths(client: &SourceClient, table_name: &str) -> Result<Vec<(String, usize)>, DbError> {
+    let rows = client
+        .query(
+            "SELECT column_name, character_maximum_length
+             FROM information_schema.columns
+             WHERE table_schema = 'public' AND table_name = $1
+               AND character_maximum_length IS NOT NULL",
+            &[&table_name],
+        )
+        .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| (row.get(0), row.get::<_, i32>(1) as usize))
+        .collect())
+}
 
-        for row in source_rows {
-            let mut values: Vec<Box<dyn ToSql + Sync>> = Vec::new();
-            for column in columns {
-                let value: String = row.get(column.as_str());
-                values.push(Box::new(value));
+async fn get_foreign_keys(client: &SourceClient, table_name: &str) -> Result<Vec<ForeignKeyColumn>, DbError> {
+    let rows = client
+        .query(
+            "SELECT cl.relname AS table_name, a.attname AS column_name,
+                    rcl.relname AS ref_table, ra.attname AS ref_column
+             FROM pg_constraint c
+             JOIN pg_class cl ON cl.oid = c.conrelid
+             JOIN pg_namespace n ON n.oid = cl.relnamespace
+             JOIN pg_class rcl ON rcl.oid = c.confrelid
+             CROSS JOIN LATERAL unnest(c.conkey, c.confkey) AS k(attnum, ref_attnum)
+             JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = k.attnum
+             JOIN pg_attribute ra ON ra.attrelid = c.confrelid AND ra.attnum = k.ref_attnum
+             WHERE c.contype = 'f' AND n.nspname = 'public' AND cl.relname = $1",
+            &[&table_name],
+        )
+        .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| ForeignKeyColumn {
+            table: row.get("table_name"),
+            column: row.get("column_name"),
+            ref_table: row.get("ref_table"),
+            ref_column: row.get("ref_column"),
+        })
+        .collect())
+}
+
+/// 收集各表列上的脱敏域，并检查外键两端的脱敏配置
+///
+/// 脱敏域需要看到所有表的列类型才能确定输出形态，因此总是针对整个配置计算
+pub async fn resolve_mask_domains(
+    client: &SourceClient,
+    tables: &[TableConfig],
+) -> Result<HashMap<String, MaskDomain>, DbError> {
+    let mut masked = Vec::new();
+    let mut fields = Vec::new();
+    let mut foreign_keys = Vec::new();
+    let mut max_lengths = HashMap::new();
+
+    for table in tables.iter().filter(|t| !t.structure_only) {
+        let columns: Vec<_> = table.columns.iter().filter(|c| c.is_masked()).collect();
+
+        if !columns.is_empty() {
+            let names: Vec<&str> = columns.iter().map(|c| c.name.as_str()).collect();
+            let types = resolve_column_types(client, &table.name, &names).await?;
+            for (column, max_length) in get_column_max_lengths(client, &table.name).await? {
+                max_lengths.insert((table.name.as_str(), column), max_length);
+            }
+            for (column, ty) in columns.into_iter().zip(types) {
+                // 数组按元素脱敏，字段规则按字段类型脱敏，脱敏域由实际处理的值类型决定
+                if let Some(rule) = &column.mask_rule {
+                    masked.push((table.name.as_str(), column.name.clone(), rule, value_type(&ty)));
+                }
+                for field_rule in &column.field_rules {
+                    let field_ty = field_type(&ty, &field_rule.field).ok_or_else(|| {
+                        DbError::Mask(format!(
+                            "列 {} 的类型 {} 中不存在字段 {}",
+                            column.name, ty, field_rule.field
+                        ))
+                    })?;
+                    fields.push((
+                        table.name.as_str(),
+                        format!("{}.{}", column.name, field_rule.field),
+                        &field_rule.rule,
+                        field_ty,
+                    ));
+                }
             }
-            let params: Vec<&(dyn ToSql + Sync)> = values.iter().map(|v| v.as_ref()).collect();
-            target_client.execute(&insert_sql, &params[..]).await?;
         }
 
-        Ok(())
+        foreign_keys.extend(get_foreign_keys(client, &table.name).await?);
     }
+
+    let columns: Vec<DomainColumn> = masked
+        .iter()
+        .map(|(table, column, rule, ty)| DomainColumn {
+            table,
+            column,
+            rule,
+            ty,
+        })
+        .collect();
+
+    // JSON 路径规则的内层规则同样可以引用脱敏域，匹配到的值按文本处理
+    let text_type = Type::TEXT;
+    let mut domain_columns: Vec<DomainColumn> = masked
+        .iter()
+        .chain(fields.iter())
+        .filter_map(|(table, column, rule, _)| {
+            rule.json_options.as_ref().map(|options| DomainColumn {
+                table,
+                column,
+                rule: &options.rule,
+                ty: &text_type,
+            })
+        })
+        .collect();
+    domain_columns.extend(columns.iter().copied());
+    domain_columns.extend(fields.iter().map(|(table, column, rule, ty)| DomainColumn {
+        table,
+        column,
+        rule,
+        ty,
+    }));
+
+    let domains = build_domains(&domain_columns)?;
+    check_foreign_keys(&columns, &foreign_keys)?;
+
+    // 哈希结果的长度固定，超出 varchar(n) 等列的长度时复制必然失败，在配置阶段就拒绝
+    for (table, column, rule, ty) in &masked {
+        if !matches!(rule.rule_type, MaskRuleType::Hash | MaskRuleType::Hmac) || !is_text_type(ty) {
+            continue;
+        }
+        let Some(max_length) = max_lengths.get(&(*table, column.clone())) else {
+            continue;
+        };
+        let domain = rule.domain.as_ref().and_then(|d| domains.get(d));
+        let length = hash_text_length(rule.hash_options.as_ref(), domain);
+        if length > *max_length {
+            return Err(DbError::Mask(format!(
+                "列 {}.{} 最长 {} 个字符，哈希结果有 {} 个字符，请将哈希输出长度设置为不超过 {}",
+                table, column, max_length, length, max_length
+            )));
+        }
+    }
+
+    info!("脱敏准备完成: {} 个脱敏列, {} 个脱敏域", columns.len() + fields.len(), domains.len());
+    Ok(domains)
+}
+
+/// 通过预编译查询取得列的完整类型
+///
+/// 与 `TableInfo::column_type` 不同，自定义类型会带上复合类型的字段定义和数组的元素类型，
+/// 供数组与复合类型列的脱敏使用
+pub async fn resolve_column_types(
+    client: &SourceClient,
+    table: &str,
+    columns: &[&str],
+) -> Result<Vec<Type>, DbError> {
+    if columns.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let statement = client
+        .prepare(&column_types_query(table, columns))
+        .await
+        .map_err(|e| DbError::Query(format!("读取表 {} 的列类型失败: {}", table, e.message())))?;
+
+    Ok(statement.columns().iter().map(|c| c.type_().clone()).collect())
+}
+
+/// 只取列类型不取数据的查询，预编译后从语句的列描述中得到完整类型
+fn column_types_query(table: &str, columns: &[&str]) -> String {
+    let select = columns
+        .iter()
+        .map(|c| format!("\"{}\"", c))
+        .collect::<Vec<String>>()
+        .join(", ");
+    format!("SELECT {} FROM \"{}\" LIMIT 0", select, table)
 }
@@ -0,0 +1,216 @@
+// 脱敏审计报告：汇总一次复制任务中每一列的脱敏方式，供合规审核
+
+use crate::database::{AuditColumn, AuditTable, Config, MaskAuditReport, MaskRule, UnmaskedColumn};
+use crate::services::masking::pii::sensitive_column_name;
+use crate::services::masking::EffectiveMaskRule;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// 根据已应用策略的配置和各表复制的行数生成审计报告
+///
+/// `mask_rules` 为 `apply_mask_policies` 返回的生效规则，`rows` 中没有的表视为未复制。
+pub fn build_audit_report(
+    task_id: &str,
+    config: &Config,
+    mask_rules: &[EffectiveMaskRule],
+    rows: &HashMap<String, u64>,
+    completed: bool,
+    generated_at: String,
+) -> MaskAuditReport {
+    let mut tables = Vec::with_capacity(config.tables.len());
+    let mut unmasked_sensitive = Vec::new();
+
+    for table in &config.tables {
+        let mut columns = Vec::with_capacity(table.columns.len());
+        for column in &table.columns {
+            // 复合类型列的字段规则以 `列.字段` 记录
+            let field_prefix = format!("{}.", column.name);
+            let rules: Vec<&EffectiveMaskRule> = mask_rules
+                .iter()
+                .filter(|r| {
+                    r.table == table.name
+                        && (r.column == column.name || r.column.starts_with(&field_prefix))
+                })
+                .collect();
+
+            if rules.is_empty() {
+                columns.push(AuditColumn {
+                    column: column.name.clone(),
+                    rule: None,
+                    policy: None,
+                });
+                if !table.structure_only {
+                    if let Some((kind, confidence, reason)) = sensitive_column_name(&column.name) {
+                        unmasked_sensitive.push(UnmaskedColumn {
+                            table: table.name.clone(),
+                            column: column.name.clone(),
+                            kind,
+                            confidence,
+                            reason,
+                        });
+                    }
+                }
+                continue;
+            }
+
+            columns.extend(rules.into_iter().map(|r| AuditColumn {
+                column: r.column.clone(),
+                rule: Some(r.rule.clone()),
+                policy: r.policy.clone(),
+            }));
+        }
+
+        tables.push(AuditTable {
+            table: table.name.clone(),
+            structure_only: table.structure_only,
+            rows: rows.get(&table.name).copied(),
+            columns,
+        });
+    }
+
+    MaskAuditReport {
+        task_id: task_id.to_string(),
+        config_name: config.name.clone(),
+        generated_at,
+        completed,
+        tables,
+        unmasked_sensitive,
+    }
+}
+
+/// 将审计报告渲染为独立的 HTML 页面
+pub fn render_audit_html(report: &MaskAuditReport) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!(
+        "<title>脱敏审计报告 {}</title>\n",
+        escape_html(&report.task_id)
+    ));
+    html.push_str(
+        "<style>\
+         body{font-family:sans-serif;margin:24px;color:#222}\
+         table{border-collapse:collapse;margin-bottom:24px;width:100%}\
+         th,td{border:1px solid #ccc;padding:4px 8px;text-align:left;vertical-align:top}\
+         th{background:#f3f3f3}\
+         code{font-size:12px;white-space:pre-wrap;word-break:break-all}\
+         .unmasked{color:#999}\
+         .warning{color:#b00}\
+         </style>\n</head>\n<body>\n",
+    );
+
+    html.push_str("<h1>脱敏审计报告</h1>\n<table>\n");
+    let summary = [
+        ("任务", report.task_id.clone()),
+        ("配置", report.config_name.clone()),
+        ("生成时间", report.generated_at.clone()),
+        (
+            "状态",
+            if report.completed {
+                "复制完成"
+            } else {
+                "复制未完成"
+            }
+            .to_string(),
+        ),
+        ("表数量", report.tables.len().to_string()),
+        (
+            "脱敏列数量",
+            report
+                .tables
+                .iter()
+                .flat_map(|t| &t.columns)
+                .filter(|c| c.rule.is_some())
+                .count()
+                .to_string(),
+        ),
+    ];
+    for (label, value) in summary {
+        html.push_str(&format!(
+            "<tr><th>{}</th><td>{}</td></tr>\n",
+            label,
+            escape_html(&value)
+        ));
+    }
+    html.push_str("</table>\n");
+
+    if !report.unmasked_sensitive.is_empty() {
+        html.push_str("<h2 class=\"warning\">疑似敏感但未脱敏的列</h2>\n<table>\n");
+        html.push_str("<tr><th>表</th><th>列</th><th>类别</th><th>置信度</th><th>依据</th></tr>\n");
+        for column in &report.unmasked_sensitive {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{:.2}</td><td>{}</td></tr>\n",
+                escape_html(&column.table),
+                escape_html(&column.column),
+                serde_name(&column.kind),
+                column.confidence,
+                escape_html(&column.reason)
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+
+    for table in &report.tables {
+        let rows = match (table.structure_only, table.rows) {
+            (true, _) => "仅复制结构".to_string(),
+            (false, Some(rows)) => format!("{} 行", rows),
+            (false, None) => "未复制".to_string(),
+        };
+        html.push_str(&format!(
+            "<h2>{} <small>（{}）</small></h2>\n<table>\n",
+            escape_html(&table.table),
+            rows
+        ));
+        html.push_str("<tr><th>列</th><th>脱敏规则</th><th>来源策略</th><th>规则配置</th></tr>\n");
+        for column in &table.columns {
+            match &column.rule {
+                Some(rule) => html.push_str(&format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td><code>{}</code></td></tr>\n",
+                    escape_html(&column.column),
+                    escape_html(&rule_name(rule)),
+                    escape_html(column.policy.as_deref().unwrap_or("-")),
+                    escape_html(&serde_json::to_string(rule).unwrap_or_default())
+                )),
+                None => html.push_str(&format!(
+                    "<tr class=\"unmasked\"><td>{}</td><td>原样复制</td><td>-</td><td></td></tr>\n",
+                    escape_html(&column.column)
+                )),
+            }
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// 规则类型的名称，配置了脱敏域时附带域名
+fn rule_name(rule: &MaskRule) -> String {
+    let name = serde_name(&rule.rule_type);
+    match &rule.domain {
+        Some(domain) => format!("{} (域 {})", name, domain),
+        None => name,
+    }
+}
+
+/// 枚举值序列化后的名称，与配置文件中的写法一致
+fn serde_name<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
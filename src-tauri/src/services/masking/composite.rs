@@ -0,0 +1,463 @@
+// 数组与复合类型的二进制线格式
+//
+// 数组：维数、是否含 NULL 标志、元素类型 OID，每一维的长度与下界，随后是各元素
+// （4 字节长度，NULL 为 -1，加数据）。复合类型：字段数，随后每个字段依次为
+// 字段类型 OID、4 字节长度与数据。脱敏只替换元素与字段的数据，其余部分原样写回，
+// 因此维数、下界和 NULL 元素都保持不变。
+//
+// 嵌入的 OID 在源库和目标库之间未必相同：枚举、复合类型、域等用户自定义类型在各库中
+// 的 OID 不同，写入目标库前需按目标库中同名类型的 OID 改写。
+
+use tokio_postgres::types::{Kind, Type};
+
+/// 去掉域类型的包装，得到值实际使用的类型
+pub fn storage_type(ty: &Type) -> Type {
+    match ty.kind() {
+        Kind::Domain(base) => storage_type(base),
+        _ => ty.clone(),
+    }
+}
+
+/// 数组类型的元素类型
+pub fn element_type(ty: &Type) -> Option<Type> {
+    match ty.kind() {
+        Kind::Array(element) => Some(storage_type(element)),
+        Kind::Domain(base) => element_type(base),
+        _ => None,
+    }
+}
+
+/// 逐值规则实际作用的类型：数组取元素类型
+pub fn value_type(ty: &Type) -> Type {
+    element_type(ty).unwrap_or_else(|| storage_type(ty))
+}
+
+/// 字段规则实际作用的类型，`path` 为以 `.` 分隔的字段路径，字段不存在时返回 None
+pub fn field_type(ty: &Type, path: &str) -> Option<Type> {
+    let mut ty = value_type(ty);
+    for name in path.split('.') {
+        let field = match ty.kind() {
+            Kind::Composite(fields) => fields.iter().find(|f| f.name() == name)?,
+            _ => return None,
+        };
+        ty = value_type(field.type_());
+    }
+    Some(ty)
+}
+
+/// 值的线格式中是否嵌入了与目标库不同的类型 OID
+///
+/// `source` 和 `target` 为同一列分别在源库和目标库中的类型
+pub fn embeds_foreign_oids(source: &Type, target: &Type) -> bool {
+    let (source, target) = (storage_type(source), storage_type(target));
+    match (source.kind(), target.kind()) {
+        (Kind::Array(source), Kind::Array(target)) => {
+            source.oid() != target.oid() || embeds_foreign_oids(source, target)
+        }
+        (Kind::Composite(source), Kind::Composite(target)) => {
+            source.len() != target.len()
+                || source.iter().zip(target).any(|(s, t)| {
+                    s.type_().oid() != t.type_().oid() || embeds_foreign_oids(s.type_(), t.type_())
+                })
+        }
+        _ => false,
+    }
+}
+
+/// 将数组元素和复合类型字段的类型 OID 改写为目标库中的 OID，逐层处理嵌套的数组和复合类型
+pub fn remap_oids(bytes: &[u8], source: &Type, target: &Type) -> Result<Vec<u8>, String> {
+    let (source, target) = (storage_type(source), storage_type(target));
+    match (source.kind(), target.kind()) {
+        (Kind::Array(source), Kind::Array(target)) => {
+            let mut array = ArrayValue::decode(bytes)?;
+            array.element_oid = target.oid();
+            if embeds_foreign_oids(source, target) {
+                for element in array.elements.iter_mut().flatten() {
+                    *element = remap_oids(element, source, target)?;
+                }
+            }
+            Ok(array.encode())
+        }
+        (Kind::Composite(source_fields), Kind::Composite(target_fields)) => {
+            let mut record = RecordValue::decode(bytes)?;
+            if record.fields.len() != target_fields.len() {
+                return Err(format!(
+                    "复合类型 {} 在目标库中有 {} 个字段，源数据有 {} 个",
+                    target,
+                    target_fields.len(),
+                    record.fields.len()
+                ));
+            }
+            for ((oid, value), (s, t)) in record
+                .fields
+                .iter_mut()
+                .zip(source_fields.iter().zip(target_fields))
+            {
+                *oid = t.type_().oid();
+                if let Some(value) = value {
+                    if embeds_foreign_oids(s.type_(), t.type_()) {
+                        *value = remap_oids(value, s.type_(), t.type_())?;
+                    }
+                }
+            }
+            Ok(record.encode())
+        }
+        _ => Ok(bytes.to_vec()),
+    }
+}
+
+/// 解码后的数组值
+pub struct ArrayValue {
+    dims: Vec<(i32, i32)>,
+    element_oid: u32,
+    pub elements: Vec<Option<Vec<u8>>>,
+}
+
+impl ArrayValue {
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader::new(bytes);
+        let ndim = reader.i32()?;
+        if ndim < 0 {
+            return Err(format!("数组维数无效: {}", ndim));
+        }
+        let _has_null = reader.i32()?;
+        let element_oid = reader.u32()?;
+
+        // 容量按剩余数据可容纳的上限截断，损坏的维数不会导致巨量分配
+        let mut dims = Vec::with_capacity((ndim as usize).min(bytes.len() / 8));
+        let mut count: usize = if ndim == 0 { 0 } else { 1 };
+        for _ in 0..ndim {
+            let len = reader.i32()?;
+            let lower_bound = reader.i32()?;
+            if len < 0 {
+                return Err(format!("数组长度无效: {}", len));
+            }
+            count = count
+                .checked_mul(len as usize)
+                .ok_or_else(|| "数组元素数量溢出".to_string())?;
+            dims.push((len, lower_bound));
+        }
+
+        let mut elements = Vec::with_capacity(count.min(bytes.len() / 4));
+        for _ in 0..count {
+            elements.push(reader.value()?);
+        }
+        reader.finish()?;
+
+        Ok(Self {
+            dims,
+            element_oid,
+            elements,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let has_null = self.elements.iter().any(Option::is_none);
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(self.dims.len() as i32).to_be_bytes());
+        bytes.extend_from_slice(&(has_null as i32).to_be_bytes());
+        bytes.extend_from_slice(&self.element_oid.to_be_bytes());
+        for (len, lower_bound) in &self.dims {
+            bytes.extend_from_slice(&len.to_be_bytes());
+            bytes.extend_from_slice(&lower_bound.to_be_bytes());
+        }
+        for element in &self.elements {
+            write_value(&mut bytes, element.as_deref());
+        }
+        bytes
+    }
+}
+
+/// 解码后的复合类型值，字段按类型定义中的顺序排列
+pub struct RecordValue {
+    pub fields: Vec<(u32, Option<Vec<u8>>)>,
+}
+
+impl RecordValue {
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader::new(bytes);
+        let count = reader.i32()?;
+        if count < 0 {
+            return Err(format!("复合类型字段数无效: {}", count));
+        }
+
+        let mut fields = Vec::with_capacity((count as usize).min(bytes.len() / 8));
+        for _ in 0..count {
+            let oid = reader.u32()?;
+            fields.push((oid, reader.value()?));
+        }
+        reader.finish()?;
+
+        Ok(Self { fields })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(self.fields.len() as i32).to_be_bytes());
+        for (oid, value) in &self.fields {
+            bytes.extend_from_slice(&oid.to_be_bytes());
+            write_value(&mut bytes, value.as_deref());
+        }
+        bytes
+    }
+}
+
+fn write_value(bytes: &mut Vec<u8>, value: Option<&[u8]>) {
+    match value {
+        Some(value) => {
+            bytes.extend_from_slice(&(value.len() as i32).to_be_bytes());
+            bytes.extend_from_slice(value);
+        }
+        None => bytes.extend_from_slice(&(-1i32).to_be_bytes()),
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| "数据长度不足".to_string())?;
+        let slice = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(slice)
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        Ok(i32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    /// 带长度前缀的值，长度为 -1 表示 NULL
+    fn value(&mut self) -> Result<Option<Vec<u8>>, String> {
+        let len = self.i32()?;
+        if len < 0 {
+            return Ok(None);
+        }
+        Ok(Some(self.take(len as usize)?.to_vec()))
+    }
+
+    fn finish(&self) -> Result<(), String> {
+        if self.offset != self.bytes.len() {
+            return Err(format!(
+                "数据末尾有 {} 字节多余内容",
+                self.bytes.len() - self.offset
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_postgres::types::Field;
+
+    fn int4(v: i32) -> Option<Vec<u8>> {
+        Some(v.to_be_bytes().to_vec())
+    }
+
+    fn array(dims: &[(i32, i32)], element_oid: u32, elements: &[Option<Vec<u8>>]) -> Vec<u8> {
+        ArrayValue {
+            dims: dims.to_vec(),
+            element_oid,
+            elements: elements.to_vec(),
+        }
+        .encode()
+    }
+
+    fn record(fields: &[(u32, Option<Vec<u8>>)]) -> Vec<u8> {
+        RecordValue {
+            fields: fields.to_vec(),
+        }
+        .encode()
+    }
+
+    fn composite(name: &str, oid: u32, fields: Vec<(&str, Type)>) -> Type {
+        let fields = fields
+            .into_iter()
+            .map(|(name, ty)| Field::new(name.to_string(), ty))
+            .collect();
+        Type::new(
+            name.to_string(),
+            oid,
+            Kind::Composite(fields),
+            "public".to_string(),
+        )
+    }
+
+    fn array_of(element: &Type, oid: u32) -> Type {
+        Type::new(
+            format!("_{}", element.name()),
+            oid,
+            Kind::Array(element.clone()),
+            "public".to_string(),
+        )
+    }
+
+    fn enumeration(oid: u32) -> Type {
+        Type::new(
+            "color".to_string(),
+            oid,
+            Kind::Enum(vec!["red".to_string()]),
+            "public".to_string(),
+        )
+    }
+
+    #[test]
+    fn arrays_round_trip() {
+        // 2x3 数组，下界分别为 0 和 -2，含 NULL 元素
+        let elements = [int4(1), None, int4(3), int4(4), int4(5), None];
+        let bytes = array(&[(2, 0), (3, -2)], Type::INT4.oid(), &elements);
+        let decoded = ArrayValue::decode(&bytes).unwrap();
+        assert_eq!(decoded.dims, [(2, 0), (3, -2)]);
+        assert_eq!(decoded.element_oid, Type::INT4.oid());
+        assert_eq!(decoded.elements, elements);
+        assert_eq!(decoded.encode(), bytes);
+        // has_null 标志按元素重新计算
+        assert_eq!(bytes[4..8], 1i32.to_be_bytes());
+
+        let empty = array(&[], Type::TEXT.oid(), &[]);
+        let decoded = ArrayValue::decode(&empty).unwrap();
+        assert!(decoded.elements.is_empty());
+        assert_eq!(decoded.encode(), empty);
+    }
+
+    #[test]
+    fn records_round_trip() {
+        let inner = record(&[(Type::INT4.oid(), int4(7)), (Type::TEXT.oid(), None)]);
+        let items = array(&[(2, 1)], 70000, &[Some(inner.clone()), None]);
+        let bytes = record(&[
+            (Type::TEXT.oid(), Some(b"name".to_vec())),
+            (70000, Some(inner.clone())),
+            (70001, Some(items.clone())),
+            (Type::INT4.oid(), None),
+        ]);
+
+        let decoded = RecordValue::decode(&bytes).unwrap();
+        assert_eq!(decoded.fields.len(), 4);
+        assert_eq!(decoded.fields[1], (70000, Some(inner.clone())));
+        assert_eq!(decoded.fields[3], (Type::INT4.oid(), None));
+        assert_eq!(decoded.encode(), bytes);
+
+        let nested = RecordValue::decode(decoded.fields[1].1.as_ref().unwrap()).unwrap();
+        assert_eq!(nested.fields[0], (Type::INT4.oid(), int4(7)));
+        let items = ArrayValue::decode(decoded.fields[2].1.as_ref().unwrap()).unwrap();
+        assert_eq!(items.elements, [Some(inner), None]);
+    }
+
+    #[test]
+    fn rejects_corrupt_input() {
+        let bytes = array(
+            &[(2, 1), (2, 1)],
+            Type::INT4.oid(),
+            &[int4(1), None, int4(3), int4(4)],
+        );
+        for len in 0..bytes.len() {
+            assert!(ArrayValue::decode(&bytes[..len]).is_err(), "{}", len);
+        }
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(ArrayValue::decode(&trailing).is_err());
+
+        let bytes = record(&[(Type::INT4.oid(), int4(1)), (Type::TEXT.oid(), None)]);
+        for len in 0..bytes.len() {
+            assert!(RecordValue::decode(&bytes[..len]).is_err(), "{}", len);
+        }
+
+        let header =
+            |values: &[i32]| -> Vec<u8> { values.iter().flat_map(|v| v.to_be_bytes()).collect() };
+        // 负数维数、负数长度、巨大的维数或字段数、溢出的元素数量
+        assert!(ArrayValue::decode(&header(&[-1, 0, 23])).is_err());
+        assert!(ArrayValue::decode(&header(&[1, 0, 23, -1, 1])).is_err());
+        assert!(ArrayValue::decode(&header(&[i32::MAX, 0, 23])).is_err());
+        assert!(
+            ArrayValue::decode(&header(&[3, 0, 23, i32::MAX, 1, i32::MAX, 1, i32::MAX, 1]))
+                .is_err()
+        );
+        assert!(ArrayValue::decode(&header(&[1, 0, 23, 1, 1, i32::MAX])).is_err());
+        assert!(RecordValue::decode(&header(&[-1])).is_err());
+        assert!(RecordValue::decode(&header(&[i32::MAX])).is_err());
+        assert!(RecordValue::decode(&header(&[1, 23, i32::MAX])).is_err());
+    }
+
+    #[test]
+    fn remaps_embedded_oids() {
+        let pair = |base: u32| {
+            let color = enumeration(base);
+            let point = composite(
+                "pt",
+                base + 1,
+                vec![("x", Type::INT4), ("c", color.clone())],
+            );
+            let shape = composite(
+                "shape",
+                base + 2,
+                vec![
+                    ("origin", point.clone()),
+                    ("colors", array_of(&color, base + 3)),
+                    ("label", Type::TEXT),
+                ],
+            );
+            let shapes = array_of(&shape, base + 4);
+            (color, point, shape, shapes)
+        };
+        let (s_color, s_point, s_shape, s_shapes) = pair(50000);
+        let (_, _, t_shape, t_shapes) = pair(60000);
+
+        let point = record(&[
+            (Type::INT4.oid(), int4(1)),
+            (s_color.oid(), Some(b"red".to_vec())),
+        ]);
+        let colors = array(&[(2, 0)], s_color.oid(), &[Some(b"red".to_vec()), None]);
+        let shape = record(&[
+            (s_point.oid(), Some(point)),
+            (50003, Some(colors)),
+            (Type::TEXT.oid(), Some(b"box".to_vec())),
+        ]);
+        let shapes = array(&[(2, 1)], s_shape.oid(), &[Some(shape), None]);
+
+        assert!(embeds_foreign_oids(&s_shapes, &t_shapes));
+        assert!(!embeds_foreign_oids(&s_shapes, &s_shapes));
+        assert!(!embeds_foreign_oids(&Type::INT4_ARRAY, &Type::INT4_ARRAY));
+
+        let remapped =
+            ArrayValue::decode(&remap_oids(&shapes, &s_shapes, &t_shapes).unwrap()).unwrap();
+        assert_eq!(remapped.element_oid, t_shape.oid());
+        assert_eq!(remapped.dims, [(2, 1)]);
+        assert_eq!(remapped.elements[1], None);
+
+        let shape = RecordValue::decode(remapped.elements[0].as_ref().unwrap()).unwrap();
+        let oids: Vec<u32> = shape.fields.iter().map(|(oid, _)| *oid).collect();
+        assert_eq!(oids, [60001, 60003, Type::TEXT.oid()]);
+        assert_eq!(shape.fields[2].1.as_deref(), Some(&b"box"[..]));
+
+        let point = RecordValue::decode(shape.fields[0].1.as_ref().unwrap()).unwrap();
+        assert_eq!(point.fields[0], (Type::INT4.oid(), int4(1)));
+        assert_eq!(point.fields[1], (60000, Some(b"red".to_vec())));
+
+        let colors = ArrayValue::decode(shape.fields[1].1.as_ref().unwrap()).unwrap();
+        assert_eq!(colors.element_oid, 60000);
+        assert_eq!(colors.dims, [(2, 0)]);
+        assert_eq!(colors.elements, [Some(b"red".to_vec()), None]);
+
+        // 字段数与目标库不一致时报错
+        let short = composite("pt", 60001, vec![("x", Type::INT4)]);
+        let point = record(&[(Type::INT4.oid(), int4(1)), (s_color.oid(), None)]);
+        assert!(remap_oids(&point, &s_point, &short).is_err());
+        assert!(remap_oids(&[0, 0], &s_point, &s_point).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+use crate::database::{is_text_type, DbError, MaskRule};
+use log::warn;
+use std::collections::HashMap;
+use tokio_postgres::types::Type;
+
+/// 脱敏域的输出形态，由域内所有列的类型共同决定
+#[derive(Debug, Clone, PartialEq)]
+pub enum DomainKind {
+    /// 输出文本（按哈希选项编码）
+    Text,
+    /// 输出给定位宽的整数，文本列输出其十进制形式；位宽取域内最窄的整数列
+    Integer(u32),
+    /// 输出 UUID，文本列输出其标准格式
+    Uuid,
+}
+
+/// 命名脱敏域
+///
+/// 同一个 `Config` 中引用同一域名的列，无论位于哪个表、是什么类型，
+/// 相同的输入总是得到相同的输出，从而保证脱敏后关联查询仍然成立。
+#[derive(Debug, Clone)]
+pub struct MaskDomain {
+    pub name: String,
+    pub kind: DomainKind,
+}
+
+/// 参与脱敏域计算的列
+#[derive(Clone, Copy)]
+pub struct DomainColumn<'a> {
+    pub table: &'a str,
+    pub column: &'a str,
+    pub rule: &'a MaskRule,
+    pub ty: &'a Type,
+}
+
+/// 外键的一列映射关系
+#[derive(Debug, Clone)]
+pub struct ForeignKeyColumn {
+    pub table: String,
+    pub column: String,
+    pub ref_table: String,
+    pub ref_column: String,
+}
+
+/// 收集所有列上的脱敏域，并检查域内规则和类型是否一致
+pub fn build_domains(columns: &[DomainColumn]) -> Result<HashMap<String, MaskDomain>, DbError> {
+    let mut grouped: HashMap<&str, Vec<&DomainColumn>> = HashMap::new();
+    for column in columns {
+        if let Some(domain) = column.rule.domain.as_deref() {
+            grouped.entry(domain).or_default().push(column);
+        }
+    }
+
+    let mut domains = HashMap::new();
+    for (name, members) in grouped {
+        let first = members[0];
+        for member in &members[1..] {
+            // 同域的列域名相同，其余配置也必须完全一致
+            if first.rule != member.rule {
+                return Err(DbError::Mask(format!(
+                    "脱敏域 {} 中 {}.{} 与 {}.{} 的规则不一致",
+                    name, first.table, first.column, member.table, member.column
+                )));
+            }
+        }
+
+        let mut int_bits: Option<u32> = None;
+        let mut uuid = false;
+        for member in &members {
+            let ty = member.ty;
+            if *ty == Type::INT2 || *ty == Type::INT4 || *ty == Type::INT8 {
+                let bits = if *ty == Type::INT2 {
+                    16
+                } else if *ty == Type::INT4 {
+                    32
+                } else {
+                    64
+                };
+                int_bits = Some(int_bits.map_or(bits, |b| b.min(bits)));
+            } else if *ty == Type::UUID {
+                uuid = true;
+            } else if !is_text_type(ty) {
+                return Err(DbError::Mask(format!(
+                    "脱敏域 {} 中的列 {}.{} 类型为 {}，只支持文本、整数和 UUID 列",
+                    name, member.table, member.column, ty
+                )));
+            }
+        }
+
+        let kind = match (int_bits, uuid) {
+            (Some(_), true) => {
+                return Err(DbError::Mask(format!(
+                    "脱敏域 {} 不能同时包含整数列和 UUID 列",
+                    name
+                )))
+            }
+            (Some(bits), false) => DomainKind::Integer(bits),
+            (None, true) => DomainKind::Uuid,
+            (None, false) => DomainKind::Text,
+        };
+
+        domains.insert(
+            name.to_string(),
+            MaskDomain {
+                name: name.to_string(),
+                kind,
+            },
+        );
+    }
+
+    Ok(domains)
+}
+
+/// 检查外键两端的脱敏配置，确保脱敏后仍能关联
+///
+/// 两端都脱敏但不在同一个域时关联必然失效，直接报错；只有一端脱敏时给出警告。
+pub fn check_foreign_keys(
+    columns: &[DomainColumn],
+    foreign_keys: &[ForeignKeyColumn],
+) -> Result<(), DbError> {
+    let rules: HashMap<(&str, &str), &MaskRule> = columns
+        .iter()
+        .map(|c| ((c.table, c.column), c.rule))
+        .collect();
+
+    for fk in foreign_keys {
+        let left = rules.get(&(fk.table.as_str(), fk.column.as_str()));
+        let right = rules.get(&(fk.ref_table.as_str(), fk.ref_column.as_str()));
+        match (left, right) {
+            (Some(left), Some(right)) => {
+                if left.domain.is_none() || left.domain != right.domain {
+                    return Err(DbError::Mask(format!(
+                        "外键 {}.{} -> {}.{} 两端都已脱敏，必须使用同一个脱敏域",
+                        fk.table, fk.column, fk.ref_table, fk.ref_column
+                    )));
+                }
+            }
+            (Some(_), None) | (None, Some(_)) => {
+                warn!(
+                    "外键 {}.{} -> {}.{} 只有一端脱敏，复制后将无法关联",
+                    fk.table, fk.column, fk.ref_table, fk.ref_column
+                );
+            }
+            (None, None) => {}
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,236 @@
+// en-US 词库
+
+pub const FIRST_NAMES: &[&str] = &[
+    "James",
+    "Mary",
+    "John",
+    "Patricia",
+    "Robert",
+    "Jennifer",
+    "Michael",
+    "Linda",
+    "William",
+    "Elizabeth",
+    "David",
+    "Barbara",
+    "Richard",
+    "Susan",
+    "Joseph",
+    "Jessica",
+    "Thomas",
+    "Sarah",
+    "Charles",
+    "Karen",
+    "Daniel",
+    "Nancy",
+    "Matthew",
+    "Lisa",
+    "Anthony",
+    "Betty",
+    "Mark",
+    "Margaret",
+    "Donald",
+    "Sandra",
+    "Steven",
+    "Ashley",
+    "Paul",
+    "Emily",
+    "Andrew",
+    "Donna",
+    "Joshua",
+    "Michelle",
+    "Kevin",
+    "Carol",
+    "Brian",
+    "Amanda",
+    "George",
+    "Melissa",
+    "Edward",
+    "Deborah",
+    "Ryan",
+    "Laura",
+    "Jacob",
+    "Rebecca",
+];
+
+pub const LAST_NAMES: &[&str] = &[
+    "Smith",
+    "Johnson",
+    "Williams",
+    "Brown",
+    "Jones",
+    "Garcia",
+    "Miller",
+    "Davis",
+    "Rodriguez",
+    "Martinez",
+    "Hernandez",
+    "Lopez",
+    "Gonzalez",
+    "Wilson",
+    "Anderson",
+    "Thomas",
+    "Taylor",
+    "Moore",
+    "Jackson",
+    "Martin",
+    "Lee",
+    "Perez",
+    "Thompson",
+    "White",
+    "Harris",
+    "Sanchez",
+    "Clark",
+    "Ramirez",
+    "Lewis",
+    "Robinson",
+    "Walker",
+    "Young",
+    "Allen",
+    "King",
+    "Wright",
+    "Scott",
+    "Torres",
+    "Nguyen",
+    "Hill",
+    "Flores",
+    "Green",
+    "Adams",
+    "Nelson",
+    "Baker",
+    "Hall",
+    "Rivera",
+    "Campbell",
+    "Mitchell",
+    "Carter",
+    "Roberts",
+];
+
+pub const STREET_NAMES: &[&str] = &[
+    "Main",
+    "Oak",
+    "Pine",
+    "Maple",
+    "Cedar",
+    "Elm",
+    "Washington",
+    "Lake",
+    "Hill",
+    "Park",
+    "Sunset",
+    "Highland",
+    "Church",
+    "River",
+    "Spring",
+    "Meadow",
+    "Forest",
+    "Lincoln",
+    "Jackson",
+    "Franklin",
+];
+
+pub const STREET_SUFFIXES: &[&str] = &[
+    "St", "Ave", "Blvd", "Rd", "Ln", "Dr", "Ct", "Way", "Pl", "Terrace",
+];
+
+pub const CITIES: &[(&str, &str)] = &[
+    ("Springfield", "IL"),
+    ("Portland", "OR"),
+    ("Austin", "TX"),
+    ("Columbus", "OH"),
+    ("Denver", "CO"),
+    ("Madison", "WI"),
+    ("Raleigh", "NC"),
+    ("Sacramento", "CA"),
+    ("Richmond", "VA"),
+    ("Albany", "NY"),
+    ("Phoenix", "AZ"),
+    ("Nashville", "TN"),
+    ("Boise", "ID"),
+    ("Salem", "MA"),
+    ("Trenton", "NJ"),
+];
+
+pub const COMPANY_WORDS: &[&str] = &[
+    "Acme",
+    "Summit",
+    "Pinnacle",
+    "Horizon",
+    "Vertex",
+    "Blue Ridge",
+    "Evergreen",
+    "Silverline",
+    "Northwind",
+    "Keystone",
+    "Brightway",
+    "Ironwood",
+    "Crescent",
+    "Redwood",
+    "Atlas",
+];
+
+pub const COMPANY_INDUSTRIES: &[&str] = &[
+    "Technologies",
+    "Solutions",
+    "Systems",
+    "Logistics",
+    "Consulting",
+    "Holdings",
+    "Labs",
+    "Partners",
+    "Industries",
+    "Networks",
+    "Health",
+    "Foods",
+];
+
+pub const COMPANY_SUFFIXES: &[&str] = &["Inc.", "LLC", "Corp.", "Ltd.", "Group", "Co."];
+
+pub const LOREM_WORDS: &[&str] = &[
+    "lorem",
+    "ipsum",
+    "dolor",
+    "sit",
+    "amet",
+    "consectetur",
+    "adipiscing",
+    "elit",
+    "sed",
+    "do",
+    "eiusmod",
+    "tempor",
+    "incididunt",
+    "ut",
+    "labore",
+    "et",
+    "dolore",
+    "magna",
+    "aliqua",
+    "enim",
+    "ad",
+    "minim",
+    "veniam",
+    "quis",
+    "nostrud",
+    "exercitation",
+    "ullamco",
+    "laboris",
+    "nisi",
+    "aliquip",
+    "ex",
+    "ea",
+    "commodo",
+    "consequat",
+    "duis",
+    "aute",
+    "irure",
+    "in",
+    "reprehenderit",
+    "voluptate",
+    "velit",
+    "esse",
+    "cillum",
+    "fugiat",
+    "nulla",
+    "pariatur",
+];
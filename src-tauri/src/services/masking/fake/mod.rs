@@ -0,0 +1,147 @@
+// 假数据生成器
+//
+// 词库直接编译进程序，离线可用。生成器由原值的摘要播种，重复执行结果稳定。
+
+mod en_us;
+mod zh_cn;
+
+use crate::database::FakeLocale;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rand_chacha::ChaCha20Rng;
+
+/// 从词库中随机取一项
+fn pick<T: Copy>(rng: &mut ChaCha20Rng, items: &[T]) -> T {
+    // 词库均为非空常量
+    *items.choose(rng).expect("词库不能为空")
+}
+
+/// 生成人名：zh-CN 为姓 + 一到两个字的名，en-US 为 First Last
+pub fn fake_name(rng: &mut ChaCha20Rng, locale: &FakeLocale) -> String {
+    match locale {
+        FakeLocale::ZhCn => {
+            let mut name = pick(rng, zh_cn::SURNAMES).to_string();
+            let given = if rng.gen_bool(0.7) { 2 } else { 1 };
+            for _ in 0..given {
+                name.push_str(pick(rng, zh_cn::GIVEN_NAME_CHARS));
+            }
+            name
+        }
+        FakeLocale::EnUs => format!(
+            "{} {}",
+            pick(rng, en_us::FIRST_NAMES),
+            pick(rng, en_us::LAST_NAMES)
+        ),
+    }
+}
+
+/// 生成地址：zh-CN 精确到楼栋单元，en-US 为门牌、街道、城市、州与邮编
+pub fn fake_address(rng: &mut ChaCha20Rng, locale: &FakeLocale) -> String {
+    match locale {
+        FakeLocale::ZhCn => {
+            let (province, cities) = pick(rng, zh_cn::PROVINCES);
+            let city = pick(rng, cities);
+            // 直辖市不重复输出省级名称
+            let region = if province == city {
+                city.to_string()
+            } else {
+                format!("{}{}", province, city)
+            };
+            format!(
+                "{}{}{}{}{}号{}{}{}栋{}单元{}室",
+                region,
+                pick(rng, zh_cn::DISTRICTS),
+                pick(rng, zh_cn::ROAD_WORDS),
+                pick(rng, zh_cn::ROAD_SUFFIXES),
+                rng.gen_range(1..=999),
+                pick(rng, zh_cn::COMMUNITY_WORDS),
+                pick(rng, zh_cn::COMMUNITY_SUFFIXES),
+                rng.gen_range(1..=30),
+                rng.gen_range(1..=6),
+                rng.gen_range(1..=32) * 100 + rng.gen_range(1..=4),
+            )
+        }
+        FakeLocale::EnUs => {
+            let (city, state) = pick(rng, en_us::CITIES);
+            format!(
+                "{} {} {}, {}, {} {:05}",
+                rng.gen_range(1..=9999),
+                pick(rng, en_us::STREET_NAMES),
+                pick(rng, en_us::STREET_SUFFIXES),
+                city,
+                state,
+                rng.gen_range(10000..=99999),
+            )
+        }
+    }
+}
+
+/// 生成公司名称
+pub fn fake_company(rng: &mut ChaCha20Rng, locale: &FakeLocale) -> String {
+    match locale {
+        FakeLocale::ZhCn => {
+            let (_, cities) = pick(rng, zh_cn::PROVINCES);
+            let city = pick(rng, cities).trim_end_matches('市');
+            format!(
+                "{}{}{}{}",
+                city,
+                pick(rng, zh_cn::COMPANY_WORDS),
+                pick(rng, zh_cn::COMPANY_INDUSTRIES),
+                pick(rng, zh_cn::COMPANY_SUFFIXES),
+            )
+        }
+        FakeLocale::EnUs => {
+            let word = if rng.gen_bool(0.5) {
+                pick(rng, en_us::COMPANY_WORDS)
+            } else {
+                pick(rng, en_us::LAST_NAMES)
+            };
+            format!(
+                "{} {} {}",
+                word,
+                pick(rng, en_us::COMPANY_INDUSTRIES),
+                pick(rng, en_us::COMPANY_SUFFIXES),
+            )
+        }
+    }
+}
+
+/// 生成与原文长度（字符数）相同的无意义文本
+pub fn fake_text(rng: &mut ChaCha20Rng, locale: &FakeLocale, length: usize) -> String {
+    if length == 0 {
+        return String::new();
+    }
+
+    let mut text = String::new();
+    let mut count = 0;
+    let mut words_in_sentence = 0;
+    while count < length {
+        let next = match locale {
+            FakeLocale::ZhCn => {
+                if words_in_sentence >= 4 && rng.gen_bool(0.3) {
+                    words_in_sentence = 0;
+                    pick(rng, zh_cn::TEXT_PUNCTUATION).to_string()
+                } else {
+                    words_in_sentence += 1;
+                    pick(rng, zh_cn::TEXT_PHRASES).to_string()
+                }
+            }
+            FakeLocale::EnUs => {
+                let word = pick(rng, en_us::LOREM_WORDS);
+                if count == 0 {
+                    let mut chars = word.chars();
+                    chars
+                        .next()
+                        .map(|c| c.to_uppercase().chain(chars).collect())
+                        .unwrap_or_default()
+                } else {
+                    format!(" {}", word)
+                }
+            }
+        };
+        count += next.chars().count();
+        text.push_str(&next);
+    }
+
+    text.chars().take(length).collect()
+}
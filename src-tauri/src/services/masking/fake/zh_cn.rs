@@ -0,0 +1,143 @@
+// zh-CN 词库
+
+pub const SURNAMES: &[&str] = &[
+    "王", "李", "张", "刘", "陈", "杨", "黄", "赵", "吴", "周", "徐", "孙", "马", "朱", "胡", "郭",
+    "何", "高", "林", "罗", "郑", "梁", "谢", "宋", "唐", "许", "韩", "冯", "邓", "曹", "彭", "曾",
+    "肖", "田", "董", "袁", "潘", "于", "蒋", "蔡", "余", "杜", "叶", "程", "苏", "魏", "吕", "丁",
+    "任", "沈", "姚", "卢", "姜", "崔", "钟", "谭", "陆", "汪", "范", "金", "欧阳", "司马", "上官",
+    "诸葛",
+];
+
+pub const GIVEN_NAME_CHARS: &[&str] = &[
+    "伟", "芳", "娜", "敏", "静", "丽", "强", "磊", "军", "洋", "勇", "艳", "杰", "娟", "涛", "明",
+    "超", "秀", "霞", "平", "刚", "桂", "英", "华", "慧", "建", "国", "文", "辉", "鑫", "宇", "浩",
+    "然", "子", "涵", "欣", "怡", "梓", "萱", "晨", "轩", "博", "雨", "佳", "琪", "思", "嘉", "俊",
+    "晓", "婷", "志", "鹏", "飞", "丹", "玲", "琳", "倩", "颖", "昊", "天", "泽", "瑞", "凯", "睿",
+    "诗", "雅", "悦", "航", "一", "诺",
+];
+
+pub const PROVINCES: &[(&str, &[&str])] = &[
+    ("北京市", &["北京市"]),
+    ("上海市", &["上海市"]),
+    ("天津市", &["天津市"]),
+    ("重庆市", &["重庆市"]),
+    (
+        "广东省",
+        &["广州市", "深圳市", "佛山市", "东莞市", "珠海市"],
+    ),
+    ("浙江省", &["杭州市", "宁波市", "温州市", "绍兴市"]),
+    ("江苏省", &["南京市", "苏州市", "无锡市", "常州市"]),
+    ("山东省", &["济南市", "青岛市", "烟台市", "潍坊市"]),
+    ("四川省", &["成都市", "绵阳市", "德阳市"]),
+    ("湖北省", &["武汉市", "宜昌市", "襄阳市"]),
+    ("湖南省", &["长沙市", "株洲市", "湘潭市"]),
+    ("河南省", &["郑州市", "洛阳市", "开封市"]),
+    ("福建省", &["福州市", "厦门市", "泉州市"]),
+    ("陕西省", &["西安市", "宝鸡市", "咸阳市"]),
+];
+
+pub const DISTRICTS: &[&str] = &[
+    "朝阳区",
+    "海淀区",
+    "东城区",
+    "西城区",
+    "浦东新区",
+    "徐汇区",
+    "天河区",
+    "南山区",
+    "福田区",
+    "西湖区",
+    "滨江区",
+    "鼓楼区",
+    "玄武区",
+    "高新区",
+    "经开区",
+    "江北区",
+    "武侯区",
+    "锦江区",
+    "雁塔区",
+    "历下区",
+];
+
+pub const ROAD_WORDS: &[&str] = &[
+    "人民", "解放", "建设", "中山", "和平", "文化", "长江", "黄河", "光明", "幸福", "新华", "胜利",
+    "青年", "科技", "学院", "朝阳", "东风", "友谊", "滨江", "花园",
+];
+
+pub const ROAD_SUFFIXES: &[&str] = &["路", "大道", "街", "大街", "中路", "南路", "北路"];
+
+pub const COMMUNITY_WORDS: &[&str] = &[
+    "阳光", "锦绣", "翠苑", "金色", "碧桂", "华府", "御景", "星河", "海岸", "绿城", "香樟", "书香",
+];
+
+pub const COMMUNITY_SUFFIXES: &[&str] = &["小区", "花园", "家园", "公寓", "新村", "苑"];
+
+pub const COMPANY_WORDS: &[&str] = &[
+    "华信", "恒通", "远航", "鼎盛", "金桥", "瑞丰", "博远", "新锐", "众合", "启明", "宏达", "天成",
+    "云帆", "卓越", "同创", "智联", "盛世", "安泰", "嘉禾", "腾飞",
+];
+
+pub const COMPANY_INDUSTRIES: &[&str] = &[
+    "科技",
+    "信息技术",
+    "网络科技",
+    "贸易",
+    "实业",
+    "电子",
+    "软件",
+    "物流",
+    "文化传媒",
+    "医疗器械",
+    "建筑工程",
+    "咨询",
+    "教育科技",
+    "餐饮管理",
+    "电子商务",
+];
+
+pub const COMPANY_SUFFIXES: &[&str] = &["有限公司", "股份有限公司", "有限责任公司"];
+
+pub const TEXT_PHRASES: &[&str] = &[
+    "我们",
+    "今天",
+    "已经",
+    "完成",
+    "项目",
+    "需要",
+    "进一步",
+    "沟通",
+    "客户",
+    "反馈",
+    "问题",
+    "处理",
+    "及时",
+    "安排",
+    "下周",
+    "会议",
+    "讨论",
+    "方案",
+    "确认",
+    "数据",
+    "系统",
+    "正常",
+    "运行",
+    "感谢",
+    "支持",
+    "请",
+    "尽快",
+    "回复",
+    "相关",
+    "资料",
+    "已",
+    "提交",
+    "审核",
+    "通过",
+    "后续",
+    "跟进",
+    "情况",
+    "良好",
+    "满意",
+    "服务",
+];
+
+pub const TEXT_PUNCTUATION: &[&str] = &["，", "。"];
@@ -0,0 +1,313 @@
+// 保留格式的脱敏：生成语法合法、校验位正确的替代值
+//
+// 所有函数都只从传入的随机数生成器取值，生成器由原值的摘要播种，
+// 因此同一输入总是得到同一输出。
+
+use rand::Rng;
+use rand_chacha::ChaCha20Rng;
+
+const ALPHANUMERIC: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+
+/// 未能识别出原值格式时使用的邮箱域名
+const DEFAULT_EMAIL_DOMAIN: &str = "example.com";
+
+/// 大陆手机号第二位的合法取值
+const MOBILE_SECOND_DIGITS: &[u8] = b"3456789";
+
+/// 身份证号前 17 位的加权因子（GB 11643-1999）
+const ID_CARD_WEIGHTS: [u32; 17] = [7, 9, 10, 5, 8, 4, 2, 1, 6, 3, 7, 9, 10, 5, 8, 4, 2];
+const ID_CARD_CHECK_CODES: &[u8; 11] = b"10X98765432";
+
+/// 原值不是合法身份证号时使用的地区码
+const ID_CARD_REGIONS: &[&str] = &[
+    "110101", "310101", "440103", "440305", "330102", "320102", "510104", "420102", "610102",
+    "370102",
+];
+
+/// 替换邮箱用户名，保留域名（或替换为指定域名）
+pub fn mask_email(value: &str, rng: &mut ChaCha20Rng, domain: Option<&str>) -> String {
+    let (local, original_domain) = match value.rsplit_once('@') {
+        Some((local, domain)) if !local.is_empty() && domain.contains('.') => (local, domain),
+        _ => ("", DEFAULT_EMAIL_DOMAIN),
+    };
+
+    let length = local.chars().count().clamp(6, 16);
+    let mut masked: String = (0..length)
+        .map(|_| char::from(ALPHANUMERIC[rng.gen_range(0..ALPHANUMERIC.len())]))
+        .collect();
+    // 用户名以字母开头，兼容较严格的校验规则
+    masked.replace_range(0..1, &char::from(b'a' + rng.gen_range(0..26)).to_string());
+
+    format!("{}@{}", masked, domain.unwrap_or(original_domain))
+}
+
+/// 替换手机号中的数字，保留 `+86`、空格、短横线等分隔格式
+///
+/// 11 位大陆手机号保证生成结果仍以 1[3-9] 开头；其他号码逐位替换，保留首位数字以维持区号形态。
+pub fn mask_phone(value: &str, rng: &mut ChaCha20Rng) -> String {
+    let digits: Vec<u8> = value.bytes().filter(u8::is_ascii_digit).collect();
+
+    // 带国家码 86 的号码只替换后 11 位
+    let offset = if digits.len() == 13 && digits.starts_with(b"86") {
+        2
+    } else {
+        0
+    };
+    let number = &digits[offset..];
+
+    let mut generated: Vec<u8> = digits[..offset].to_vec();
+    if number.len() == 11 && number[0] == b'1' {
+        generated.push(b'1');
+        generated.push(MOBILE_SECOND_DIGITS[rng.gen_range(0..MOBILE_SECOND_DIGITS.len())]);
+        generated.extend((0..9).map(|_| random_digit(rng)));
+    } else {
+        for (i, digit) in number.iter().enumerate() {
+            generated.push(if i == 0 { *digit } else { random_digit(rng) });
+        }
+    }
+
+    replace_digits(value, &generated)
+}
+
+/// 替换银行卡号，保留前 6 位发卡行标识与长度，并重新计算 Luhn 校验位
+pub fn mask_bank_card(value: &str, rng: &mut ChaCha20Rng) -> String {
+    let digits: Vec<u8> = value.bytes().filter(u8::is_ascii_digit).collect();
+    if digits.len() < 2 {
+        return value.to_string();
+    }
+
+    let keep = if digits.len() >= 12 { 6 } else { 1 };
+    let mut generated: Vec<u8> = digits[..keep].to_vec();
+    generated.extend((keep..digits.len() - 1).map(|_| random_digit(rng)));
+    generated.push(luhn_check_digit(&generated));
+
+    replace_digits(value, &generated)
+}
+
+/// 生成 18 位身份证号：保留地区码、出生年份和性别，随机生成出生月日与顺序码，
+/// 并按 GB 11643-1999 重新计算校验位
+pub fn mask_id_card(value: &str, rng: &mut ChaCha20Rng) -> String {
+    let chars: Vec<char> = value.trim().chars().collect();
+    let valid = chars.len() == 18
+        && chars[..17].iter().all(|c| c.is_ascii_digit())
+        && (chars[17].is_ascii_digit() || chars[17] == 'X' || chars[17] == 'x');
+
+    let (region, year, male) = if valid {
+        let text: String = chars.iter().collect();
+        let year = text[6..10].parse::<u32>().unwrap_or(1990);
+        let male = chars[16].to_digit(10).unwrap_or(1) % 2 == 1;
+        (text[..6].to_string(), year, male)
+    } else {
+        (
+            ID_CARD_REGIONS[rng.gen_range(0..ID_CARD_REGIONS.len())].to_string(),
+            rng.gen_range(1960..=2005),
+            rng.gen_bool(0.5),
+        )
+    };
+
+    // 日期只取 1-28 日，任何月份都合法
+    let month = rng.gen_range(1..=12);
+    let day = rng.gen_range(1..=28);
+    // 顺序码共 3 位，末位奇数为男性、偶数为女性
+    let sequence = rng.gen_range(0..100);
+    let gender_digit = rng.gen_range(0..5) * 2 + u32::from(male);
+    let body = format!(
+        "{}{:04}{:02}{:02}{:02}{}",
+        region, year, month, day, sequence, gender_digit
+    );
+
+    let check = id_card_check_code(&body);
+    format!("{}{}", body, char::from(check))
+}
+
+/// 是否为校验位正确的 18 位身份证号
+pub fn is_valid_id_card(value: &str) -> bool {
+    let value = value.trim();
+    let bytes = value.as_bytes();
+    bytes.len() == 18
+        && bytes[..17].iter().all(u8::is_ascii_digit)
+        && id_card_check_code(&value[..17]) == bytes[17].to_ascii_uppercase()
+}
+
+/// 是否为 Luhn 校验通过的数字串，忽略空格和短横线
+pub fn is_luhn_valid(value: &str) -> bool {
+    let digits: Vec<u8> = value.bytes().filter(|b| *b != b' ' && *b != b'-').collect();
+    match digits.split_last() {
+        Some((check, body)) if !body.is_empty() && digits.iter().all(u8::is_ascii_digit) => {
+            luhn_check_digit(body) == *check
+        }
+        _ => false,
+    }
+}
+
+/// 计算身份证校验码
+fn id_card_check_code(body: &str) -> u8 {
+    let sum: u32 = body
+        .chars()
+        .zip(ID_CARD_WEIGHTS.iter())
+        .map(|(c, w)| c.to_digit(10).unwrap_or(0) * w)
+        .sum();
+    ID_CARD_CHECK_CODES[(sum % 11) as usize]
+}
+
+/// 计算 Luhn 校验位，`digits` 为不含校验位的数字
+fn luhn_check_digit(digits: &[u8]) -> u8 {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, d)| {
+            let d = (d - b'0') as u32;
+            if i % 2 == 0 {
+                let doubled = d * 2;
+                if doubled > 9 {
+                    doubled - 9
+                } else {
+                    doubled
+                }
+            } else {
+                d
+            }
+        })
+        .sum();
+    b'0' + ((10 - sum % 10) % 10) as u8
+}
+
+fn random_digit(rng: &mut ChaCha20Rng) -> u8 {
+    b'0' + rng.gen_range(0..10)
+}
+
+/// 按顺序把原值中的数字替换为生成的数字，其他字符保持不变
+fn replace_digits(value: &str, digits: &[u8]) -> String {
+    let mut digits = digits.iter();
+    value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_digit() {
+                digits.next().map(|d| char::from(*d)).unwrap_or(c)
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn rng(seed: u64) -> ChaCha20Rng {
+        ChaCha20Rng::seed_from_u64(seed)
+    }
+
+    /// 非数字字符组成的骨架，用来比较分隔格式
+    fn layout(value: &str) -> String {
+        value
+            .chars()
+            .map(|c| if c.is_ascii_digit() { '0' } else { c })
+            .collect()
+    }
+
+    #[test]
+    fn validates_checksums() {
+        assert!(is_luhn_valid("4111 1111 1111 1111"));
+        assert!(is_luhn_valid("4111-1111-1111-1111"));
+        assert!(!is_luhn_valid("4111 1111 1111 1112"));
+        assert!(!is_luhn_valid("4"));
+        assert!(!is_luhn_valid("4111a1111"));
+
+        assert!(is_valid_id_card("11010519491231002X"));
+        assert!(is_valid_id_card("11010519491231002x"));
+        assert!(!is_valid_id_card("110105194912310021"));
+        assert!(!is_valid_id_card("11010519491231002"));
+    }
+
+    #[test]
+    fn bank_cards_pass_luhn() {
+        for (seed, card) in ["6222 0212 3456 7890 123", "4111-1111-1111-1111", "62170012"]
+            .iter()
+            .enumerate()
+        {
+            for i in 0..50 {
+                let masked = mask_bank_card(card, &mut rng(seed as u64 * 100 + i));
+                assert!(is_luhn_valid(&masked), "{} -> {}", card, masked);
+                assert_eq!(layout(&masked), layout(card));
+            }
+        }
+        let masked = mask_bank_card("6222 0212 3456 7890 123", &mut rng(1));
+        assert!(masked.starts_with("6222 02"));
+        assert_eq!(mask_bank_card("1", &mut rng(1)), "1");
+    }
+
+    #[test]
+    fn id_cards_pass_checksum() {
+        let mut check_codes = std::collections::HashSet::new();
+        for seed in 0..500 {
+            let masked = mask_id_card("11010519491231002X", &mut rng(seed));
+            assert!(is_valid_id_card(&masked), "{}", masked);
+            // 保留地区码、出生年份和性别
+            assert!(masked.starts_with("1101051949"));
+            assert_eq!(masked.as_bytes()[16] % 2, b'2' % 2);
+            check_codes.insert(masked.as_bytes()[17]);
+
+            let generated = mask_id_card("not an id", &mut rng(seed));
+            assert!(is_valid_id_card(&generated), "{}", generated);
+        }
+        // 校验码 X 也会出现
+        assert!(check_codes.contains(&b'X'));
+    }
+
+    #[test]
+    fn phones_keep_format() {
+        for seed in 0..50 {
+            for phone in ["13800138000", "+86 138-0013-8000", "86 13800138000"] {
+                let masked = mask_phone(phone, &mut rng(seed));
+                assert_eq!(layout(&masked), layout(phone));
+                let digits: String = masked.chars().filter(char::is_ascii_digit).collect();
+                let mobile = &digits[digits.len() - 11..];
+                assert!(mobile.starts_with('1'), "{}", masked);
+                assert!(b"3456789".contains(&mobile.as_bytes()[1]), "{}", masked);
+                if phone.contains("86") {
+                    assert!(digits.starts_with("86"), "{}", masked);
+                }
+            }
+
+            let masked = mask_phone("010-6552 9988", &mut rng(seed));
+            assert_eq!(layout(&masked), layout("010-6552 9988"));
+            assert!(masked.starts_with('0'));
+        }
+    }
+
+    #[test]
+    fn emails_keep_domain() {
+        let masked = mask_email("john.doe@corp.example.com", &mut rng(1), None);
+        let (local, domain) = masked.split_once('@').unwrap();
+        assert_eq!(domain, "corp.example.com");
+        assert_eq!(local.len(), 8);
+        assert!(local.starts_with(|c: char| c.is_ascii_lowercase()));
+        assert!(local.bytes().all(|b| ALPHANUMERIC.contains(&b)));
+
+        let masked = mask_email("a@b.cn", &mut rng(1), Some("test.local"));
+        assert!(masked.ends_with("@test.local"));
+        assert_eq!(masked.split_once('@').unwrap().0.len(), 6);
+
+        assert!(mask_email("not-an-email", &mut rng(1), None).ends_with("@example.com"));
+    }
+
+    #[test]
+    fn same_seed_same_output() {
+        let twice = |f: &dyn Fn(&mut ChaCha20Rng) -> String| (f(&mut rng(42)), f(&mut rng(42)));
+        let cases: [&dyn Fn(&mut ChaCha20Rng) -> String; 4] = [
+            &|r| mask_email("john@corp.com", r, None),
+            &|r| mask_phone("+86 138-0013-8000", r),
+            &|r| mask_bank_card("6222021234567890123", r),
+            &|r| mask_id_card("11010519491231002X", r),
+        ];
+        for case in cases {
+            let (first, second) = twice(case);
+            assert_eq!(first, second);
+            assert_ne!(case(&mut rng(42)), case(&mut rng(43)));
+        }
+    }
+}
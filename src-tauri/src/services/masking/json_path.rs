@@ -0,0 +1,364 @@
+// JSON 路径：支持常用的 JSONPath 子集
+//
+//   $.contact.phone        对象成员
+//   $['contact']['phone']  方括号写法，引号内可用 `\` 转义引号和 `\`
+//   $.items[0]             数组下标
+//   $.items[*].address     数组或对象的所有成员
+//   $..email               任意层级的同名成员
+
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+    Wildcard,
+    Descendant(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+impl JsonPath {
+    pub fn parse(path: &str) -> Result<Self, String> {
+        let invalid = |reason: &str| format!("无效的 JSON 路径 '{}': {}", path, reason);
+
+        let rest = path
+            .trim()
+            .strip_prefix('$')
+            .ok_or_else(|| invalid("必须以 $ 开头"))?;
+        let chars: Vec<char> = rest.chars().collect();
+        let mut segments = Vec::new();
+        let mut i = 0;
+
+        let read_name = |i: &mut usize| -> String {
+            let start = *i;
+            while *i < chars.len() && chars[*i] != '.' && chars[*i] != '[' {
+                *i += 1;
+            }
+            chars[start..*i].iter().collect()
+        };
+
+        while i < chars.len() {
+            match chars[i] {
+                '.' if chars.get(i + 1) == Some(&'.') => {
+                    i += 2;
+                    let name = read_name(&mut i);
+                    if name.is_empty() || name == "*" {
+                        return Err(invalid("'..' 之后需要成员名"));
+                    }
+                    segments.push(Segment::Descendant(name));
+                }
+                '.' => {
+                    i += 1;
+                    let name = read_name(&mut i);
+                    match name.as_str() {
+                        "" => return Err(invalid("'.' 之后需要成员名")),
+                        "*" => segments.push(Segment::Wildcard),
+                        _ => segments.push(Segment::Key(name)),
+                    }
+                }
+                '[' => {
+                    i += 1;
+                    while chars.get(i).is_some_and(|c| c.is_whitespace()) {
+                        i += 1;
+                    }
+                    let segment = match chars.get(i) {
+                        // 带引号的成员名先找到闭合引号，名称中可以包含 ']'
+                        Some(&quote) if quote == '\'' || quote == '"' => {
+                            i += 1;
+                            let mut key = String::new();
+                            loop {
+                                match chars.get(i) {
+                                    Some('\\') if i + 1 < chars.len() => {
+                                        key.push(chars[i + 1]);
+                                        i += 2;
+                                    }
+                                    Some(&c) if c == quote => break,
+                                    Some(&c) => {
+                                        key.push(c);
+                                        i += 1;
+                                    }
+                                    None => return Err(invalid("成员名缺少闭合引号")),
+                                }
+                            }
+                            i += 1;
+                            while chars.get(i).is_some_and(|c| c.is_whitespace()) {
+                                i += 1;
+                            }
+                            if chars.get(i) != Some(&']') {
+                                return Err(invalid("成员名之后需要 ']'"));
+                            }
+                            Segment::Key(key)
+                        }
+                        _ => {
+                            let end = chars[i..]
+                                .iter()
+                                .position(|c| *c == ']')
+                                .map(|p| i + p)
+                                .ok_or_else(|| invalid("缺少 ']'"))?;
+                            let inner: String = chars[i..end].iter().collect();
+                            i = end;
+                            match inner.trim() {
+                                "*" => Segment::Wildcard,
+                                inner => Segment::Index(
+                                    inner.parse().map_err(|_| invalid("无效的数组下标"))?,
+                                ),
+                            }
+                        }
+                    };
+                    segments.push(segment);
+                    i += 1;
+                }
+                _ => return Err(invalid("路径段必须以 '.' 或 '[' 开头")),
+            }
+        }
+
+        Ok(Self { segments })
+    }
+
+    /// 对所有匹配的节点调用 `f`，未匹配的部分保持不变
+    pub fn apply<E>(
+        &self,
+        value: &mut Value,
+        f: &mut dyn FnMut(&mut Value) -> Result<(), E>,
+    ) -> Result<(), E> {
+        visit(value, &self.segments, f)
+    }
+}
+
+fn visit<E>(
+    value: &mut Value,
+    segments: &[Segment],
+    f: &mut dyn FnMut(&mut Value) -> Result<(), E>,
+) -> Result<(), E> {
+    let (segment, rest) = match segments.split_first() {
+        Some(split) => split,
+        None => return f(value),
+    };
+
+    match segment {
+        Segment::Key(key) => {
+            if let Some(child) = value.as_object_mut().and_then(|o| o.get_mut(key)) {
+                visit(child, rest, f)?;
+            }
+        }
+        Segment::Index(index) => {
+            if let Some(child) = value.as_array_mut().and_then(|a| a.get_mut(*index)) {
+                visit(child, rest, f)?;
+            }
+        }
+        Segment::Wildcard => match value {
+            Value::Array(items) => {
+                for child in items {
+                    visit(child, rest, f)?;
+                }
+            }
+            Value::Object(members) => {
+                for child in members.values_mut() {
+                    visit(child, rest, f)?;
+                }
+            }
+            _ => {}
+        },
+        Segment::Descendant(key) => {
+            // 同名成员按剩余路径处理，其他成员继续向下查找；已匹配的节点不再重复进入
+            match value {
+                Value::Object(members) => {
+                    for (name, child) in members.iter_mut() {
+                        if name == key {
+                            visit(child, rest, f)?;
+                        } else {
+                            visit(child, segments, f)?;
+                        }
+                    }
+                }
+                Value::Array(items) => {
+                    for child in items {
+                        visit(child, segments, f)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// 对节点下所有标量叶子调用 `f`，匹配到对象或数组时整体脱敏其内容
+pub fn for_each_leaf<E>(
+    value: &mut Value,
+    f: &mut dyn FnMut(&mut Value) -> Result<(), E>,
+) -> Result<(), E> {
+    match value {
+        Value::Array(items) => {
+            for child in items {
+                for_each_leaf(child, f)?;
+            }
+            Ok(())
+        }
+        Value::Object(members) => {
+            for child in members.values_mut() {
+                for_each_leaf(child, f)?;
+            }
+            Ok(())
+        }
+        // JSON null 保持为 null
+        Value::Null => Ok(()),
+        _ => f(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn segments(path: &str) -> Vec<Segment> {
+        JsonPath::parse(path).unwrap().segments
+    }
+
+    fn key(name: &str) -> Segment {
+        Segment::Key(name.to_string())
+    }
+
+    /// 把所有匹配的节点替换为 "*"
+    fn masked(path: &str, mut value: Value) -> Value {
+        JsonPath::parse(path)
+            .unwrap()
+            .apply(&mut value, &mut |v| {
+                *v = json!("*");
+                Ok::<_, ()>(())
+            })
+            .unwrap();
+        value
+    }
+
+    #[test]
+    fn parses_segments() {
+        assert_eq!(segments("$"), []);
+        assert_eq!(
+            segments(" $.contact.phone "),
+            [key("contact"), key("phone")]
+        );
+        assert_eq!(
+            segments("$['contact'][\"phone\"]"),
+            [key("contact"), key("phone")]
+        );
+        assert_eq!(
+            segments("$.items[0][ 12 ].address"),
+            [
+                key("items"),
+                Segment::Index(0),
+                Segment::Index(12),
+                key("address")
+            ]
+        );
+        assert_eq!(
+            segments("$.items[*].*"),
+            [key("items"), Segment::Wildcard, Segment::Wildcard]
+        );
+        assert_eq!(
+            segments("$..email"),
+            [Segment::Descendant("email".to_string())]
+        );
+        assert_eq!(
+            segments("$.a..b[1]"),
+            [
+                key("a"),
+                Segment::Descendant("b".to_string()),
+                Segment::Index(1)
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_quoted_keys() {
+        assert_eq!(segments("$['a]b']"), [key("a]b")]);
+        assert_eq!(segments("$[\"a.b[0]\"].c"), [key("a.b[0]"), key("c")]);
+        assert_eq!(segments(r"$['it\'s']"), [key("it's")]);
+        assert_eq!(segments(r"$['a\\b']"), [key(r"a\b")]);
+        assert_eq!(segments("$[ '*' ]"), [key("*")]);
+        assert_eq!(segments("$['']"), [key("")]);
+    }
+
+    #[test]
+    fn rejects_invalid_paths() {
+        for path in [
+            "",
+            "contact.phone",
+            "$.",
+            "$.a.",
+            "$..",
+            "$..*",
+            "$a",
+            "$[0",
+            "$[-1]",
+            "$[x]",
+            "$[]",
+            "$['a'",
+            "$['a]",
+            "$['a' b]",
+            "$.a[0]b",
+        ] {
+            let error = JsonPath::parse(path).err();
+            assert!(error.is_some(), "{}", path);
+            assert!(error.unwrap().contains("无效的 JSON 路径"));
+        }
+    }
+
+    #[test]
+    fn applies_to_matches() {
+        let doc = json!({
+            "name": "a",
+            "contact": {"phone": "1", "email": "x@y"},
+            "items": [
+                {"address": "p", "email": "i@j"},
+                {"address": "q", "tags": [{"email": "k@l"}]},
+                "plain"
+            ]
+        });
+
+        assert_eq!(
+            masked("$.contact.phone", doc.clone())["contact"],
+            json!({"phone": "*", "email": "x@y"})
+        );
+        let result = masked("$.items[1].address", doc.clone());
+        assert_eq!(result["items"][0]["address"], "p");
+        assert_eq!(result["items"][1]["address"], "*");
+
+        let result = masked("$.items[*].address", doc.clone());
+        assert_eq!(result["items"][0]["address"], "*");
+        assert_eq!(result["items"][1]["address"], "*");
+        assert_eq!(result["items"][2], "plain");
+
+        let result = masked("$.contact.*", doc.clone());
+        assert_eq!(result["contact"], json!({"phone": "*", "email": "*"}));
+
+        let result = masked("$..email", doc.clone());
+        assert_eq!(result["contact"]["email"], "*");
+        assert_eq!(result["items"][0]["email"], "*");
+        assert_eq!(result["items"][1]["tags"][0]["email"], "*");
+        assert_eq!(result["name"], "a");
+
+        // 不存在的成员和越界下标保持原样
+        assert_eq!(masked("$.missing.x", doc.clone()), doc);
+        assert_eq!(masked("$.items[9]", doc.clone()), doc);
+        assert_eq!(masked("$.name[0]", doc.clone()), doc);
+        assert_eq!(masked("$", doc.clone()), json!("*"));
+    }
+
+    #[test]
+    fn masks_leaves() {
+        let mut value = json!({"a": [1, null, {"b": "x"}], "c": true});
+        for_each_leaf(&mut value, &mut |v| {
+            *v = json!("*");
+            Ok::<_, ()>(())
+        })
+        .unwrap();
+        assert_eq!(value, json!({"a": ["*", null, {"b": "*"}], "c": "*"}));
+    }
+}
@@ -0,0 +1,1024 @@
+use crate::database::{
+    encode_literal, is_text_type, ColumnConfig, DbError, FieldMaskRule, HashEncoding, HashOptions,
+    MaskRule, MaskRuleType, RawValue,
+};
+use crate::services::masking::composite::{element_type, storage_type, ArrayValue, RecordValue};
+use crate::services::masking::fake::{fake_address, fake_company, fake_name, fake_text};
+use crate::services::masking::formats::{mask_bank_card, mask_email, mask_id_card, mask_phone};
+use crate::services::masking::json_path::{for_each_leaf, JsonPath};
+use crate::services::masking::perturb::{
+    add_noise, clamp, is_date_type, is_numeric_type, round_to_step, shift_date,
+};
+use crate::services::masking::{DomainKind, MaskDomain};
+use crate::services::TokenVault;
+use base64::engine::general_purpose::STANDARD_NO_PAD as BASE64;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio_postgres::types::{Kind, Type};
+
+/// 未配置固定值时使用的默认掩码
+const DEFAULT_FIXED_VALUE: &str = "****";
+
+const DEFAULT_SHIFT_DAYS: u32 = 30;
+const DEFAULT_NOISE_PERCENT: f64 = 10.0;
+const DEFAULT_ROUND_STEP: f64 = 1.0;
+
+const DEFAULT_TOKEN_PREFIX: &str = "tok_";
+const DEFAULT_TOKEN_LENGTH: usize = 16;
+
+/// 整数哈希使用的 Feistel 置换轮数
+const FEISTEL_ROUNDS: u8 = 8;
+
+/// 一次复制任务中所有列共享的脱敏上下文
+#[derive(Debug, Default, Clone)]
+pub struct MaskContext {
+    /// HMAC 密钥，由 `Storage` 按配置作用域保存
+    secret: Option<Arc<Vec<u8>>>,
+    /// 当前配置中所有列引用的脱敏域
+    domains: Arc<HashMap<String, MaskDomain>>,
+    /// 已解锁的令牌库，令牌化规则需要
+    vault: Option<Arc<TokenVault>>,
+}
+
+impl MaskContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_secret(mut self, secret: Vec<u8>) -> Self {
+        self.secret = Some(Arc::new(secret));
+        self
+    }
+
+    pub fn with_domains(mut self, domains: HashMap<String, MaskDomain>) -> Self {
+        self.domains = Arc::new(domains);
+        self
+    }
+
+    pub fn with_vault(mut self, vault: TokenVault) -> Self {
+        self.vault = Some(Arc::new(vault));
+        self
+    }
+
+    /// 提交令牌库中的新令牌，每张表写入目标库之前调用
+    pub fn flush_vault(&self) -> Result<(), DbError> {
+        match &self.vault {
+            Some(vault) => vault
+                .flush()
+                .map_err(|e| DbError::Mask(format!("令牌库: {}", e))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// 单列脱敏器，在复制管道中对该列的每个值应用配置的脱敏规则
+///
+/// 规则与列类型的兼容性在创建时检查，避免复制到一半才发现规则无法应用。
+/// 数组列对每个元素应用规则，复合类型列对配置了规则的字段分别应用各自的规则。
+pub struct ColumnMasker {
+    column: String,
+    ty: Type,
+    layout: Layout,
+}
+
+enum Layout {
+    Value(Box<ValueMasker>),
+    /// 数组的每个非 NULL 元素交给元素脱敏器
+    Array(Box<ColumnMasker>),
+    /// 复合类型按字段顺序排列，未配置规则的字段为 None
+    Composite(Vec<Option<ColumnMasker>>),
+}
+
+impl ColumnMasker {
+    pub fn new(
+        column: &str,
+        rule: &MaskRule,
+        ty: &Type,
+        context: &MaskContext,
+    ) -> Result<Self, DbError> {
+        let ty = storage_type(ty);
+        let layout = match element_type(&ty) {
+            // 洗牌交换的是各行的整个值，数组整体参与洗牌
+            Some(element) if rule.rule_type != MaskRuleType::Shuffle => {
+                Layout::Array(Box::new(Self::new(column, rule, &element, context)?))
+            }
+            _ => Layout::Value(Box::new(ValueMasker::new(column, rule, &ty, context)?)),
+        };
+        Ok(Self {
+            column: column.to_string(),
+            ty,
+            layout,
+        })
+    }
+
+    /// 按字段规则创建复合类型（或复合类型数组）列的脱敏器
+    pub fn with_fields(
+        column: &str,
+        field_rules: &[FieldMaskRule],
+        ty: &Type,
+        context: &MaskContext,
+    ) -> Result<Self, DbError> {
+        let ty = storage_type(ty);
+        if let Some(element) = element_type(&ty) {
+            let elements = Self::with_fields(column, field_rules, &element, context)?;
+            return Ok(Self {
+                column: column.to_string(),
+                ty,
+                layout: Layout::Array(Box::new(elements)),
+            });
+        }
+
+        let fields = match ty.kind() {
+            Kind::Composite(fields) => fields,
+            _ => {
+                return Err(DbError::Mask(format!(
+                    "列 {} 的类型 {} 不是复合类型，不能配置字段规则",
+                    column, ty
+                )))
+            }
+        };
+
+        for field_rule in field_rules {
+            let name = field_rule.field.split('.').next().unwrap_or_default();
+            if !fields.iter().any(|f| f.name() == name) {
+                return Err(DbError::Mask(format!(
+                    "列 {}: 类型 {} 中不存在字段 {}",
+                    column, ty, name
+                )));
+            }
+            // 洗牌和种子列都以整列为单位，字段上无法使用
+            let rule = &field_rule.rule;
+            let seeded = rule
+                .perturb_options
+                .as_ref()
+                .is_some_and(|o| o.seed_column.is_some());
+            if rule.rule_type == MaskRuleType::Shuffle || seeded {
+                return Err(DbError::Mask(format!(
+                    "列 {}: 字段 {} 的规则不能使用洗牌或种子列",
+                    column, field_rule.field
+                )));
+            }
+        }
+
+        let mut maskers = Vec::with_capacity(fields.len());
+        for field in fields {
+            let label = format!("{}.{}", column, field.name());
+            let mut direct = Vec::new();
+            let mut nested = Vec::new();
+            for field_rule in field_rules {
+                match field_rule.field.split_once('.') {
+                    Some((name, rest)) if name == field.name() => nested.push(FieldMaskRule {
+                        field: rest.to_string(),
+                        rule: field_rule.rule.clone(),
+                    }),
+                    None if field_rule.field == field.name() => direct.push(&field_rule.rule),
+                    _ => {}
+                }
+            }
+            if direct.len() > 1 {
+                return Err(DbError::Mask(format!(
+                    "列 {}: 字段 {} 配置了多条规则",
+                    column,
+                    field.name()
+                )));
+            }
+
+            let masker = match (direct.first(), nested.is_empty()) {
+                (Some(_), false) => {
+                    return Err(DbError::Mask(format!(
+                        "列 {}: 字段 {} 不能同时配置整体规则和子字段规则",
+                        column,
+                        field.name()
+                    )))
+                }
+                (Some(rule), true) => Some(Self::new(&label, rule, field.type_(), context)?),
+                (None, false) => Some(Self::with_fields(&label, &nested, field.type_(), context)?),
+                (None, true) => None,
+            };
+            maskers.push(masker);
+        }
+
+        Ok(Self {
+            column: column.to_string(),
+            ty,
+            layout: Layout::Composite(maskers),
+        })
+    }
+
+    /// 按列配置创建脱敏器，未启用脱敏的列返回 None
+    pub fn for_column(
+        column: &ColumnConfig,
+        ty: &Type,
+        context: &MaskContext,
+    ) -> Result<Option<Self>, DbError> {
+        if !column.selected {
+            return Ok(None);
+        }
+        match (&column.mask_rule, column.field_rules.is_empty()) {
+            (Some(_), false) => Err(DbError::Mask(format!(
+                "列 {} 不能同时配置列规则和字段规则",
+                column.name
+            ))),
+            (Some(rule), true) => Self::new(&column.name, rule, ty, context).map(Some),
+            (None, false) => {
+                Self::with_fields(&column.name, &column.field_rules, ty, context).map(Some)
+            }
+            (None, true) => Ok(None),
+        }
+    }
+
+    pub fn column(&self) -> &str {
+        &self.column
+    }
+
+    /// 整列使用的单值脱敏器，复合类型列没有
+    fn value_masker(&self) -> Option<&ValueMasker> {
+        match &self.layout {
+            Layout::Value(masker) => Some(masker),
+            Layout::Array(elements) => elements.value_masker(),
+            Layout::Composite(_) => None,
+        }
+    }
+
+    /// 决定扰动偏移量的列，未配置时由值本身决定
+    pub fn seed_column(&self) -> Option<&str> {
+        self.value_masker().and_then(ValueMasker::seed_column)
+    }
+
+    /// 洗牌规则需要看到整表的值，由调用方通过 `ColumnShuffler` 处理
+    pub fn is_shuffle(&self) -> bool {
+        self.value_masker().is_some_and(ValueMasker::is_shuffle)
+    }
+
+    /// 洗牌规则的分组列
+    pub fn group_column(&self) -> Option<&str> {
+        self.value_masker().and_then(ValueMasker::group_column)
+    }
+
+    /// 对单个值脱敏，NULL 保持为 NULL
+    pub fn mask(&self, value: Option<RawValue>) -> Result<Option<RawValue>, DbError> {
+        self.mask_with_seed(value, None)
+    }
+
+    /// 对单个值脱敏，`seed` 为 `seed_column` 在同一行中的原值
+    ///
+    /// 数组的每个元素使用同一个种子，NULL 元素和数组维数保持不变。
+    pub fn mask_with_seed(
+        &self,
+        value: Option<RawValue>,
+        seed: Option<&RawValue>,
+    ) -> Result<Option<RawValue>, DbError> {
+        let value = match value {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+
+        let bytes = match &self.layout {
+            Layout::Value(masker) => return masker.mask_with_seed(Some(value), seed),
+            Layout::Array(elements) => {
+                let mut array = ArrayValue::decode(&value.bytes).map_err(|e| self.error(e))?;
+                for element in array.elements.iter_mut() {
+                    *element = elements.mask_part(element.take(), seed)?;
+                }
+                array.encode()
+            }
+            Layout::Composite(fields) => {
+                let mut record = RecordValue::decode(&value.bytes).map_err(|e| self.error(e))?;
+                if record.fields.len() != fields.len() {
+                    return Err(self.error(format!(
+                        "复合类型有 {} 个字段，值中有 {} 个字段",
+                        fields.len(),
+                        record.fields.len()
+                    )));
+                }
+                for ((_, field), masker) in record.fields.iter_mut().zip(fields) {
+                    if let Some(masker) = masker {
+                        *field = masker.mask_part(field.take(), seed)?;
+                    }
+                }
+                record.encode()
+            }
+        };
+
+        Ok(Some(RawValue::new(value.ty, bytes)))
+    }
+
+    /// 对数组元素或复合类型字段的原始字节脱敏
+    fn mask_part(
+        &self,
+        bytes: Option<Vec<u8>>,
+        seed: Option<&RawValue>,
+    ) -> Result<Option<Vec<u8>>, DbError> {
+        let value = bytes.map(|bytes| RawValue::new(self.ty.clone(), bytes));
+        Ok(self.mask_with_seed(value, seed)?.map(|v| v.bytes))
+    }
+
+    fn error(&self, msg: String) -> DbError {
+        DbError::Mask(format!("列 {}: {}", self.column, msg))
+    }
+}
+
+/// 按单一规则对标量值脱敏
+struct ValueMasker {
+    column: String,
+    rule: MaskRule,
+    ty: Type,
+    /// 固定值规则预先按列类型编码好的值
+    fixed: Option<RawValue>,
+    secret: Option<Arc<Vec<u8>>>,
+    domain: Option<MaskDomain>,
+    /// JSON 路径规则匹配的路径与对匹配值使用的内层脱敏器
+    json_paths: Vec<JsonPath>,
+    inner: Option<Box<ValueMasker>>,
+    vault: Option<Arc<TokenVault>>,
+}
+
+impl ValueMasker {
+    fn new(
+        column: &str,
+        rule: &MaskRule,
+        ty: &Type,
+        context: &MaskContext,
+    ) -> Result<Self, DbError> {
+        let text = is_text_type(ty);
+        let unsupported = || {
+            DbError::Mask(format!(
+                "列 {} 的类型 {} 不支持 {:?} 脱敏规则",
+                column, ty, rule.rule_type
+            ))
+        };
+
+        let domain = match rule.domain.as_deref() {
+            Some(name) => Some(context.domains.get(name).cloned().ok_or_else(|| {
+                DbError::Mask(format!("列 {}: 脱敏域 {} 未初始化", column, name))
+            })?),
+            None => None,
+        };
+
+        let mut json_paths = Vec::new();
+        let mut inner = None;
+
+        let fixed = match rule.rule_type {
+            MaskRuleType::Fixed => {
+                let literal = rule.pattern.as_deref().unwrap_or(DEFAULT_FIXED_VALUE);
+                let value = encode_literal(ty, literal)
+                    .map_err(|e| DbError::Mask(format!("列 {}: {}", column, e)))?;
+                Some(value)
+            }
+            MaskRuleType::Hash => {
+                if !text && !Self::hashable(ty) {
+                    return Err(unsupported());
+                }
+                // 整数置换的轮函数总是使用密钥，否则任何人都能逆推出原值
+                let integer = match domain.as_ref().map(|d| &d.kind) {
+                    Some(kind) => matches!(kind, DomainKind::Integer(_)),
+                    None => integer_bits(ty).is_some(),
+                };
+                if integer && context.secret.is_none() {
+                    return Err(DbError::Mask(format!(
+                        "列 {}: 整数哈希需要脱敏密钥",
+                        column
+                    )));
+                }
+                None
+            }
+            MaskRuleType::Hmac => {
+                if !text && !Self::hashable(ty) {
+                    return Err(unsupported());
+                }
+                if context.secret.is_none() {
+                    return Err(DbError::Mask(format!("列 {}: 未配置 HMAC 密钥", column)));
+                }
+                None
+            }
+            MaskRuleType::Pattern
+            | MaskRuleType::Email
+            | MaskRuleType::Phone
+            | MaskRuleType::BankCard
+            | MaskRuleType::IdCard
+            | MaskRuleType::FakeName
+            | MaskRuleType::FakeAddress
+            | MaskRuleType::FakeCompany
+            | MaskRuleType::FakeText => {
+                if !text {
+                    return Err(unsupported());
+                }
+                None
+            }
+            MaskRuleType::DateShift => {
+                if !is_date_type(ty) {
+                    return Err(unsupported());
+                }
+                None
+            }
+            MaskRuleType::Noise => {
+                if !is_numeric_type(ty) {
+                    return Err(unsupported());
+                }
+                let percent = rule.perturb_options.as_ref().and_then(|o| o.percent);
+                if percent.is_some_and(|p| !p.is_finite()) {
+                    return Err(DbError::Mask(format!(
+                        "列 {}: 噪声百分比必须是有限数",
+                        column
+                    )));
+                }
+                None
+            }
+            MaskRuleType::Round => {
+                if !is_numeric_type(ty) {
+                    return Err(unsupported());
+                }
+                None
+            }
+            MaskRuleType::Clamp => {
+                if !is_numeric_type(ty) {
+                    return Err(unsupported());
+                }
+                let options = rule.perturb_options.as_ref();
+                if options.and_then(|o| o.min.or(o.max)).is_none() {
+                    return Err(DbError::Mask(format!(
+                        "列 {}: 范围限制规则至少需要设置 min 或 max",
+                        column
+                    )));
+                }
+                None
+            }
+            MaskRuleType::JsonPath => {
+                if !text && *ty != Type::JSON && *ty != Type::JSONB {
+                    return Err(unsupported());
+                }
+                let options = rule.json_options.as_ref().ok_or_else(|| {
+                    DbError::Mask(format!("列 {}: JSON 路径规则缺少路径配置", column))
+                })?;
+                if options.paths.is_empty() {
+                    return Err(DbError::Mask(format!(
+                        "列 {}: JSON 路径规则至少需要一个路径",
+                        column
+                    )));
+                }
+                if options.rule.rule_type == MaskRuleType::JsonPath {
+                    return Err(DbError::Mask(format!(
+                        "列 {}: JSON 路径规则不能嵌套",
+                        column
+                    )));
+                }
+                if options.rule.rule_type == MaskRuleType::Shuffle {
+                    return Err(DbError::Mask(format!(
+                        "列 {}: JSON 路径规则内不能使用洗牌规则",
+                        column
+                    )));
+                }
+                for path in &options.paths {
+                    json_paths.push(
+                        JsonPath::parse(path)
+                            .map_err(|e| DbError::Mask(format!("列 {}: {}", column, e)))?,
+                    );
+                }
+                // 匹配到的值统一按文本处理
+                inner = Some(Box::new(Self::new(
+                    column,
+                    &options.rule,
+                    &Type::TEXT,
+                    context,
+                )?));
+                None
+            }
+            MaskRuleType::Shuffle => {
+                // 洗牌交换的是原值本身，与脱敏域的确定性映射无关
+                if domain.is_some() {
+                    return Err(DbError::Mask(format!(
+                        "列 {}: 洗牌规则不能使用脱敏域",
+                        column
+                    )));
+                }
+                None
+            }
+            MaskRuleType::Token => {
+                if !text {
+                    return Err(unsupported());
+                }
+                if context.vault.is_none() {
+                    return Err(DbError::Mask(format!(
+                        "列 {}: 令牌化规则需要提供令牌库密钥",
+                        column
+                    )));
+                }
+                // 令牌库按原值全局去重，脱敏域不起作用
+                if domain.is_some() {
+                    return Err(DbError::Mask(format!(
+                        "列 {}: 令牌化规则不能使用脱敏域",
+                        column
+                    )));
+                }
+                None
+            }
+        };
+
+        Ok(Self {
+            column: column.to_string(),
+            rule: rule.clone(),
+            ty: ty.clone(),
+            fixed,
+            secret: context.secret.clone(),
+            domain,
+            json_paths,
+            inner,
+            vault: context.vault.clone(),
+        })
+    }
+
+    fn seed_column(&self) -> Option<&str> {
+        self.rule
+            .perturb_options
+            .as_ref()
+            .and_then(|o| o.seed_column.as_deref())
+    }
+
+    fn is_shuffle(&self) -> bool {
+        self.rule.rule_type == MaskRuleType::Shuffle
+    }
+
+    fn group_column(&self) -> Option<&str> {
+        self.rule
+            .shuffle_options
+            .as_ref()
+            .and_then(|o| o.group_by.as_deref())
+    }
+
+    fn mask(&self, value: Option<RawValue>) -> Result<Option<RawValue>, DbError> {
+        self.mask_with_seed(value, None)
+    }
+
+    fn mask_with_seed(
+        &self,
+        value: Option<RawValue>,
+        seed: Option<&RawValue>,
+    ) -> Result<Option<RawValue>, DbError> {
+        let value = match value {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        let options = self.rule.perturb_options.clone().unwrap_or_default();
+
+        let masked = match self.rule.rule_type {
+            MaskRuleType::Fixed => self.fixed.clone().unwrap_or(value),
+            MaskRuleType::Hash | MaskRuleType::Hmac => self.hash(&value)?,
+            MaskRuleType::Pattern => {
+                let text = value.as_text().map_err(|e| self.error(e))?;
+                let pattern = self.rule.pattern.as_deref().unwrap_or_default();
+                RawValue::from_text(&self.ty, &apply_pattern(text, pattern))
+            }
+            MaskRuleType::Email
+            | MaskRuleType::Phone
+            | MaskRuleType::BankCard
+            | MaskRuleType::IdCard
+            | MaskRuleType::FakeName
+            | MaskRuleType::FakeAddress
+            | MaskRuleType::FakeCompany
+            | MaskRuleType::FakeText => {
+                let mut rng = self.rng(&value)?;
+                let text = value.as_text().map_err(|e| self.error(e))?;
+                let locale = self.rule.locale.clone().unwrap_or_default();
+                let masked = match self.rule.rule_type {
+                    MaskRuleType::Email => mask_email(text, &mut rng, self.rule.pattern.as_deref()),
+                    MaskRuleType::Phone => mask_phone(text, &mut rng),
+                    MaskRuleType::BankCard => mask_bank_card(text, &mut rng),
+                    MaskRuleType::IdCard => mask_id_card(text, &mut rng),
+                    MaskRuleType::FakeName => fake_name(&mut rng, &locale),
+                    MaskRuleType::FakeAddress => fake_address(&mut rng, &locale),
+                    MaskRuleType::FakeCompany => fake_company(&mut rng, &locale),
+                    _ => fake_text(&mut rng, &locale, text.chars().count()),
+                };
+                RawValue::from_text(&self.ty, &masked)
+            }
+            MaskRuleType::DateShift => {
+                let mut rng = self.rng(seed.unwrap_or(&value))?;
+                let days = options.days.unwrap_or(DEFAULT_SHIFT_DAYS);
+                shift_date(&value, &mut rng, days).map_err(|e| self.error(e))?
+            }
+            MaskRuleType::Noise => {
+                let mut rng = self.rng(seed.unwrap_or(&value))?;
+                let percent = options.percent.unwrap_or(DEFAULT_NOISE_PERCENT);
+                add_noise(&value, &mut rng, percent).map_err(|e| self.error(e))?
+            }
+            MaskRuleType::Round => {
+                let step = options.step.unwrap_or(DEFAULT_ROUND_STEP);
+                round_to_step(&value, step).map_err(|e| self.error(e))?
+            }
+            MaskRuleType::Clamp => {
+                clamp(&value, options.min, options.max).map_err(|e| self.error(e))?
+            }
+            MaskRuleType::JsonPath => self.mask_json(&value)?,
+            MaskRuleType::Shuffle => {
+                return Err(self.error("洗牌规则不能逐值处理".to_string()));
+            }
+            MaskRuleType::Token => {
+                let vault = self
+                    .vault
+                    .as_ref()
+                    .ok_or_else(|| self.error("令牌库未解锁".to_string()))?;
+                let options = self.rule.token_options.clone().unwrap_or_default();
+                let text = value.as_text().map_err(|e| self.error(e))?;
+                let token = vault
+                    .tokenize(
+                        text,
+                        options.prefix.as_deref().unwrap_or(DEFAULT_TOKEN_PREFIX),
+                        options.length.unwrap_or(DEFAULT_TOKEN_LENGTH),
+                    )
+                    .map_err(|e| self.error(e))?;
+                RawValue::from_text(&self.ty, &token)
+            }
+        };
+
+        Ok(Some(masked))
+    }
+
+    /// 解析 JSON 文档，对路径匹配到的每个标量值应用内层规则，其余内容保持不变
+    ///
+    /// 字符串按原文脱敏，数字和布尔值先转为文本，脱敏结果一律写回为字符串。
+    fn mask_json(&self, value: &RawValue) -> Result<RawValue, DbError> {
+        let ty = &self.ty;
+        let mut document: Value = if is_text_type(ty) {
+            let text = value.as_text().map_err(|e| self.error(e))?;
+            serde_json::from_str(text).map_err(|e| self.error(format!("不是有效的 JSON: {}", e)))?
+        } else {
+            value.decode().map_err(|e| self.error(e))?
+        };
+
+        let inner = match &self.inner {
+            Some(inner) => inner,
+            None => return Ok(value.clone()),
+        };
+
+        let mut mask_leaf = |leaf: &mut Value| -> Result<(), DbError> {
+            let text = match &*leaf {
+                Value::String(text) => text.clone(),
+                other => other.to_string(),
+            };
+            let masked = inner.mask(Some(RawValue::from_text(&Type::TEXT, &text)))?;
+            if let Some(masked) = masked {
+                let masked = masked.as_text().map_err(|e| self.error(e))?;
+                *leaf = Value::String(masked.to_string());
+            }
+            Ok(())
+        };
+
+        for path in &self.json_paths {
+            path.apply(&mut document, &mut |node| {
+                for_each_leaf(node, &mut mask_leaf)
+            })?;
+        }
+
+        if is_text_type(ty) {
+            Ok(RawValue::from_text(ty, &document.to_string()))
+        } else {
+            RawValue::encode(ty, document).map_err(|e| self.error(e))
+        }
+    }
+
+    /// 除文本外，可以由摘要派生出同类型值的列类型
+    fn hashable(ty: &Type) -> bool {
+        [
+            Type::INT2,
+            Type::INT4,
+            Type::INT8,
+            Type::UUID,
+            Type::BYTEA,
+            Type::JSON,
+            Type::JSONB,
+        ]
+        .contains(ty)
+    }
+
+    /// 计算值的摘要
+    ///
+    /// `Hash` 规则保持不带密钥的 SHA-256；其他规则在配置了密钥时使用 HMAC-SHA256，
+    /// 避免通过字典反推出由摘要派生的脱敏结果。
+    ///
+    /// 属于脱敏域的列先把值转换为与类型无关的规范形式，并以域名作为前缀，
+    /// 使 `int4` 的 42、`int8` 的 42 与文本 "42" 在同一域中得到相同摘要。
+    fn digest(&self, value: &RawValue) -> Result<Vec<u8>, DbError> {
+        let input = match &self.domain {
+            Some(domain) => {
+                let mut input = domain.name.as_bytes().to_vec();
+                input.push(0);
+                input.extend(canonical_bytes(value).map_err(|e| self.error(e))?);
+                input
+            }
+            None => value.bytes.clone(),
+        };
+        self.keyed_digest(&input)
+    }
+
+    /// 按规则对任意字节求摘要：`Hash` 为 SHA-256，其他规则配置了密钥时为 HMAC-SHA256
+    fn keyed_digest(&self, input: &[u8]) -> Result<Vec<u8>, DbError> {
+        match (&self.rule.rule_type, &self.secret) {
+            (MaskRuleType::Hash, _) => Ok(Sha256::digest(input).to_vec()),
+            (_, Some(secret)) => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(secret)
+                    .map_err(|e| self.error(e.to_string()))?;
+                mac.update(input);
+                Ok(mac.finalize().into_bytes().to_vec())
+            }
+            (MaskRuleType::Hmac, None) => Err(self.error("未配置 HMAC 密钥".to_string())),
+            (_, None) => Ok(Sha256::digest(input).to_vec()),
+        }
+    }
+
+    /// Feistel 置换的轮函数，总是使用密钥
+    fn round_digest(&self, input: &[u8]) -> Result<Vec<u8>, DbError> {
+        let secret = self
+            .secret
+            .as_ref()
+            .ok_or_else(|| self.error("整数哈希需要脱敏密钥".to_string()))?;
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(secret).map_err(|e| self.error(e.to_string()))?;
+        mac.update(input);
+        Ok(mac.finalize().into_bytes().to_vec())
+    }
+
+    /// 在 `bits` 位有符号整数空间上做带密钥的 Feistel 置换
+    ///
+    /// 置换是双射，不同输入必然得到不同输出，整数主键和外键脱敏后不会冲突。
+    /// 轮函数无论规则是 `Hash` 还是 `Hmac` 都是以配置密钥为密钥的 HMAC-SHA256，
+    /// 不持有密钥就无法逆向各轮求出原值。
+    /// 轮函数的输入带有域名（不属于域时为空），同一域中的列使用同一个置换。
+    fn permute_integer(&self, value: i64, bits: u32) -> Result<i64, DbError> {
+        if bits < 64 {
+            let limit = 1i64 << (bits - 1);
+            if value < -limit || value >= limit {
+                return Err(self.error(format!(
+                    "值 {} 超出脱敏域的 {} 位整数范围",
+                    value, bits
+                )));
+            }
+        }
+
+        let half = bits / 2;
+        let half_mask = (1u64 << half) - 1;
+        let unsigned = if bits == 64 {
+            value as u64
+        } else {
+            value as u64 & ((1u64 << bits) - 1)
+        };
+
+        let mut input = self
+            .domain
+            .as_ref()
+            .map(|d| d.name.as_bytes().to_vec())
+            .unwrap_or_default();
+        input.push(0);
+        let prefix = input.len();
+
+        let (mut left, mut right) = (unsigned >> half, unsigned & half_mask);
+        for round in 0..FEISTEL_ROUNDS {
+            input.truncate(prefix);
+            input.push(round);
+            input.extend_from_slice(&right.to_be_bytes());
+            let digest = self.round_digest(&input)?;
+            let f = u64::from_be_bytes(digest[..8].try_into().unwrap_or_default()) & half_mask;
+            (left, right) = (right, left ^ f);
+        }
+
+        // 按位宽做符号扩展
+        let shift = 64 - bits;
+        Ok((((left << half) | right) << shift) as i64 >> shift)
+    }
+
+    /// 由值的摘要播种的随机数生成器，同一输入总是得到同一序列
+    fn rng(&self, value: &RawValue) -> Result<ChaCha20Rng, DbError> {
+        let digest = self.digest(value)?;
+        let mut seed = [0u8; 32];
+        seed.copy_from_slice(&digest[..32]);
+        Ok(ChaCha20Rng::from_seed(seed))
+    }
+
+    /// 对原始线格式字节求摘要，再按列类型转换为合法的值
+    fn hash(&self, value: &RawValue) -> Result<RawValue, DbError> {
+        let digest = self.digest(value)?;
+        let ty = &self.ty;
+        let options = self.rule.hash_options.as_ref();
+
+        // 取摘要前 8 字节作为非负整数
+        let number = u64::from_be_bytes(digest[..8].try_into().unwrap_or_default()) >> 1;
+
+        // 整数列和整数域内的列按位宽做置换，保证不同的值脱敏后仍然不同
+        let integer_bits = match self.domain.as_ref().map(|d| &d.kind) {
+            Some(DomainKind::Integer(bits)) => Some(*bits),
+            Some(_) => None,
+            None => integer_bits(ty),
+        };
+
+        if let Some(bits) = integer_bits {
+            let number = match integer_value(value).map_err(|e| self.error(e))? {
+                Some(value) => self.permute_integer(value, bits)?,
+                // 不是整数的文本无法参与置换，由摘要派生非负整数，不保证唯一
+                None => (number >> (64 - bits)) as i64,
+            };
+            let result = if is_text_type(ty) {
+                Ok(RawValue::from_text(ty, &number.to_string()))
+            } else if *ty == Type::INT2 {
+                RawValue::encode(ty, number as i16)
+            } else if *ty == Type::INT4 {
+                RawValue::encode(ty, number as i32)
+            } else {
+                RawValue::encode(ty, number)
+            };
+            return result.map_err(|e| self.error(e));
+        }
+
+        // 脱敏域决定输出形态，保证域内不同类型的列输出一致
+        match self.domain.as_ref().map(|d| &d.kind) {
+            Some(DomainKind::Uuid) => {
+                let bytes = uuid_from_digest(&digest);
+                return Ok(if is_text_type(ty) {
+                    RawValue::from_text(ty, &format_uuid(&bytes))
+                } else {
+                    RawValue::new(ty.clone(), bytes.to_vec())
+                });
+            }
+            Some(DomainKind::Integer(_)) | Some(DomainKind::Text) | None => {}
+        }
+
+        let result = if is_text_type(ty) {
+            Ok(RawValue::from_text(ty, &encode_digest(&digest, options)))
+        } else if *ty == Type::UUID {
+            Ok(RawValue::new(
+                ty.clone(),
+                uuid_from_digest(&digest).to_vec(),
+            ))
+        } else if *ty == Type::BYTEA {
+            Ok(RawValue::new(ty.clone(), digest.to_vec()))
+        } else if *ty == Type::JSON || *ty == Type::JSONB {
+            RawValue::encode(
+                ty,
+                serde_json::Value::String(encode_digest(&digest, options)),
+            )
+        } else {
+            Err(format!("类型 {} 不支持哈希脱敏", ty))
+        };
+
+        result.map_err(|e| self.error(e))
+    }
+
+    fn error(&self, msg: String) -> DbError {
+        DbError::Mask(format!("列 {}: {}", self.column, msg))
+    }
+}
+
+/// 整数类型的位宽，其他类型为 None
+fn integer_bits(ty: &Type) -> Option<u32> {
+    if *ty == Type::INT2 {
+        Some(16)
+    } else if *ty == Type::INT4 {
+        Some(32)
+    } else if *ty == Type::INT8 {
+        Some(64)
+    } else {
+        None
+    }
+}
+
+/// 整数列的值，或文本列中可以解析为整数的值
+fn integer_value(value: &RawValue) -> Result<Option<i64>, String> {
+    let ty = &value.ty;
+    if *ty == Type::INT2 {
+        Ok(Some(value.decode::<i16>()? as i64))
+    } else if *ty == Type::INT4 {
+        Ok(Some(value.decode::<i32>()? as i64))
+    } else if *ty == Type::INT8 {
+        Ok(Some(value.decode::<i64>()?))
+    } else {
+        Ok(value
+            .as_text()
+            .ok()
+            .and_then(|text| text.trim().parse::<i64>().ok()))
+    }
+}
+
+/// 值的规范形式：整数为十进制文本，UUID 为标准格式文本，其他类型为原始字节
+fn canonical_bytes(value: &RawValue) -> Result<Vec<u8>, String> {
+    let ty = &value.ty;
+    let text = if *ty == Type::INT2 {
+        value.decode::<i16>()?.to_string()
+    } else if *ty == Type::INT4 {
+        value.decode::<i32>()?.to_string()
+    } else if *ty == Type::INT8 {
+        value.decode::<i64>()?.to_string()
+    } else if *ty == Type::UUID {
+        let bytes: [u8; 16] = value
+            .bytes
+            .as_slice()
+            .try_into()
+            .map_err(|_| "UUID 数据长度错误".to_string())?;
+        format_uuid(&bytes)
+    } else {
+        return Ok(value.bytes.clone());
+    };
+    Ok(text.into_bytes())
+}
+
+/// 按 UUID v4 设置版本号与变体位，保证结果是格式合法的 UUID
+fn uuid_from_digest(digest: &[u8]) -> [u8; 16] {
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    bytes
+}
+
+fn format_uuid(bytes: &[u8; 16]) -> String {
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+/// 文本列上哈希结果的最大字符数，用于在配置阶段检查是否超出列长度
+pub fn hash_text_length(options: Option<&HashOptions>, domain: Option<&MaskDomain>) -> usize {
+    match domain.map(|d| &d.kind) {
+        // 带符号的十进制整数，最长的是该位宽的最小值
+        Some(DomainKind::Integer(bits)) => (i64::MIN >> (64 - bits)).to_string().len(),
+        Some(DomainKind::Uuid) => 36,
+        Some(DomainKind::Text) | None => {
+            let length = match options.map(|o| &o.encoding) {
+                None | Some(HashEncoding::Hex) => 64,
+                Some(HashEncoding::Base64) => 43,
+                // 256 位整数的十进制位数
+                Some(HashEncoding::Numeric) => 78,
+            };
+            options
+                .and_then(|o| o.length)
+                .map_or(length, |limit| limit.min(length))
+        }
+    }
+}
+
+/// 按配置的编码与长度输出摘要
+fn encode_digest(digest: &[u8], options: Option<&HashOptions>) -> String {
+    let encoding = options.map(|o| o.encoding.clone()).unwrap_or_default();
+    let mut encoded = match encoding {
+        HashEncoding::Hex => digest.iter().map(|b| format!("{:02x}", b)).collect(),
+        HashEncoding::Base64 => BASE64.encode(digest),
+        HashEncoding::Numeric => digest_digits(digest),
+    };
+    if let Some(length) = options.and_then(|o| o.length) {
+        encoded.truncate(length.min(encoded.len()));
+    }
+    encoded
+}
+
+/// 将摘要视为大端整数转换为十进制数字串
+///
+/// 数字按从低位到高位的顺序输出，截断时保留的是分布均匀的低位数字。
+fn digest_digits(digest: &[u8]) -> String {
+    let mut number = digest.to_vec();
+    let mut digits = String::with_capacity(digest.len() * 3);
+    while number.iter().any(|b| *b != 0) {
+        let mut remainder = 0u32;
+        for byte in number.iter_mut() {
+            let current = (remainder << 8) | *byte as u32;
+            *byte = (current / 10) as u8;
+            remainder = current % 10;
+        }
+        digits.push(char::from(b'0' + remainder as u8));
+    }
+    digits
+}
+
+/// 按模式逐字符替换：`#` 保留原字符，`*` 替换为星号，其他字符原样输出
+fn apply_pattern(value: &str, pattern: &str) -> String {
+    if pattern.is_empty() {
+        return value.to_string();
+    }
+
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    for p in pattern.chars() {
+        match p {
+            '#' => {
+                if let Some(c) = chars.next() {
+                    result.push(c);
+                }
+            }
+            '*' => {
+                if chars.next().is_some() {
+                    result.push('*');
+                }
+            }
+            _ => result.push(p),
+        }
+    }
+    result
+}
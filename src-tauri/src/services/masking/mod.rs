@@ -0,0 +1,23 @@
+// 数据脱敏模块
+
+// 子模块
+mod audit;
+mod composite;
+mod domain;
+mod fake;
+mod formats;
+mod json_path;
+mod masker;
+mod perturb;
+mod pii;
+mod policy;
+mod shuffle;
+
+pub use audit::{build_audit_report, render_audit_html};
+pub use composite::{embeds_foreign_oids, field_type, remap_oids, value_type};
+pub use domain::*;
+pub use json_path::JsonPath;
+pub use masker::*;
+pub use pii::detect_pii;
+pub use policy::*;
+pub use shuffle::ColumnShuffler;
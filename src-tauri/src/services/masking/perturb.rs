@@ -0,0 +1,473 @@
+// 日期与数值扰动：在原生类型上保留数据分布，而不是直接破坏取值
+
+use crate::database::{decode_numeric, encode_numeric, RawValue};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+use rand::Rng;
+use rand_chacha::ChaCha20Rng;
+use std::cmp::Ordering;
+use tokio_postgres::types::Type;
+
+pub fn is_date_type(ty: &Type) -> bool {
+    [Type::DATE, Type::TIMESTAMP, Type::TIMESTAMPTZ].contains(ty)
+}
+
+pub fn is_numeric_type(ty: &Type) -> bool {
+    [
+        Type::INT2,
+        Type::INT4,
+        Type::INT8,
+        Type::FLOAT4,
+        Type::FLOAT8,
+        Type::NUMERIC,
+    ]
+    .contains(ty)
+}
+
+/// 将日期平移 [-days, days] 范围内的非零天数，时间部分保持不变
+///
+/// `infinity` 与 `-infinity` 保持原样。
+pub fn shift_date(value: &RawValue, rng: &mut ChaCha20Rng, days: u32) -> Result<RawValue, String> {
+    if is_infinite_date(value) {
+        return Ok(value.clone());
+    }
+    let days = days.max(1) as i64;
+    let offset = rng.gen_range(1..=days) * if rng.gen_bool(0.5) { 1 } else { -1 };
+    let offset = Duration::days(offset);
+    let ty = &value.ty;
+
+    if *ty == Type::DATE {
+        let date: NaiveDate = value.decode()?;
+        RawValue::encode(ty, date.checked_add_signed(offset).unwrap_or(date))
+    } else if *ty == Type::TIMESTAMP {
+        let timestamp: NaiveDateTime = value.decode()?;
+        RawValue::encode(
+            ty,
+            timestamp.checked_add_signed(offset).unwrap_or(timestamp),
+        )
+    } else if *ty == Type::TIMESTAMPTZ {
+        let timestamp: DateTime<Utc> = value.decode()?;
+        RawValue::encode(
+            ty,
+            timestamp.checked_add_signed(offset).unwrap_or(timestamp),
+        )
+    } else {
+        Err(format!("类型 {} 不支持日期平移", ty))
+    }
+}
+
+/// 日期类型的 `infinity` 与 `-infinity`，线格式为对应整数类型的最大值与最小值
+fn is_infinite_date(value: &RawValue) -> bool {
+    let bytes = value.bytes.as_slice();
+    if value.ty == Type::DATE {
+        <[u8; 4]>::try_from(bytes)
+            .map(i32::from_be_bytes)
+            .is_ok_and(|v| v == i32::MAX || v == i32::MIN)
+    } else {
+        <[u8; 8]>::try_from(bytes)
+            .map(i64::from_be_bytes)
+            .is_ok_and(|v| v == i64::MAX || v == i64::MIN)
+    }
+}
+
+/// 噪声系数转换为定点数时保留的小数位数
+const FACTOR_SCALE: u32 = 12;
+
+/// 按比例添加随机噪声，`percent` 为最大偏移百分比
+pub fn add_noise(
+    value: &RawValue,
+    rng: &mut ChaCha20Rng,
+    percent: f64,
+) -> Result<RawValue, String> {
+    if !percent.is_finite() {
+        return Err(format!("噪声百分比 {} 无效", percent));
+    }
+    let ratio = percent.abs() / 100.0;
+    let factor = 1.0 + rng.gen_range(-ratio..=ratio);
+    map_number(
+        value,
+        |v| v * factor,
+        |d| {
+            // 系数的精度随原值位数降低，保证乘积不超出 i128
+            let digits = d.mantissa.unsigned_abs().checked_ilog10().unwrap_or(0) + 1;
+            let scale = FACTOR_SCALE.min(37u32.saturating_sub(digits));
+            let fixed = Decimal {
+                mantissa: (factor * 10f64.powi(scale as i32)).round() as i128,
+                scale,
+            };
+            d.checked_mul(fixed)
+        },
+    )
+}
+
+/// 取整到 `step` 的整数倍，用于分桶
+pub fn round_to_step(value: &RawValue, step: f64) -> Result<RawValue, String> {
+    if step <= 0.0 {
+        return Err("取整步长必须大于 0".to_string());
+    }
+    let fixed = Decimal::from_f64(step).ok_or_else(|| format!("取整步长 {} 超出精度范围", step))?;
+    map_number(
+        value,
+        |v| (v / step).round() * step,
+        |d| d.round_to_step(fixed),
+    )
+}
+
+/// 限制在 [min, max] 范围内，范围内的值原样保留
+pub fn clamp(value: &RawValue, min: Option<f64>, max: Option<f64>) -> Result<RawValue, String> {
+    let ty = &value.ty;
+
+    if *ty == Type::FLOAT4 || *ty == Type::FLOAT8 {
+        let v = if *ty == Type::FLOAT4 {
+            value.decode::<f32>()? as f64
+        } else {
+            value.decode::<f64>()?
+        };
+        let bound = match (min, max) {
+            (Some(min), _) if v < min => min,
+            (_, Some(max)) if v > max => max,
+            _ => return Ok(value.clone()),
+        };
+        return if *ty == Type::FLOAT4 {
+            RawValue::encode(ty, bound as f32)
+        } else {
+            RawValue::encode(ty, bound)
+        };
+    }
+
+    // 超出 38 位有效数字的 numeric 退回按 f64 比较，范围内的值同样原样保留
+    let compare: Box<dyn Fn(f64) -> Option<Ordering>> = match decimal_value(value) {
+        Ok(Some(number)) => Box::new(move |bound| number.cmp_f64(bound)),
+        // NaN 与无穷保持原样
+        Ok(None) => return Ok(value.clone()),
+        Err(e) if *ty == Type::NUMERIC => {
+            let approx: f64 = decode_numeric(&value.bytes)?.parse().map_err(|_| e)?;
+            Box::new(move |bound| approx.partial_cmp(&bound))
+        }
+        Err(e) => return Err(e),
+    };
+    let bound = match (min, max) {
+        (Some(min), _) if compare(min) == Some(Ordering::Less) => min,
+        (_, Some(max)) if compare(max) == Some(Ordering::Greater) => max,
+        _ => return Ok(value.clone()),
+    };
+    let bound =
+        Decimal::from_f64(bound).ok_or_else(|| format!("范围边界 {} 超出精度范围", bound))?;
+    encode_decimal(value, bound)
+}
+
+/// 按原类型对数值做运算后写回
+///
+/// 浮点类型用 f64 计算；整数和 numeric 用十进制定点数计算，避免超过 2^53 的整数或
+/// 有效数字较多的 numeric 经过 f64 后被改写。整数类型四舍五入并限制在类型范围内，
+/// numeric 保留原值的小数位数。
+fn map_number(
+    value: &RawValue,
+    float: impl Fn(f64) -> f64,
+    decimal: impl Fn(Decimal) -> Option<Decimal>,
+) -> Result<RawValue, String> {
+    let ty = &value.ty;
+    if *ty == Type::FLOAT4 {
+        return RawValue::encode(ty, float(value.decode::<f32>()? as f64) as f32);
+    }
+    if *ty == Type::FLOAT8 {
+        return RawValue::encode(ty, float(value.decode::<f64>()?));
+    }
+
+    let number = match decimal_value(value)? {
+        Some(number) => number,
+        // NaN 与无穷保持原样
+        None => return Ok(value.clone()),
+    };
+    let result =
+        decimal(number).ok_or_else(|| "数值超出 38 位有效数字，无法精确计算".to_string())?;
+    encode_decimal(value, result)
+}
+
+/// 整数或 numeric 的值，NaN 与无穷返回 None
+fn decimal_value(value: &RawValue) -> Result<Option<Decimal>, String> {
+    let ty = &value.ty;
+    let integer = |v: i64| Ok(Some(Decimal::from_integer(v)));
+    if *ty == Type::INT2 {
+        integer(value.decode::<i16>()? as i64)
+    } else if *ty == Type::INT4 {
+        integer(value.decode::<i32>()? as i64)
+    } else if *ty == Type::INT8 {
+        integer(value.decode::<i64>()?)
+    } else if *ty == Type::NUMERIC {
+        let text = decode_numeric(&value.bytes)?;
+        if text == "NaN" || text.ends_with("Infinity") {
+            return Ok(None);
+        }
+        Decimal::parse(&text)
+            .map(Some)
+            .ok_or_else(|| format!("numeric 值 {} 超出 38 位有效数字，无法精确计算", text))
+    } else {
+        Err(format!("类型 {} 不是数值类型", ty))
+    }
+}
+
+/// 按原值的类型写回：整数四舍五入并限制在类型范围内，numeric 保留原值的小数位数
+fn encode_decimal(original: &RawValue, number: Decimal) -> Result<RawValue, String> {
+    let ty = &original.ty;
+    let integer = || number.round(0).map(|d| d.mantissa);
+    if *ty == Type::INT2 {
+        let v = integer().unwrap_or(number.mantissa.signum() * i128::MAX);
+        RawValue::encode(ty, v.clamp(i16::MIN as i128, i16::MAX as i128) as i16)
+    } else if *ty == Type::INT4 {
+        let v = integer().unwrap_or(number.mantissa.signum() * i128::MAX);
+        RawValue::encode(ty, v.clamp(i32::MIN as i128, i32::MAX as i128) as i32)
+    } else if *ty == Type::INT8 {
+        let v = integer().unwrap_or(number.mantissa.signum() * i128::MAX);
+        RawValue::encode(ty, v.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    } else if *ty == Type::NUMERIC {
+        let scale = decode_numeric(&original.bytes)?
+            .split_once('.')
+            .map_or(0, |(_, fraction)| fraction.len() as u32);
+        // 增加小数位数会溢出时按边界本身的位数写出，由目标列的精度约束取舍
+        let number = number.round(scale).unwrap_or(number);
+        Ok(RawValue::new(
+            ty.clone(),
+            encode_numeric(&number.to_string())?,
+        ))
+    } else {
+        Err(format!("类型 {} 不是数值类型", ty))
+    }
+}
+
+/// 十进制定点数，值为 `mantissa / 10^scale`，最多 38 位有效数字
+#[derive(Debug, Clone, Copy)]
+struct Decimal {
+    mantissa: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    fn from_integer(value: i64) -> Self {
+        Self {
+            mantissa: value as i128,
+            scale: 0,
+        }
+    }
+
+    /// 解析十进制字符串，超出 i128 范围时返回 None
+    fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let (negative, unsigned) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let (int_part, frac_part) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        if int_part.is_empty() && frac_part.is_empty() {
+            return None;
+        }
+        let mut mantissa: i128 = 0;
+        for c in int_part.chars().chain(frac_part.chars()) {
+            let digit = c.to_digit(10)? as i128;
+            mantissa = mantissa.checked_mul(10)?.checked_add(digit)?;
+        }
+        Some(Self {
+            mantissa: if negative { -mantissa } else { mantissa },
+            scale: frac_part.len() as u32,
+        })
+    }
+
+    /// 按 f64 的最短十进制表示转换，如 0.1 转换为精确的 0.1
+    fn from_f64(value: f64) -> Option<Self> {
+        if value.is_finite() {
+            Self::parse(&value.to_string())
+        } else {
+            None
+        }
+    }
+
+    /// 调整到指定小数位数，减少位数时四舍五入（远离零），溢出时返回 None
+    fn round(self, scale: u32) -> Option<Self> {
+        let mantissa = if scale >= self.scale {
+            self.mantissa
+                .checked_mul(10i128.checked_pow(scale - self.scale)?)?
+        } else {
+            let divisor = 10i128.checked_pow(self.scale - scale)?;
+            div_round(self.mantissa, divisor)
+        };
+        Some(Self { mantissa, scale })
+    }
+
+    fn checked_mul(self, other: Self) -> Option<Self> {
+        Some(Self {
+            mantissa: self.mantissa.checked_mul(other.mantissa)?,
+            scale: self.scale + other.scale,
+        })
+    }
+
+    /// 取整到 `step` 的整数倍（四舍五入，远离零）
+    fn round_to_step(self, step: Self) -> Option<Self> {
+        let scale = self.scale.max(step.scale);
+        let value = self.round(scale)?.mantissa;
+        let step = step.round(scale)?.mantissa;
+        if step == 0 {
+            return None;
+        }
+        Some(Self {
+            mantissa: div_round(value, step).checked_mul(step)?,
+            scale,
+        })
+    }
+
+    /// 与 f64 比较，能转换为定点数时精确比较，否则退回按 f64 比较
+    fn cmp_f64(self, other: f64) -> Option<Ordering> {
+        let exact = Self::from_f64(other).and_then(|other| {
+            let scale = self.scale.max(other.scale);
+            Some(
+                self.round(scale)?
+                    .mantissa
+                    .cmp(&other.round(scale)?.mantissa),
+            )
+        });
+        exact.or_else(|| (self.mantissa as f64 / 10f64.powi(self.scale as i32)).partial_cmp(&other))
+    }
+}
+
+impl std::fmt::Display for Decimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let scale = self.scale as usize;
+        let digits = format!(
+            "{}{}",
+            "0".repeat((scale + 1).saturating_sub(digits.len())),
+            digits
+        );
+        let (int_part, frac_part) = digits.split_at(digits.len() - scale);
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        if frac_part.is_empty() {
+            write!(f, "{}{}", sign, int_part)
+        } else {
+            write!(f, "{}{}.{}", sign, int_part, frac_part)
+        }
+    }
+}
+
+/// 整数除法，四舍五入（远离零）
+fn div_round(value: i128, divisor: i128) -> i128 {
+    let quotient = value / divisor;
+    let remainder = value % divisor;
+    if remainder.unsigned_abs() * 2 >= divisor.unsigned_abs() {
+        quotient + value.signum() * divisor.signum()
+    } else {
+        quotient
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn decimal(text: &str) -> Decimal {
+        Decimal::parse(text).unwrap()
+    }
+
+    fn numeric(text: &str) -> RawValue {
+        RawValue::new(Type::NUMERIC, encode_numeric(text).unwrap())
+    }
+
+    #[test]
+    fn parses_decimals() {
+        let d = decimal("12.345");
+        assert_eq!((d.mantissa, d.scale), (12345, 3));
+        let d = decimal(" -0.50 ");
+        assert_eq!((d.mantissa, d.scale), (-50, 2));
+        let d = decimal("+7");
+        assert_eq!((d.mantissa, d.scale), (7, 0));
+        let d = decimal(".5");
+        assert_eq!((d.mantissa, d.scale), (5, 1));
+
+        for text in ["", "-", ".", "1e5", "1.2.3", "abc"] {
+            assert!(Decimal::parse(text).is_none(), "{}", text);
+        }
+        // 超出 i128 的有效数字
+        assert!(Decimal::parse(&"9".repeat(40)).is_none());
+        assert!(Decimal::parse(&"9".repeat(38)).is_some());
+    }
+
+    #[test]
+    fn rounds_half_away_from_zero() {
+        assert_eq!(decimal("1.25").round(1).unwrap().to_string(), "1.3");
+        assert_eq!(decimal("-1.25").round(1).unwrap().to_string(), "-1.3");
+        assert_eq!(decimal("1.24").round(1).unwrap().to_string(), "1.2");
+        assert_eq!(decimal("0.5").round(0).unwrap().to_string(), "1");
+        assert_eq!(decimal("1.5").round(3).unwrap().to_string(), "1.500");
+        assert!(decimal(&"9".repeat(38)).round(2).is_none());
+
+        assert_eq!(div_round(5, 2), 3);
+        assert_eq!(div_round(-5, 2), -3);
+        assert_eq!(div_round(5, -2), -3);
+        assert_eq!(div_round(4, 3), 1);
+        assert_eq!(div_round(-4, 3), -1);
+        assert_eq!(div_round(7, 7), 1);
+        assert_eq!(div_round(0, 3), 0);
+    }
+
+    #[test]
+    fn displays_decimals() {
+        let display = |mantissa, scale| Decimal { mantissa, scale }.to_string();
+        assert_eq!(display(5, 3), "0.005");
+        assert_eq!(display(-5, 3), "-0.005");
+        assert_eq!(display(12345, 2), "123.45");
+        assert_eq!(display(42, 0), "42");
+        assert_eq!(display(0, 0), "0");
+        assert_eq!(display(0, 2), "0.00");
+        assert_eq!(display(i128::MIN + 1, 0), (i128::MIN + 1).to_string());
+    }
+
+    #[test]
+    fn rounds_to_step() {
+        let step = |value: &str, step: f64| {
+            let rounded = round_to_step(&numeric(value), step).unwrap();
+            decode_numeric(&rounded.bytes).unwrap()
+        };
+        assert_eq!(step("17", 5.0), "15");
+        assert_eq!(step("17.5", 5.0), "20.0");
+        assert_eq!(step("-17.5", 5.0), "-20.0");
+        assert_eq!(step("1.234", 0.1), "1.200");
+        // 超过 2^53 的整数不经过 f64
+        let big = RawValue::encode(&Type::INT8, 9_007_199_254_740_993i64).unwrap();
+        let rounded = round_to_step(&big, 10.0).unwrap();
+        assert_eq!(rounded.decode::<i64>().unwrap(), 9_007_199_254_740_990);
+        assert!(round_to_step(&numeric("1"), 0.0).is_err());
+    }
+
+    #[test]
+    fn noise_stays_in_range() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1);
+        for _ in 0..100 {
+            let noisy = add_noise(&numeric("1000.00"), &mut rng, 10.0).unwrap();
+            let noisy = decimal(&decode_numeric(&noisy.bytes).unwrap());
+            assert_eq!(noisy.scale, 2);
+            assert!((90000..=110000).contains(&noisy.mantissa));
+        }
+        for percent in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(add_noise(&numeric("1"), &mut rng, percent).is_err());
+        }
+        let nan = numeric("NaN");
+        assert_eq!(add_noise(&nan, &mut rng, 10.0).unwrap().bytes, nan.bytes);
+    }
+
+    #[test]
+    fn keeps_infinite_dates() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1);
+        for value in [
+            RawValue::new(Type::DATE, i32::MAX.to_be_bytes().to_vec()),
+            RawValue::new(Type::DATE, i32::MIN.to_be_bytes().to_vec()),
+            RawValue::new(Type::TIMESTAMP, i64::MAX.to_be_bytes().to_vec()),
+            RawValue::new(Type::TIMESTAMPTZ, i64::MIN.to_be_bytes().to_vec()),
+        ] {
+            assert_eq!(shift_date(&value, &mut rng, 30).unwrap().bytes, value.bytes);
+        }
+
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let value = RawValue::encode(&Type::DATE, date).unwrap();
+        let shifted: NaiveDate = shift_date(&value, &mut rng, 30).unwrap().decode().unwrap();
+        let days = (shifted - date).num_days();
+        assert!(days != 0 && days.abs() <= 30);
+    }
+}
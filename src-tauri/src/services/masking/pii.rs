@@ -0,0 +1,342 @@
+// 敏感数据检测：根据列名关键字和抽样数据为未配置脱敏的列给出建议规则
+//
+// 列名和样本各自给出一个置信度，两者都命中时按独立证据合并。
+
+use crate::database::{MaskRule, MaskRuleType, MaskSuggestion, PerturbOptions, PiiKind};
+use crate::services::masking::formats::{is_luhn_valid, is_valid_id_card};
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// 低于该置信度的检测结果不作为建议返回
+const MIN_CONFIDENCE: f64 = 0.3;
+
+/// 样本数少于该值时降低样本证据的权重
+const MIN_CONFIDENT_SAMPLES: usize = 5;
+
+/// 列名关键字及其置信度
+///
+/// 关键字按单词匹配：列名拆分后须有连续的完整单词拼接起来与去掉下划线的关键字相同，
+/// 避免 `tel` 误中 `hotel`、`id_no` 误中 `paid_note` 这类情况。
+/// 非 ASCII 关键字（中文列名通常不分词）在单词内做子串匹配。
+const NAME_KEYWORDS: &[(&str, PiiKind, f64)] = &[
+    ("email", PiiKind::Email, 0.8),
+    ("mail", PiiKind::Email, 0.6),
+    ("邮箱", PiiKind::Email, 0.8),
+    ("phone", PiiKind::Phone, 0.8),
+    ("mobile", PiiKind::Phone, 0.8),
+    ("cellphone", PiiKind::Phone, 0.8),
+    ("telephone", PiiKind::Phone, 0.8),
+    ("tel", PiiKind::Phone, 0.6),
+    ("手机", PiiKind::Phone, 0.8),
+    ("电话", PiiKind::Phone, 0.8),
+    ("id_card", PiiKind::IdCard, 0.9),
+    ("id_no", PiiKind::IdCard, 0.7),
+    ("id_number", PiiKind::IdCard, 0.7),
+    ("identity", PiiKind::IdCard, 0.6),
+    ("ssn", PiiKind::IdCard, 0.8),
+    ("身份证", PiiKind::IdCard, 0.9),
+    ("bank_card", PiiKind::BankCard, 0.9),
+    ("card_no", PiiKind::BankCard, 0.7),
+    ("card_number", PiiKind::BankCard, 0.7),
+    ("account_no", PiiKind::BankCard, 0.6),
+    ("iban", PiiKind::BankCard, 0.8),
+    ("银行卡", PiiKind::BankCard, 0.9),
+    ("卡号", PiiKind::BankCard, 0.7),
+    ("real_name", PiiKind::Name, 0.8),
+    ("full_name", PiiKind::Name, 0.8),
+    ("first_name", PiiKind::Name, 0.8),
+    ("last_name", PiiKind::Name, 0.8),
+    ("nickname", PiiKind::Name, 0.5),
+    ("name", PiiKind::Name, 0.3),
+    ("姓名", PiiKind::Name, 0.8),
+    ("address", PiiKind::Address, 0.8),
+    ("addr", PiiKind::Address, 0.7),
+    ("street", PiiKind::Address, 0.6),
+    ("地址", PiiKind::Address, 0.8),
+    ("住址", PiiKind::Address, 0.8),
+    ("password", PiiKind::Credential, 0.9),
+    ("passwd", PiiKind::Credential, 0.9),
+    ("pwd", PiiKind::Credential, 0.8),
+    ("secret", PiiKind::Credential, 0.8),
+    ("token", PiiKind::Credential, 0.8),
+    ("api_key", PiiKind::Credential, 0.9),
+    ("密码", PiiKind::Credential, 0.9),
+    ("ip", PiiKind::IpAddress, 0.6),
+    ("ip_address", PiiKind::IpAddress, 0.8),
+    ("birthday", PiiKind::BirthDate, 0.8),
+    ("birth_date", PiiKind::BirthDate, 0.8),
+    ("dob", PiiKind::BirthDate, 0.6),
+    ("生日", PiiKind::BirthDate, 0.8),
+    ("出生", PiiKind::BirthDate, 0.7),
+];
+
+struct Detectors {
+    email: Regex,
+    mobile: Regex,
+    international_phone: Regex,
+    id_card: Regex,
+    bank_card: Regex,
+    ipv4: Regex,
+    password_hash: Regex,
+}
+
+fn detectors() -> &'static Detectors {
+    static DETECTORS: OnceLock<Detectors> = OnceLock::new();
+    DETECTORS.get_or_init(|| {
+        let build = |pattern: &str| Regex::new(pattern).expect("内置正则表达式无效");
+        Detectors {
+            email: build(r"^[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}$"),
+            mobile: build(r"^(\+?86[- ]?)?1[3-9]\d[- ]?\d{4}[- ]?\d{4}$"),
+            international_phone: build(r"^\+\d{1,3}[- ]?\d[\d -]{6,14}\d$"),
+            id_card: build(r"^\d{17}[\dXx]$"),
+            bank_card: build(r"^\d{4}[ -]?\d{4}[ -]?\d{4}[ -]?\d{1,7}$"),
+            ipv4: build(r"^(25[0-5]|2[0-4]\d|1?\d?\d)(\.(25[0-5]|2[0-4]\d|1?\d?\d)){3}$"),
+            password_hash: build(r"^(\$2[aby]\$\d{2}\$[./A-Za-z0-9]{53}|\$argon2(id|i|d)\$.+)$"),
+        }
+    })
+}
+
+/// 判断单个样本值属于哪类敏感数据
+fn classify_value(value: &str) -> Option<PiiKind> {
+    let d = detectors();
+    let value = value.trim();
+    if d.email.is_match(value) {
+        Some(PiiKind::Email)
+    } else if d.id_card.is_match(value) && is_valid_id_card(value) {
+        // 身份证号需在银行卡号之前判断，18 位数字也可能恰好通过 Luhn 校验
+        Some(PiiKind::IdCard)
+    } else if d.mobile.is_match(value) || d.international_phone.is_match(value) {
+        Some(PiiKind::Phone)
+    } else if d.bank_card.is_match(value) && is_luhn_valid(value) {
+        Some(PiiKind::BankCard)
+    } else if d.ipv4.is_match(value) {
+        Some(PiiKind::IpAddress)
+    } else if d.password_hash.is_match(value) {
+        Some(PiiKind::Credential)
+    } else {
+        None
+    }
+}
+
+/// 将列名拆分为小写单词，兼容下划线、短横线和驼峰命名
+fn split_words(column: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+    for c in column.chars() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            prev_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && prev_lower && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        prev_lower = c.is_lowercase() || c.is_ascii_digit();
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+/// 根据列名判断敏感数据类别，返回置信度最高的一项
+fn match_column_name(column: &str) -> Option<(PiiKind, f64, &'static str)> {
+    let words = split_words(column);
+
+    NAME_KEYWORDS
+        .iter()
+        .filter(|(keyword, _, _)| {
+            if keyword.is_ascii() {
+                matches_word_sequence(&words, &keyword.replace('_', ""))
+            } else {
+                words.iter().any(|word| word.contains(keyword))
+            }
+        })
+        .max_by(|a, b| a.2.total_cmp(&b.2))
+        .map(|(keyword, kind, confidence)| (*kind, *confidence, *keyword))
+}
+
+/// 列名中是否有连续的若干个完整单词拼接后恰好等于 `target`
+///
+/// `id_card`、`idCard`、`idcard` 都能命中 `idcard`，`paid_note` 不会命中 `idno`
+fn matches_word_sequence(words: &[String], target: &str) -> bool {
+    (0..words.len()).any(|start| {
+        let mut joined = String::new();
+        for word in &words[start..] {
+            joined.push_str(word);
+            if !target.starts_with(joined.as_str()) {
+                return false;
+            }
+            if joined.len() == target.len() {
+                return true;
+            }
+        }
+        false
+    })
+}
+
+/// 根据样本判断敏感数据类别，返回命中最多的类别及其命中比例
+fn match_samples(samples: &[String]) -> Option<(PiiKind, f64, usize)> {
+    let samples: Vec<&str> = samples
+        .iter()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect();
+    if samples.is_empty() {
+        return None;
+    }
+
+    let mut counts: Vec<(PiiKind, usize)> = Vec::new();
+    for sample in &samples {
+        if let Some(kind) = classify_value(sample) {
+            match counts.iter_mut().find(|(k, _)| *k == kind) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((kind, 1)),
+            }
+        }
+    }
+
+    counts
+        .into_iter()
+        .max_by_key(|(_, count)| *count)
+        .map(|(kind, count)| (kind, count as f64 / samples.len() as f64, samples.len()))
+}
+
+/// 是否为文本列，`data_type` 为 information_schema 中的类型名称
+fn is_text_data_type(data_type: &str) -> bool {
+    matches!(
+        data_type,
+        "text" | "character varying" | "character" | "citext"
+    )
+}
+
+fn is_date_data_type(data_type: &str) -> bool {
+    data_type == "date" || data_type.starts_with("timestamp")
+}
+
+/// 为检测到的类别生成建议规则，列类型不支持时返回 None
+fn suggested_rule(kind: PiiKind, data_type: &str) -> Option<MaskRule> {
+    let text = is_text_data_type(data_type);
+    let rule_type = match kind {
+        PiiKind::Email if text => MaskRuleType::Email,
+        PiiKind::Phone if text => MaskRuleType::Phone,
+        PiiKind::IdCard if text => MaskRuleType::IdCard,
+        PiiKind::BankCard if text => MaskRuleType::BankCard,
+        PiiKind::Name if text => MaskRuleType::FakeName,
+        PiiKind::Address if text => MaskRuleType::FakeAddress,
+        PiiKind::Credential if text => MaskRuleType::Hmac,
+        PiiKind::IpAddress if text => MaskRuleType::Hash,
+        PiiKind::BirthDate if is_date_data_type(data_type) => MaskRuleType::DateShift,
+        PiiKind::BirthDate if text => MaskRuleType::Fixed,
+        _ => return None,
+    };
+
+    let perturb_options = (rule_type == MaskRuleType::DateShift).then(|| PerturbOptions {
+        days: Some(180),
+        ..Default::default()
+    });
+
+    Some(MaskRule {
+        rule_type,
+        perturb_options,
+        ..Default::default()
+    })
+}
+
+/// 检测列是否包含敏感数据
+///
+/// `data_type` 为 information_schema 中的类型名称，`samples` 为抽样得到的非空值的文本形式。
+pub fn detect_pii(column: &str, data_type: &str, samples: &[String]) -> Option<MaskSuggestion> {
+    let by_name = match_column_name(column);
+    let by_samples = match_samples(samples).filter(|(_, ratio, _)| *ratio >= 0.5);
+
+    let mut reasons = Vec::new();
+    let (kind, confidence) = match (by_name, by_samples) {
+        (Some((name_kind, name_confidence, keyword)), Some((sample_kind, ratio, count))) => {
+            reasons.push(format!("列名包含关键字 {}", keyword));
+            reasons.push(format!(
+                "{:.0}% 的样本（共 {} 个）符合{}格式",
+                ratio * 100.0,
+                count,
+                kind_label(sample_kind)
+            ));
+            let sample_confidence = sample_confidence(ratio, count);
+            if name_kind == sample_kind {
+                (
+                    name_kind,
+                    1.0 - (1.0 - name_confidence) * (1.0 - sample_confidence),
+                )
+            } else if sample_confidence >= name_confidence {
+                // 列名和数据不一致时以更可信的一方为准
+                (sample_kind, sample_confidence)
+            } else {
+                (name_kind, name_confidence)
+            }
+        }
+        (Some((kind, confidence, keyword)), None) => {
+            reasons.push(format!("列名包含关键字 {}", keyword));
+            (kind, confidence)
+        }
+        (None, Some((kind, ratio, count))) => {
+            reasons.push(format!(
+                "{:.0}% 的样本（共 {} 个）符合{}格式",
+                ratio * 100.0,
+                count,
+                kind_label(kind)
+            ));
+            (kind, sample_confidence(ratio, count))
+        }
+        (None, None) => return None,
+    };
+
+    if confidence < MIN_CONFIDENCE {
+        return None;
+    }
+
+    let rule = suggested_rule(kind, data_type)?;
+    Some(MaskSuggestion {
+        kind,
+        rule,
+        confidence: (confidence * 100.0).round() / 100.0,
+        reasons,
+    })
+}
+
+/// 仅根据列名判断列是否像是敏感数据，返回类别、置信度和判断依据
+pub fn sensitive_column_name(column: &str) -> Option<(PiiKind, f64, String)> {
+    match_column_name(column)
+        .filter(|(_, confidence, _)| *confidence >= MIN_CONFIDENCE)
+        .map(|(kind, confidence, keyword)| {
+            (
+                kind,
+                confidence,
+                format!("列名包含{}关键字 {}", kind_label(kind), keyword),
+            )
+        })
+}
+
+fn kind_label(kind: PiiKind) -> &'static str {
+    match kind {
+        PiiKind::Email => "邮箱",
+        PiiKind::Phone => "电话号码",
+        PiiKind::IdCard => "身份证号",
+        PiiKind::BankCard => "银行卡号",
+        PiiKind::Name => "姓名",
+        PiiKind::Address => "地址",
+        PiiKind::Credential => "密码哈希",
+        PiiKind::IpAddress => "IP 地址",
+        PiiKind::BirthDate => "出生日期",
+    }
+}
+
+fn sample_confidence(ratio: f64, count: usize) -> f64 {
+    if count < MIN_CONFIDENT_SAMPLES {
+        ratio * 0.6
+    } else {
+        ratio * 0.95
+    }
+}
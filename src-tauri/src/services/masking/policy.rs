@@ -0,0 +1,114 @@
+// 脱敏策略：按表名、列名模式为列补充脱敏规则
+
+use crate::database::{MaskPolicy, MaskRule, TableConfig};
+use serde::Serialize;
+
+/// 列最终生效的脱敏规则及其来源
+#[derive(Debug, Serialize, Clone)]
+pub struct EffectiveMaskRule {
+    pub table: String,
+    pub column: String,
+    pub rule: MaskRule,
+    /// 来源策略名称，为空表示列上单独配置的规则
+    pub policy: Option<String>,
+}
+
+/// 不区分大小写的通配符匹配，`*` 匹配任意字符串，`?` 匹配单个字符
+pub fn pattern_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.trim().to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    // 回溯到最近一个 `*` 的贪心匹配
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// 在策略中查找第一条匹配该列的规则
+fn find_policy_rule<'a>(
+    policies: &'a [MaskPolicy],
+    table: &str,
+    column: &str,
+) -> Option<(&'a MaskPolicy, &'a MaskRule)> {
+    policies.iter().find_map(|policy| {
+        policy
+            .rules
+            .iter()
+            .find(|r| {
+                r.table_pattern
+                    .as_deref()
+                    .is_none_or(|pattern| pattern_matches(pattern, table))
+                    && pattern_matches(&r.column_pattern, column)
+            })
+            .map(|r| (policy, &r.rule))
+    })
+}
+
+/// 将策略应用到表配置，返回所有脱敏列最终生效的规则
+///
+/// 已配置规则的列保持不变：选中的列使用自己的规则，字段规则按 `列.字段` 列出，
+/// 未选中的列视为用户有意关闭脱敏，不再套用策略。只有没有配置规则的列才会在匹配到
+/// 策略规则时使用该规则并标记为选中。
+pub fn apply_mask_policies(
+    tables: &mut [TableConfig],
+    policies: &[MaskPolicy],
+) -> Vec<EffectiveMaskRule> {
+    let mut effective = Vec::new();
+
+    for table in tables.iter_mut().filter(|t| !t.structure_only) {
+        for column in table.columns.iter_mut() {
+            // 配置了字段规则的复合类型列不再套用策略
+            if !column.field_rules.is_empty() {
+                if column.selected {
+                    effective.extend(column.field_rules.iter().map(|f| EffectiveMaskRule {
+                        table: table.name.clone(),
+                        column: format!("{}.{}", column.name, f.field),
+                        rule: f.rule.clone(),
+                        policy: None,
+                    }));
+                }
+                continue;
+            }
+
+            if let Some(rule) = &column.mask_rule {
+                if column.selected {
+                    effective.push(EffectiveMaskRule {
+                        table: table.name.clone(),
+                        column: column.name.clone(),
+                        rule: rule.clone(),
+                        policy: None,
+                    });
+                }
+                continue;
+            }
+
+            if let Some((policy, rule)) = find_policy_rule(policies, &table.name, &column.name) {
+                column.mask_rule = Some(rule.clone());
+                column.selected = true;
+                effective.push(EffectiveMaskRule {
+                    table: table.name.clone(),
+                    column: column.name.clone(),
+                    rule: rule.clone(),
+                    policy: Some(policy.name.clone()),
+                });
+            }
+        }
+    }
+
+    effective
+}
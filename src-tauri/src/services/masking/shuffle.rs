@@ -0,0 +1,472 @@
+// 列洗牌：在表内（或分组内）随机交换各行的值
+//
+// 复制分两遍进行：第一遍读取洗牌列的全部取值，第二遍复制时依次取出打乱后的值。
+// 缓冲（包括分组键）不超过内存上限时，各分组的值在内存中打乱后依次取出。
+// 超过上限后，缓冲中的值各配一个随机标签，按（分组, 标签）排序后作为一段写入临时文件，
+// 内存清空；取值前将所有段归并为按分组连续存放的文件，每组内按随机标签排序即为
+// 均匀的随机排列，内存中只保留每组的摘要和位置。
+
+use crate::database::{DbError, RawValue};
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use sha2::{Digest, Sha256};
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap};
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use tokio_postgres::types::Type;
+
+/// 每个值在缓冲中除数据外的估算开销
+const VALUE_OVERHEAD: usize = 32;
+
+/// 每个分组在缓冲中除分组键外的估算开销
+const GROUP_OVERHEAD: usize = 64;
+
+/// 归并时每段读取缓冲的大小范围
+const MIN_READ_CHUNK: usize = 4 * 1024;
+const MAX_READ_CHUNK: usize = 1024 * 1024;
+
+/// 分组键、随机标签和值
+type TaggedRecord = (Option<Vec<u8>>, u64, Option<Vec<u8>>);
+
+/// 归并后的文件中一个分组的剩余部分
+struct Segment {
+    offset: u64,
+    remaining: usize,
+}
+
+/// 归并完成后的取值状态
+struct Sorted {
+    file: File,
+    segments: HashMap<[u8; 16], Segment>,
+}
+
+pub struct ColumnShuffler {
+    column: String,
+    ty: Type,
+    memory_limit: usize,
+    buffered_bytes: usize,
+    groups: HashMap<Option<Vec<u8>>, Vec<Option<Vec<u8>>>>,
+    /// 已写入临时文件的各段，记录起止位置
+    runs: Vec<(u64, u64)>,
+    /// 已写入临时文件的值的数量
+    spilled: usize,
+    spill: Option<File>,
+    spill_len: u64,
+    sorted: Option<Sorted>,
+    /// 第一次取值时打乱内存中的缓冲，或归并临时文件中的各段
+    draining: bool,
+    rng: ChaCha20Rng,
+}
+
+impl ColumnShuffler {
+    pub fn new(column: &str, ty: &Type, memory_limit: usize) -> Self {
+        Self {
+            column: column.to_string(),
+            ty: ty.clone(),
+            memory_limit,
+            buffered_bytes: 0,
+            groups: HashMap::new(),
+            runs: Vec::new(),
+            spilled: 0,
+            spill: None,
+            spill_len: 0,
+            sorted: None,
+            draining: false,
+            rng: ChaCha20Rng::from_entropy(),
+        }
+    }
+
+    /// 第一遍：记录一个值，`group` 为分组列的原值
+    pub fn push(
+        &mut self,
+        group: Option<&RawValue>,
+        value: Option<RawValue>,
+    ) -> Result<(), DbError> {
+        if self.draining {
+            return Err(self.error("取值开始后不能再写入".to_string()));
+        }
+
+        let value = value.map(|v| v.bytes);
+        self.buffered_bytes += value.as_ref().map_or(0, Vec::len) + VALUE_OVERHEAD;
+        let values = match self.groups.entry(group.map(|g| g.bytes.clone())) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                self.buffered_bytes += entry.key().as_ref().map_or(0, Vec::len) + GROUP_OVERHEAD;
+                entry.insert(Vec::new())
+            }
+        };
+        values.push(value);
+
+        if self.buffered_bytes > self.memory_limit {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    /// 第二遍：取出分组内下一个打乱后的值
+    pub fn next(&mut self, group: Option<&RawValue>) -> Result<Option<RawValue>, DbError> {
+        if !self.draining {
+            self.draining = true;
+            if self.runs.is_empty() {
+                let rng = &mut self.rng;
+                for values in self.groups.values_mut() {
+                    values.shuffle(rng);
+                }
+            } else {
+                self.spill()?;
+                self.merge()?;
+            }
+        }
+
+        let value = match self.take(group)? {
+            Some(value) => value,
+            None => {
+                return Err(self.error(
+                    "第二遍读取的行数多于第一遍，表数据可能在复制过程中发生了变化".to_string(),
+                ))
+            }
+        };
+        Ok(value.map(|bytes| RawValue::new(self.ty.clone(), bytes)))
+    }
+
+    /// 取出分组内的下一个值，分组已取完时返回 `None`
+    fn take(&mut self, group: Option<&RawValue>) -> Result<Option<Option<Vec<u8>>>, DbError> {
+        let Some(sorted) = self.sorted.as_mut() else {
+            let key = group.map(|g| g.bytes.clone());
+            return Ok(self.groups.get_mut(&key).and_then(Vec::pop));
+        };
+
+        let digest = group_digest(group.map(|g| g.bytes.as_slice()));
+        let segment = match sorted.segments.get_mut(&digest) {
+            Some(segment) if segment.remaining > 0 => segment,
+            _ => return Ok(None),
+        };
+        let (value, len) = read_record(&mut sorted.file, segment.offset).map_err(|e| {
+            DbError::Mask(format!("列 {}: 读取洗牌临时文件失败: {}", self.column, e))
+        })?;
+        segment.offset += len;
+        segment.remaining -= 1;
+        Ok(Some(value))
+    }
+
+    /// 尚未取出的值的数量
+    pub fn remaining(&self) -> usize {
+        match &self.sorted {
+            Some(sorted) => sorted.segments.values().map(|s| s.remaining).sum(),
+            None => self.groups.values().map(Vec::len).sum::<usize>() + self.spilled,
+        }
+    }
+
+    /// 为缓冲中的值配上随机标签，按（分组, 标签）排序后作为一段写入临时文件
+    fn spill(&mut self) -> Result<(), DbError> {
+        let column = self.column.clone();
+        let io_error = |e: std::io::Error| {
+            DbError::Mask(format!("列 {}: 写入洗牌临时文件失败: {}", column, e))
+        };
+
+        if self.spill.is_none() {
+            self.spill = Some(tempfile::tempfile().map_err(io_error)?);
+        }
+        let file = self.spill.as_mut().expect("临时文件已创建");
+        file.seek(SeekFrom::Start(self.spill_len))
+            .map_err(io_error)?;
+        let mut writer = BufWriter::new(file);
+
+        let start = self.spill_len;
+        let mut groups: Vec<_> = self.groups.drain().collect();
+        groups.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        for (key, values) in groups {
+            self.spilled += values.len();
+            let mut tagged: Vec<(u64, Option<Vec<u8>>)> =
+                values.into_iter().map(|v| (self.rng.gen(), v)).collect();
+            tagged.sort_unstable_by_key(|(tag, _)| *tag);
+            for (tag, value) in tagged {
+                self.spill_len += write_record(&mut writer, key.as_deref()).map_err(io_error)?;
+                writer.write_all(&tag.to_be_bytes()).map_err(io_error)?;
+                self.spill_len += 8;
+                self.spill_len += write_record(&mut writer, value.as_deref()).map_err(io_error)?;
+            }
+        }
+
+        writer.flush().map_err(io_error)?;
+        if self.spill_len > start {
+            self.runs.push((start, self.spill_len));
+        }
+        self.buffered_bytes = 0;
+        Ok(())
+    }
+
+    /// 按（分组, 标签）归并各段，写入只含值的新临时文件并记录每组的位置
+    fn merge(&mut self) -> Result<(), DbError> {
+        let column = self.column.clone();
+        let io_error = |e: std::io::Error| {
+            DbError::Mask(format!("列 {}: 归并洗牌临时文件失败: {}", column, e))
+        };
+
+        let mut spill = self.spill.take().expect("存在分段时临时文件已创建");
+        let chunk = (self.memory_limit / self.runs.len()).clamp(MIN_READ_CHUNK, MAX_READ_CHUNK);
+        let mut readers: Vec<RunReader> = self
+            .runs
+            .drain(..)
+            .map(|(start, end)| RunReader::new(start, end))
+            .collect();
+
+        let mut heap = BinaryHeap::new();
+        for (index, reader) in readers.iter_mut().enumerate() {
+            if let Some((key, tag, value)) =
+                reader.next_record(&mut spill, chunk).map_err(io_error)?
+            {
+                heap.push(Reverse((key, tag, index, value)));
+            }
+        }
+
+        let mut file = tempfile::tempfile().map_err(io_error)?;
+        let mut segments = HashMap::new();
+        {
+            let mut writer = BufWriter::new(&mut file);
+            let mut offset = 0u64;
+            let mut current: Option<(Option<Vec<u8>>, Segment)> = None;
+            while let Some(Reverse((key, _, index, value))) = heap.pop() {
+                if current.as_ref().is_none_or(|(k, _)| *k != key) {
+                    if let Some((k, segment)) = current.take() {
+                        segments.insert(group_digest(k.as_deref()), segment);
+                    }
+                    current = Some((
+                        key,
+                        Segment {
+                            offset,
+                            remaining: 0,
+                        },
+                    ));
+                }
+                if let Some((_, segment)) = current.as_mut() {
+                    segment.remaining += 1;
+                }
+                offset += write_record(&mut writer, value.as_deref()).map_err(io_error)?;
+
+                if let Some((key, tag, value)) = readers[index]
+                    .next_record(&mut spill, chunk)
+                    .map_err(io_error)?
+                {
+                    heap.push(Reverse((key, tag, index, value)));
+                }
+            }
+            if let Some((k, segment)) = current {
+                segments.insert(group_digest(k.as_deref()), segment);
+            }
+            writer.flush().map_err(io_error)?;
+        }
+
+        self.spill_len = 0;
+        self.sorted = Some(Sorted { file, segments });
+        Ok(())
+    }
+
+    fn error(&self, message: String) -> DbError {
+        DbError::Mask(format!("列 {}: {}", self.column, message))
+    }
+}
+
+/// 分组键的摘要，归并后代替分组键本身保存在内存中
+fn group_digest(key: Option<&[u8]>) -> [u8; 16] {
+    let mut hasher = Sha256::new();
+    match key {
+        Some(bytes) => {
+            hasher.update([1]);
+            hasher.update(bytes);
+        }
+        None => hasher.update([0]),
+    }
+    let mut digest = [0u8; 16];
+    digest.copy_from_slice(&hasher.finalize()[..16]);
+    digest
+}
+
+/// 按块读取临时文件中的一段，多个段共用同一个文件，每次读取前定位
+struct RunReader {
+    offset: u64,
+    end: u64,
+    buffer: Vec<u8>,
+    consumed: usize,
+}
+
+impl RunReader {
+    fn new(start: u64, end: u64) -> Self {
+        Self {
+            offset: start,
+            end,
+            buffer: Vec::new(),
+            consumed: 0,
+        }
+    }
+
+    fn next_record(
+        &mut self,
+        file: &mut File,
+        chunk: usize,
+    ) -> std::io::Result<Option<TaggedRecord>> {
+        if self.consumed == self.buffer.len() && self.offset == self.end {
+            return Ok(None);
+        }
+        let key = self.read_value(file, chunk)?;
+        let mut tag = [0u8; 8];
+        self.read_exact(file, chunk, &mut tag)?;
+        let value = self.read_value(file, chunk)?;
+        Ok(Some((key, u64::from_be_bytes(tag), value)))
+    }
+
+    fn read_value(&mut self, file: &mut File, chunk: usize) -> std::io::Result<Option<Vec<u8>>> {
+        let mut len = [0u8; 4];
+        self.read_exact(file, chunk, &mut len)?;
+        let len = i32::from_be_bytes(len);
+        if len < 0 {
+            return Ok(None);
+        }
+        let mut bytes = vec![0u8; len as usize];
+        self.read_exact(file, chunk, &mut bytes)?;
+        Ok(Some(bytes))
+    }
+
+    fn read_exact(&mut self, file: &mut File, chunk: usize, out: &mut [u8]) -> std::io::Result<()> {
+        let mut filled = 0;
+        while filled < out.len() {
+            if self.consumed == self.buffer.len() {
+                let len = chunk.min((self.end - self.offset) as usize);
+                if len == 0 {
+                    return Err(std::io::ErrorKind::UnexpectedEof.into());
+                }
+                self.buffer.resize(len, 0);
+                file.seek(SeekFrom::Start(self.offset))?;
+                file.read_exact(&mut self.buffer)?;
+                self.offset += len as u64;
+                self.consumed = 0;
+            }
+            let n = (out.len() - filled).min(self.buffer.len() - self.consumed);
+            out[filled..filled + n].copy_from_slice(&self.buffer[self.consumed..self.consumed + n]);
+            filled += n;
+            self.consumed += n;
+        }
+        Ok(())
+    }
+}
+
+/// 写入一条记录：4 字节长度（NULL 为 -1）加数据，返回写入的字节数
+fn write_record(writer: &mut impl Write, value: Option<&[u8]>) -> std::io::Result<u64> {
+    match value {
+        Some(bytes) => {
+            writer.write_all(&(bytes.len() as i32).to_be_bytes())?;
+            writer.write_all(bytes)?;
+            Ok(4 + bytes.len() as u64)
+        }
+        None => {
+            writer.write_all(&(-1i32).to_be_bytes())?;
+            Ok(4)
+        }
+    }
+}
+
+/// 读取指定位置的一条记录，返回值和记录占用的字节数
+fn read_record(file: &mut File, offset: u64) -> std::io::Result<(Option<Vec<u8>>, u64)> {
+    file.seek(SeekFrom::Start(offset))?;
+    let mut len = [0u8; 4];
+    file.read_exact(&mut len)?;
+    let len = i32::from_be_bytes(len);
+    if len < 0 {
+        return Ok((None, 4));
+    }
+    let mut bytes = vec![0u8; len as usize];
+    file.read_exact(&mut bytes)?;
+    Ok((Some(bytes), 4 + len as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(value: &str) -> RawValue {
+        RawValue::new(Type::TEXT, value.as_bytes().to_vec())
+    }
+
+    /// 三个分组（含 NULL 分组键），每组的值含 NULL，值长度为 `width`
+    fn pushed(width: usize) -> Vec<(Option<RawValue>, Vec<Option<RawValue>>)> {
+        [None, Some("a"), Some("b")]
+            .into_iter()
+            .enumerate()
+            .map(|(g, key)| {
+                let values = (0..200 + g * 50)
+                    .map(|i| (i % 7 != 0).then(|| text(&format!("{}-{:0width$}", g, i))))
+                    .collect();
+                (key.map(text), values)
+            })
+            .collect()
+    }
+
+    fn shuffle(memory_limit: usize, width: usize) -> ColumnShuffler {
+        let groups = pushed(width);
+        let mut shuffler = ColumnShuffler::new("c", &Type::TEXT, memory_limit);
+        // 各组交错写入，每段都包含多个分组
+        let longest = groups.iter().map(|(_, v)| v.len()).max().unwrap_or(0);
+        for i in 0..longest {
+            for (key, values) in &groups {
+                if let Some(value) = values.get(i) {
+                    shuffler.push(key.as_ref(), value.clone()).unwrap();
+                }
+            }
+        }
+
+        let total: usize = groups.iter().map(|(_, v)| v.len()).sum();
+        assert_eq!(shuffler.remaining(), total);
+        for (key, values) in &groups {
+            let mut drained: Vec<_> = (0..values.len())
+                .map(|_| shuffler.next(key.as_ref()).unwrap().map(|v| v.bytes))
+                .collect();
+            let mut expected: Vec<_> = values.iter().map(|v| v.clone().map(|v| v.bytes)).collect();
+            assert_ne!(drained, expected, "值未被打乱");
+            drained.sort();
+            expected.sort();
+            assert_eq!(drained, expected);
+            // 分组取完后再取值报错
+            assert!(shuffler.next(key.as_ref()).is_err());
+        }
+        assert_eq!(shuffler.remaining(), 0);
+        assert!(shuffler.push(None, None).is_err());
+        shuffler
+    }
+
+    #[test]
+    fn shuffles_in_memory() {
+        let shuffler = shuffle(usize::MAX, 4);
+        assert!(shuffler.sorted.is_none());
+    }
+
+    #[test]
+    fn shuffles_many_small_runs() {
+        let shuffler = shuffle(256, 4);
+        assert!(shuffler.sorted.is_some());
+        assert!(shuffler.spill.is_none());
+    }
+
+    #[test]
+    fn shuffles_runs_across_read_chunks() {
+        // 每段约 32KB，远大于读取块，记录会跨越块边界
+        let shuffler = shuffle(32 * 1024, 300);
+        assert!(shuffler.sorted.is_some());
+    }
+
+    #[test]
+    fn unknown_group_errors() {
+        let mut shuffler = ColumnShuffler::new("c", &Type::TEXT, 64);
+        for i in 0..20 {
+            shuffler
+                .push(Some(&text("a")), Some(text(&i.to_string())))
+                .unwrap();
+        }
+        assert!(!shuffler.runs.is_empty());
+        assert!(shuffler.next(Some(&text("b"))).is_err());
+        assert!(shuffler.next(None).is_err());
+        assert!(shuffler.next(Some(&text("a"))).unwrap().is_some());
+    }
+}
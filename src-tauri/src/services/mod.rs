@@ -3,11 +3,14 @@
 // 子模块
 pub mod commands;
 mod storage;
+mod vault;
 pub mod command;
 pub mod db_copier;
+pub mod masking;
 
 pub use commands::*;
 pub use storage::*;
+pub use vault::*;
 
 // 命令处理服务
 pub mod service {
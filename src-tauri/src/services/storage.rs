@@ -1,10 +1,13 @@
-use crate::database::{Config, TaskStatus};
+use crate::database::{Config, MaskPolicy, TaskStatus};
 use std::path::PathBuf;
 use rusqlite::params;
 use tokio_rusqlite::Connection as AsyncConnection;
 use serde_json;
 use log::{info, error};
 use std::convert::From;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use rand::RngCore;
 
 #[derive(Debug)]
 pub struct Storage {
@@ -183,6 +186,128 @@ impl Storage {
         }).await
     }
 
+    // 获取脱敏密钥，不存在时生成随机密钥并保存
+    pub async fn get_or_create_mask_secret(&self, scope: &str) -> Result<Vec<u8>, tokio_rusqlite::Error> {
+        let scope_clone = scope.to_string();
+        let secret = self.conn.call(move |conn| {
+            match conn.query_row(
+                "SELECT secret FROM mask_secrets WHERE scope = ?1",
+                params![scope_clone],
+                |row| row.get::<_, String>(0),
+            ) {
+                Ok(secret) => Ok(secret),
+                Err(rusqlite::Error::QueryReturnedNoRows) => {
+                    let secret = generate_secret();
+                    conn.execute(
+                        "INSERT INTO mask_secrets (scope, secret) VALUES (?1, ?2)",
+                        params![scope_clone, secret],
+                    )?;
+                    info!("生成脱敏密钥: {}", scope_clone);
+                    Ok(secret)
+                }
+                Err(e) => Err(tokio_rusqlite::Error::Rusqlite(e)),
+            }
+        }).await?;
+
+        BASE64.decode(secret).map_err(|e| {
+            tokio_rusqlite::Error::Rusqlite(rusqlite::Error::InvalidParameterName(
+                format!("解析脱敏密钥失败: {}", e)
+            ))
+        })
+    }
+
+    // 设置脱敏密钥，未提供时重新生成随机密钥
+    pub async fn set_mask_secret(&self, scope: &str, secret: Option<&[u8]>) -> Result<(), tokio_rusqlite::Error> {
+        let scope_clone = scope.to_string();
+        let secret = match secret {
+            Some(secret) => BASE64.encode(secret),
+            None => generate_secret(),
+        };
+        self.conn.call(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO mask_secrets (scope, secret, created_at)
+                 VALUES (?1, ?2, CURRENT_TIMESTAMP)",
+                params![scope_clone, secret],
+            )?;
+            Ok(())
+        }).await?;
+
+        info!("更新脱敏密钥: {}", scope);
+        Ok(())
+    }
+
+    // 保存脱敏策略
+    pub async fn save_mask_policy(&self, policy: &MaskPolicy) -> Result<(), tokio_rusqlite::Error> {
+        let name = policy.name.clone();
+        let content = serde_json::to_string(policy).map_err(|e| {
+            tokio_rusqlite::Error::Rusqlite(rusqlite::Error::InvalidParameterName(
+                format!("序列化脱敏策略失败: {}", e)
+            ))
+        })?;
+
+        self.conn.call(move |conn| {
+            Ok(conn.execute(
+                "INSERT INTO mask_policies (name, content) VALUES (?1, ?2)
+                 ON CONFLICT(name) DO UPDATE SET content = ?2, updated_at = CURRENT_TIMESTAMP",
+                params![name, content],
+            )?)
+        }).await?;
+
+        info!("保存脱敏策略成功: {}", policy.name);
+        Ok(())
+    }
+
+    // 获取所有脱敏策略
+    pub async fn list_mask_policies(&self) -> Result<Vec<MaskPolicy>, tokio_rusqlite::Error> {
+        self.conn.call(|conn| {
+            let mut stmt = conn.prepare("SELECT content FROM mask_policies ORDER BY name")?;
+            let policies = stmt.query_map([], |row| {
+                let content: String = row.get(0)?;
+                serde_json::from_str(&content)
+                    .map_err(|e| rusqlite::Error::InvalidParameterName(
+                        format!("解析脱敏策略失败: {}", e)
+                    ))
+            })?
+            .collect::<Result<Vec<MaskPolicy>, _>>()?;
+            Ok(policies)
+        }).await
+    }
+
+    // 按名称顺序加载脱敏策略，不存在的策略返回错误
+    pub async fn load_mask_policies(&self, names: &[String]) -> Result<Vec<MaskPolicy>, tokio_rusqlite::Error> {
+        let all = self.list_mask_policies().await?;
+        // 按名称首次出现的顺序返回，重复的名称只取一次
+        let mut unique: Vec<&String> = Vec::with_capacity(names.len());
+        for name in names {
+            if !unique.contains(&name) {
+                unique.push(name);
+            }
+        }
+        unique.into_iter()
+            .map(|name| {
+                all.iter()
+                    .find(|p| &p.name == name)
+                    .cloned()
+                    .ok_or_else(|| tokio_rusqlite::Error::Rusqlite(
+                        rusqlite::Error::InvalidParameterName(format!("脱敏策略不存在: {}", name))
+                    ))
+            })
+            .collect()
+    }
+
+    pub async fn delete_mask_policy(&self, name: &str) -> Result<bool, tokio_rusqlite::Error> {
+        let name_clone = name.to_string();
+        let rows = self.conn.call(move |conn| {
+            Ok(conn.execute("DELETE FROM mask_policies WHERE name = ?1", params![name_clone])?)
+        }).await?;
+
+        if rows > 0 {
+            info!("删除脱敏策略成功: {}", name);
+        }
+
+        Ok(rows > 0)
+    }
+
     // 初始化数据库时添加任务表
     pub async fn init_db(&self) -> Result<(), tokio_rusqlite::Error> {
         self.conn.call(|conn| {
@@ -193,8 +318,32 @@ impl Storage {
                 )",
                 [],
             )?;
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS mask_secrets (
+                    scope TEXT PRIMARY KEY,
+                    secret TEXT NOT NULL,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                )",
+                [],
+            )?;
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS mask_policies (
+                    name TEXT PRIMARY KEY,
+                    content TEXT NOT NULL,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                )",
+                [],
+            )?;
             Ok(())
         }).await?;
         Ok(())
     }
+}
+
+/// 生成 32 字节随机密钥，以 base64 形式保存
+fn generate_secret() -> String {
+    let mut secret = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut secret);
+    BASE64.encode(secret)
 } 
\ No newline at end of file
@@ -0,0 +1,406 @@
+// 令牌库：保存令牌化脱敏中令牌到原值的映射
+//
+// 令牌库是与配置库分开的 SQLite 文件，原值以 AES-256-GCM 加密保存，密钥由用户提供的
+// 令牌库密钥经 PBKDF2 派生，不落盘。相同原值通过 HMAC 查找已有令牌，保证同一原值
+// 在不同表、不同任务中得到相同的令牌。
+//
+// SQLite 连接由专用线程持有：脱敏在复制管道中逐值同步执行，请求经通道发给该线程，
+// 调用方在 `block_in_place` 中等待结果，不会占住异步运行时的工作线程。新令牌在同一个
+// 事务中批量写入，攒够一批、通道空闲、调用 `flush` 或关闭令牌库时提交。
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use hmac::{Hmac, Mac};
+use log::{error, info};
+use rand::distributions::Alphanumeric;
+use rand::{Rng, RngCore};
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::Sha256;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tokio::runtime::{Handle, RuntimeFlavor};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 派生密钥的 PBKDF2 迭代次数
+const KDF_ROUNDS: u32 = 210_000;
+
+/// 用于校验令牌库密钥是否正确的固定消息
+const KEY_CHECK_MESSAGE: &[u8] = b"db-copier token vault";
+
+/// 生成令牌时遇到冲突的最大重试次数
+const MAX_TOKEN_ATTEMPTS: usize = 8;
+
+/// 一个写入事务中最多包含的新令牌数
+const COMMIT_BATCH_SIZE: usize = 1000;
+
+/// 有未提交的新令牌时，通道空闲超过该时长即提交
+const COMMIT_IDLE: Duration = Duration::from_millis(200);
+
+type Reply<T> = Sender<Result<T, String>>;
+
+/// 发给令牌库线程的请求
+enum Request {
+    Tokenize {
+        original: String,
+        prefix: String,
+        length: usize,
+        reply: Reply<String>,
+    },
+    Detokenize {
+        token: String,
+        reply: Reply<Option<String>>,
+    },
+    Flush {
+        reply: Reply<()>,
+    },
+}
+
+/// 已解锁的令牌库，请求由持有 SQLite 连接的专用线程处理
+pub struct TokenVault {
+    requests: Option<Sender<Request>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+/// 令牌库线程持有的状态
+struct VaultStore {
+    conn: Connection,
+    cipher: Aes256Gcm,
+    lookup_key: Vec<u8>,
+    /// 当前写入事务中未提交的新令牌数
+    pending: usize,
+}
+
+impl std::fmt::Debug for TokenVault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TokenVault").finish_non_exhaustive()
+    }
+}
+
+impl TokenVault {
+    /// 打开令牌库并用密钥解锁，首次使用时以该密钥初始化
+    ///
+    /// 密钥派生较慢，在异步代码中需通过 `spawn_blocking` 调用
+    pub fn open(vault_key: &str) -> Result<Self, String> {
+        if vault_key.is_empty() {
+            return Err("令牌库密钥不能为空".to_string());
+        }
+
+        let data_dir = PathBuf::from("data");
+        std::fs::create_dir_all(&data_dir).map_err(|e| format!("创建数据目录失败: {}", e))?;
+        let db_path = data_dir.join("vault.db");
+
+        let conn = Connection::open(&db_path).map_err(|e| format!("打开令牌库失败: {}", e))?;
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA synchronous = NORMAL;
+             CREATE TABLE IF NOT EXISTS vault_meta (
+                 id INTEGER PRIMARY KEY CHECK (id = 1),
+                 salt BLOB NOT NULL,
+                 key_check BLOB NOT NULL,
+                 created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+             );
+             CREATE TABLE IF NOT EXISTS tokens (
+                 token TEXT PRIMARY KEY,
+                 lookup BLOB NOT NULL UNIQUE,
+                 nonce BLOB NOT NULL,
+                 ciphertext BLOB NOT NULL,
+                 created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+             );",
+        )
+        .map_err(|e| format!("初始化令牌库失败: {}", e))?;
+
+        let meta: Option<(Vec<u8>, Vec<u8>)> = conn
+            .query_row(
+                "SELECT salt, key_check FROM vault_meta WHERE id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| format!("读取令牌库失败: {}", e))?;
+
+        let (cipher_key, lookup_key) = match meta {
+            Some((salt, key_check)) => {
+                let keys = derive_keys(vault_key, &salt);
+                let mut mac = new_mac(&keys.1);
+                mac.update(KEY_CHECK_MESSAGE);
+                mac.verify_slice(&key_check)
+                    .map_err(|_| "令牌库密钥错误".to_string())?;
+                keys
+            }
+            None => {
+                let mut salt = [0u8; 16];
+                rand::thread_rng().fill_bytes(&mut salt);
+                let keys = derive_keys(vault_key, &salt);
+                let mut mac = new_mac(&keys.1);
+                mac.update(KEY_CHECK_MESSAGE);
+                conn.execute(
+                    "INSERT INTO vault_meta (id, salt, key_check) VALUES (1, ?1, ?2)",
+                    params![salt.to_vec(), mac.finalize().into_bytes().to_vec()],
+                )
+                .map_err(|e| format!("初始化令牌库失败: {}", e))?;
+                info!("初始化令牌库: {:?}", db_path);
+                keys
+            }
+        };
+
+        let store = VaultStore {
+            conn,
+            cipher: Aes256Gcm::new_from_slice(&cipher_key).expect("密钥长度为 32 字节"),
+            lookup_key,
+            pending: 0,
+        };
+        let (requests, receiver) = mpsc::channel();
+        let worker = std::thread::Builder::new()
+            .name("token-vault".to_string())
+            .spawn(move || store.run(receiver))
+            .map_err(|e| format!("启动令牌库线程失败: {}", e))?;
+
+        Ok(Self {
+            requests: Some(requests),
+            worker: Some(worker),
+        })
+    }
+
+    /// 获取原值对应的令牌，不存在时生成新令牌并加密保存原值
+    pub fn tokenize(&self, original: &str, prefix: &str, length: usize) -> Result<String, String> {
+        self.call(|reply| Request::Tokenize {
+            original: original.to_string(),
+            prefix: prefix.to_string(),
+            length,
+            reply,
+        })
+    }
+
+    /// 查询令牌对应的原值，令牌不存在时返回 None
+    pub fn detokenize(&self, token: &str) -> Result<Option<String>, String> {
+        self.call(|reply| Request::Detokenize {
+            token: token.to_string(),
+            reply,
+        })
+    }
+
+    /// 提交所有新令牌，写入目标库之前调用，保证目标库中的令牌都能查到原值
+    pub fn flush(&self) -> Result<(), String> {
+        self.call(|reply| Request::Flush { reply })
+    }
+
+    /// 把请求发给令牌库线程并等待结果
+    ///
+    /// 在多线程运行时中通过 `block_in_place` 等待，运行时会把其他任务转移到别的工作线程
+    fn call<T>(&self, request: impl FnOnce(Reply<T>) -> Request) -> Result<T, String> {
+        let (reply, result) = mpsc::channel();
+        self.requests
+            .as_ref()
+            .and_then(|requests| requests.send(request(reply)).ok())
+            .ok_or_else(|| "令牌库已关闭".to_string())?;
+        let wait = move || result.recv();
+        let result = match Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                tokio::task::block_in_place(wait)
+            }
+            _ => wait(),
+        };
+        result.map_err(|_| "令牌库线程已退出".to_string())?
+    }
+}
+
+impl Drop for TokenVault {
+    fn drop(&mut self) {
+        // 关闭通道后令牌库线程提交未完成的事务并退出
+        drop(self.requests.take());
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl VaultStore {
+    /// 令牌库线程的主循环，通道关闭时提交并退出
+    fn run(mut self, receiver: Receiver<Request>) {
+        loop {
+            let request = if !self.conn.is_autocommit() {
+                match receiver.recv_timeout(COMMIT_IDLE) {
+                    Ok(request) => request,
+                    Err(RecvTimeoutError::Timeout) => {
+                        self.commit_logged();
+                        continue;
+                    }
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            } else {
+                match receiver.recv() {
+                    Ok(request) => request,
+                    Err(_) => break,
+                }
+            };
+
+            match request {
+                Request::Tokenize {
+                    original,
+                    prefix,
+                    length,
+                    reply,
+                } => {
+                    let _ = reply.send(self.tokenize(&original, &prefix, length));
+                }
+                Request::Detokenize { token, reply } => {
+                    let _ = reply.send(self.detokenize(&token));
+                }
+                Request::Flush { reply } => {
+                    let _ = reply.send(self.commit());
+                }
+            }
+            if self.pending >= COMMIT_BATCH_SIZE {
+                self.commit_logged();
+            }
+        }
+        self.commit_logged();
+    }
+
+    fn tokenize(&mut self, original: &str, prefix: &str, length: usize) -> Result<String, String> {
+        let lookup = self.lookup(original);
+        let existing: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT token FROM tokens WHERE lookup = ?1",
+                params![lookup],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("查询令牌库失败: {}", e))?;
+        if let Some(token) = existing {
+            return Ok(token);
+        }
+
+        if self.conn.is_autocommit() {
+            self.conn
+                .execute_batch("BEGIN")
+                .map_err(|e| format!("开启令牌库事务失败: {}", e))?;
+        }
+        let token = self.insert(&lookup, original, prefix, length)?;
+        self.pending += 1;
+        Ok(token)
+    }
+
+    /// 提交当前写入事务
+    fn commit(&mut self) -> Result<(), String> {
+        if self.conn.is_autocommit() {
+            return Ok(());
+        }
+        self.conn
+            .execute_batch("COMMIT")
+            .map_err(|e| format!("提交令牌库失败: {}", e))?;
+        info!("令牌库写入 {} 个新令牌", self.pending);
+        self.pending = 0;
+        Ok(())
+    }
+
+    fn commit_logged(&mut self) {
+        if let Err(e) = self.commit() {
+            error!("{}", e);
+        }
+    }
+
+    fn detokenize(&self, token: &str) -> Result<Option<String>, String> {
+        let row: Option<(Vec<u8>, Vec<u8>)> = self
+            .conn
+            .query_row(
+                "SELECT nonce, ciphertext FROM tokens WHERE token = ?1",
+                params![token],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| format!("查询令牌库失败: {}", e))?;
+
+        let (nonce, ciphertext) = match row {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+        let plaintext = self
+            .cipher
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: token.as_bytes(),
+                },
+            )
+            .map_err(|_| format!("令牌 {} 的原值解密失败", token))?;
+
+        String::from_utf8(plaintext)
+            .map(Some)
+            .map_err(|e| format!("令牌 {} 的原值不是有效的 UTF-8: {}", token, e))
+    }
+
+    fn insert(
+        &self,
+        lookup: &[u8],
+        original: &str,
+        prefix: &str,
+        length: usize,
+    ) -> Result<String, String> {
+        for _ in 0..MAX_TOKEN_ATTEMPTS {
+            let token = random_token(prefix, length);
+
+            let mut nonce = [0u8; 12];
+            rand::thread_rng().fill_bytes(&mut nonce);
+            // 令牌作为附加数据参与认证，密文无法挪用到其他令牌上
+            let ciphertext = self
+                .cipher
+                .encrypt(
+                    Nonce::from_slice(&nonce),
+                    Payload {
+                        msg: original.as_bytes(),
+                        aad: token.as_bytes(),
+                    },
+                )
+                .map_err(|e| format!("加密原值失败: {}", e))?;
+
+            let inserted = self
+                .conn
+                .execute(
+                    "INSERT OR IGNORE INTO tokens (token, lookup, nonce, ciphertext)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![token, lookup, nonce.to_vec(), ciphertext],
+                )
+                .map_err(|e| format!("写入令牌库失败: {}", e))?;
+            if inserted > 0 {
+                return Ok(token);
+            }
+        }
+
+        Err(format!(
+            "连续 {} 次生成的令牌均已存在，请增加令牌长度",
+            MAX_TOKEN_ATTEMPTS
+        ))
+    }
+
+    fn lookup(&self, original: &str) -> Vec<u8> {
+        let mut mac = new_mac(&self.lookup_key);
+        mac.update(original.as_bytes());
+        mac.finalize().into_bytes().to_vec()
+    }
+}
+
+fn new_mac(key: &[u8]) -> HmacSha256 {
+    <HmacSha256 as Mac>::new_from_slice(key).expect("HMAC 接受任意长度的密钥")
+}
+
+/// 由令牌库密钥派生加密密钥和查找密钥
+fn derive_keys(vault_key: &str, salt: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let mut derived = [0u8; 64];
+    pbkdf2::pbkdf2_hmac::<Sha256>(vault_key.as_bytes(), salt, KDF_ROUNDS, &mut derived);
+    (derived[..32].to_vec(), derived[32..].to_vec())
+}
+
+fn random_token(prefix: &str, length: usize) -> String {
+    let random: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(length)
+        .map(char::from)
+        .collect();
+    format!("{}{}", prefix, random)
+}
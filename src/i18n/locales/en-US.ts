@@ -103,6 +103,7 @@ export default {
     enterReplacement: 'Enter replacement value or pattern',
     columnCount: '{count} Columns',
     ruleCount: '{count} Rules',
+    previewMask: 'Preview Masking',
     treeTitle: 'Database Structure',
     selectAll: 'Select All',
     deselectAll: 'Deselect All',
@@ -120,7 +121,39 @@ export default {
       none: 'None',
       hash: 'Hash',
       fixed: 'Fixed',
-      pattern: 'Pattern'
+      pattern: 'Pattern',
+      hmac: 'HMAC',
+      email: 'Email',
+      phone: 'Phone',
+      bank_card: 'Bank Card',
+      id_card: 'ID Card',
+      fake_name: 'Fake Name',
+      fake_address: 'Fake Address',
+      fake_company: 'Fake Company',
+      fake_text: 'Fake Text',
+      date_shift: 'Date Shift',
+      noise: 'Noise',
+      round: 'Round',
+      clamp: 'Clamp',
+      json_path: 'JSON Path',
+      shuffle: 'Shuffle',
+      token: 'Tokenize'
+    },
+    suggestion: {
+      label: 'Looks like {kind}, suggest {rule} ({confidence}% confidence)',
+      accept: 'Accept',
+      reject: 'Dismiss'
+    },
+    piiKinds: {
+      email: 'email',
+      phone: 'phone number',
+      id_card: 'ID card number',
+      bank_card: 'bank card number',
+      name: 'name',
+      address: 'address',
+      credential: 'password or secret',
+      ip_address: 'IP address',
+      birth_date: 'birth date'
     },
     messages: {
       loadSuccess: 'Table structure loaded successfully',
@@ -129,21 +162,61 @@ export default {
       updateFailed: 'Failed to update table structure'
     }
   },
+  maskPreview: {
+    title: 'Mask Preview: {table}',
+    rows: 'Sample rows',
+    refresh: 'Refresh',
+    summary: '{rows} sampled rows, {columns} masked columns',
+    noMaskedColumns: 'No selected column in this table has a mask rule',
+    invalidRule: 'Invalid rule',
+    notPreviewable: 'Not previewable',
+    fieldRules: '{count} field rules',
+    original: 'Original',
+    masked: 'Masked',
+    flagsColumn: 'Flags',
+    loadFailed: 'Preview failed: {error}',
+    flags: {
+      error: 'Error',
+      source_length_overflow: 'Exceeds column length',
+      source_length_overflow_max: 'Exceeds column length {max}',
+      unchanged: 'Unchanged'
+    }
+  },
   databaseConfig: {
     source: 'Source Database',
     target: 'Target Database',
+    connectionString: 'Connection String',
+    connectionStringPlaceholder: "postgresql://user{'@'}host:5432/db or host=... dbname=...",
+    importConnectionString: 'Import',
     host: 'Host',
-    hostPlaceholder: 'e.g. localhost',
+    hostPlaceholder: 'e.g. localhost, or a Unix socket directory like /var/run/postgresql; leave empty for the default local socket',
+    extraHosts: 'Other Hosts',
+    extraHostsPlaceholder: 'Tried in order, e.g. replica1:5432,replica2:5432',
+    targetSessionAttrs: 'Target Session',
+    targetSessionAttrsOptions: {
+      any: 'Any',
+      'read-write': 'Read-Write (primary)',
+      'prefer-standby': 'Prefer Standby'
+    },
     port: 'Port',
     database: 'Database',
     username: 'Username',
     password: 'Password',
+    passwordPlaceholder: 'Leave empty to read from ~/.pgpass',
     sslMode: 'SSL Mode',
     sslModes: {
       prefer: 'Prefer',
       require: 'Require',
-      disable: 'Disable'
+      disable: 'Disable',
+      'verify-ca': 'Verify CA',
+      'verify-full': 'Verify Full'
     },
+    sslRootCert: 'Root certificate (CA)',
+    sslRootCertPlaceholder: 'Path to CA certificate file (PEM)',
+    sslCert: 'Client certificate',
+    sslCertPlaceholder: 'Path to client certificate file (PEM)',
+    sslKey: 'Client key',
+    sslKeyPlaceholder: 'Path to client key file (PKCS#8 PEM)',
     testConnection: 'Test Connection',
     enableSSH: 'Enable SSH Tunnel',
     sshHost: 'SSH Host',
@@ -153,20 +226,34 @@ export default {
     authType: 'Authentication Type',
     authTypes: {
       password: 'Password',
-      privateKey: 'Private Key'
+      privateKey: 'Private Key',
+      agent: 'SSH Agent'
     },
     privateKey: 'Private Key',
     selectPrivateKeyPlaceholder: 'Please select private key file',
     selectFile: 'Select File',
+    privateKeyContent: 'Private Key Content',
+    privateKeyContentPlaceholder: 'Or paste the private key (OpenSSH/PEM); takes precedence over the key file',
+    passphrase: 'Passphrase',
+    passphrasePlaceholder: 'Leave empty if the key is not encrypted',
+    agentHint: 'Try each key held by the local SSH agent',
+    jumpHosts: 'Jump Hosts',
+    addJumpHost: 'Add Jump Host',
+    jumpHostsHint: 'Connected in list order; the SSH host above is reached through the last one. Each hop has its own authentication',
+    jumpHost: 'Hop {index}',
     errors: {
       hostRequired: 'Host is required',
       databaseRequired: 'Database name is required',
       usernameRequired: 'Username is required',
       connectionFailed: 'Connection failed: {error}',
-      selectKeyFailed: 'Failed to select private key file'
+      selectKeyFailed: 'Failed to select private key file',
+      connectionStringInvalid: 'Invalid connection string: {error}'
     },
     messages: {
-      connectionSuccess: 'Connection successful: {message}'
+      connectionSuccess: 'Connection successful: {message}',
+      connectionStringImported: 'Imported from connection string',
+      trustHostKeyTitle: 'Confirm SSH host key',
+      trustHostKey: 'Host {host} is not trusted yet. Its {keyType} key fingerprint is {fingerprint}. Confirm to pin this fingerprint in the configuration.'
     }
   },
   configDetail: {
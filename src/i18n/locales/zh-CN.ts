@@ -103,6 +103,7 @@ export default {
     enterReplacement: '请输入替换值或模式',
     columnCount: '{count} 列',
     ruleCount: '{count} 规则',
+    previewMask: '预览脱敏',
     treeTitle: '数据库表结构',
     selectAll: '全选',
     deselectAll: '取消全选',
@@ -120,7 +121,39 @@ export default {
       none: '无',
       hash: '哈希',
       fixed: '固定值',
-      pattern: '模式'
+      pattern: '模式',
+      hmac: 'HMAC',
+      email: '邮箱',
+      phone: '手机号',
+      bank_card: '银行卡号',
+      id_card: '身份证号',
+      fake_name: '虚构姓名',
+      fake_address: '虚构地址',
+      fake_company: '虚构公司名',
+      fake_text: '虚构文本',
+      date_shift: '日期平移',
+      noise: '数值噪声',
+      round: '取整',
+      clamp: '范围限制',
+      json_path: 'JSON 路径',
+      shuffle: '洗牌',
+      token: '令牌化'
+    },
+    suggestion: {
+      label: '疑似{kind}，建议{rule}（置信度 {confidence}%）',
+      accept: '采纳',
+      reject: '忽略'
+    },
+    piiKinds: {
+      email: '邮箱',
+      phone: '手机号',
+      id_card: '身份证号',
+      bank_card: '银行卡号',
+      name: '姓名',
+      address: '地址',
+      credential: '密码或密钥',
+      ip_address: 'IP 地址',
+      birth_date: '出生日期'
     },
     messages: {
       loadSuccess: '加载表结构成功',
@@ -129,21 +162,61 @@ export default {
       updateFailed: '更新表结构失败'
     }
   },
+  maskPreview: {
+    title: '脱敏预览：{table}',
+    rows: '抽样行数',
+    refresh: '刷新',
+    summary: '抽样 {rows} 行，{columns} 个脱敏列',
+    noMaskedColumns: '该表没有选中且配置了脱敏规则的列',
+    invalidRule: '规则无效',
+    notPreviewable: '不支持预览',
+    fieldRules: '{count} 个字段规则',
+    original: '原值',
+    masked: '脱敏结果',
+    flagsColumn: '标记',
+    loadFailed: '预览失败：{error}',
+    flags: {
+      error: '出错',
+      source_length_overflow: '超出列长度',
+      source_length_overflow_max: '超出列长度 {max}',
+      unchanged: '未改变'
+    }
+  },
   databaseConfig: {
     source: '源数据库',
     target: '目标数据库',
+    connectionString: '连接串',
+    connectionStringPlaceholder: "postgresql://user{'@'}host:5432/db 或 host=... dbname=...",
+    importConnectionString: '导入',
     host: '主机地址',
-    hostPlaceholder: '例如：localhost',
+    hostPlaceholder: '例如：localhost，或 Unix 套接字目录 /var/run/postgresql；留空时使用本机默认套接字',
+    extraHosts: '其他主机',
+    extraHostsPlaceholder: '按顺序尝试，如 replica1:5432,replica2:5432',
+    targetSessionAttrs: '会话要求',
+    targetSessionAttrsOptions: {
+      any: '任意 (any)',
+      'read-write': '主库 (read-write)',
+      'prefer-standby': '优先备库 (prefer-standby)'
+    },
     port: '端口',
     database: '数据库名',
     username: '用户名',
     password: '密码',
+    passwordPlaceholder: '留空时从 ~/.pgpass 读取',
     sslMode: 'SSL模式',
     sslModes: {
       prefer: '首选 (prefer)',
       require: '要求 (require)',
-      disable: '禁用 (disable)'
+      disable: '禁用 (disable)',
+      'verify-ca': '校验 CA (verify-ca)',
+      'verify-full': '完全校验 (verify-full)'
     },
+    sslRootCert: '根证书 (CA)',
+    sslRootCertPlaceholder: 'CA 证书文件路径（PEM）',
+    sslCert: '客户端证书',
+    sslCertPlaceholder: '客户端证书文件路径（PEM）',
+    sslKey: '客户端私钥',
+    sslKeyPlaceholder: '客户端私钥文件路径（PKCS#8 PEM）',
     testConnection: '测试连接',
     enableSSH: '启用 SSH 隧道',
     sshHost: 'SSH 主机',
@@ -153,20 +226,34 @@ export default {
     authType: '认证方式',
     authTypes: {
       password: '密码',
-      privateKey: '密钥'
+      privateKey: '密钥',
+      agent: 'SSH Agent'
     },
     privateKey: '私钥',
     selectPrivateKeyPlaceholder: '请选择私钥文件',
     selectFile: '选择文件',
+    privateKeyContent: '私钥内容',
+    privateKeyContentPlaceholder: '也可以直接粘贴私钥内容（OpenSSH/PEM 格式），填写后优先于私钥文件',
+    passphrase: '私钥口令',
+    passphrasePlaceholder: '私钥未加密时留空',
+    agentHint: '依次尝试本机 SSH Agent 中的密钥',
+    jumpHosts: '跳板机',
+    addJumpHost: '添加跳板机',
+    jumpHostsHint: '按列表顺序依次连接，经过最后一个跳板机后再连接上面的 SSH 主机；每个跳板机使用自己的认证方式',
+    jumpHost: '第 {index} 跳',
     errors: {
       hostRequired: '主机地址不能为空',
       databaseRequired: '数据库名不能为空',
       usernameRequired: '用户名不能为空',
       connectionFailed: '连接失败: {error}',
-      selectKeyFailed: '选择私钥文件失败'
+      selectKeyFailed: '选择私钥文件失败',
+      connectionStringInvalid: '连接串无效: {error}'
     },
     messages: {
-      connectionSuccess: '连接成功: {message}'
+      connectionSuccess: '连接成功: {message}',
+      connectionStringImported: '已从连接串导入',
+      trustHostKeyTitle: '确认 SSH 主机密钥',
+      trustHostKey: '主机 {host} 尚未被信任，其 {keyType} 密钥指纹为 {fingerprint}。确认无误后将在配置中固定该指纹。'
     }
   },
   configDetail: {
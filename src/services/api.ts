@@ -4,7 +4,7 @@
  */
 
 import { invoke } from "@tauri-apps/api/core";
-import { DatabaseConfig, Config, TaskStatus, TableConfig } from '@/types';
+import { DatabaseConfig, Config, TaskStatus, TableConfig, MaskPreview, MaskPolicy, ConfigSummary, TokenLookup, ReportFormat, ConnectionTestResult, ConnectionStringFormat } from '@/types';
 
 /**
  * 配置相关API
@@ -71,6 +71,71 @@ export const configApi = {
   async mergeTableConfig(databaseConfig: DatabaseConfig, tableConfigs: TableConfig[]): Promise<TableConfig[]> {
     return await invoke<TableConfig[]>('merge_table_config', { databaseConfig, tableConfigs });
   },
+
+  /**
+   * 预览表的脱敏效果
+   * @param config 配置对象
+   * @param tableName 表名
+   * @param rows 抽样行数，默认 20
+   * @returns 各脱敏列脱敏前后的对比
+   */
+  async previewMask(config: Config, tableName: string, rows?: number): Promise<MaskPreview> {
+    return await invoke<MaskPreview>('preview_mask', { config, tableName, rows });
+  },
+
+  /**
+   * 获取配置摘要，包含各脱敏列最终生效的规则
+   * @param config 配置对象
+   */
+  async summary(config: Config): Promise<ConfigSummary> {
+    return await invoke<ConfigSummary>('get_config_summary', { config });
+  },
+
+  /**
+   * 设置HMAC脱敏密钥
+   * @param scope 密钥作用域（配置名或共享作用域）
+   * @param secret base64 编码的密钥，为空时重新生成
+   */
+  async setMaskSecret(scope: string, secret?: string): Promise<void> {
+    await invoke('set_mask_secret', { scope, secret });
+  },
+};
+
+/**
+ * 脱敏策略相关API
+ */
+export const policyApi = {
+  /**
+   * 获取所有脱敏策略
+   */
+  async list(): Promise<MaskPolicy[]> {
+    return await invoke<MaskPolicy[]>('list_mask_policies');
+  },
+
+  /**
+   * 保存脱敏策略，同名策略会被覆盖
+   * @param policy 脱敏策略
+   */
+  async save(policy: MaskPolicy): Promise<void> {
+    await invoke('save_mask_policy', { policy });
+  },
+
+  /**
+   * 删除脱敏策略
+   * @param name 策略名称
+   */
+  async delete(name: string): Promise<void> {
+    await invoke('delete_mask_policy', { name });
+  },
+
+  /**
+   * 测试名称是否匹配模式
+   * @param pattern 模式，支持 * 和 ?
+   * @param name 表名或列名
+   */
+  async testPattern(pattern: string, name: string): Promise<boolean> {
+    return await invoke<boolean>('test_mask_pattern', { pattern, name });
+  },
 };
 
 /**
@@ -80,10 +145,11 @@ export const taskApi = {
   /**
    * 启动复制任务
    * @param config 配置对象
+   * @param vaultKey 令牌库密钥，使用令牌化规则时必填
    * @returns 任务ID
    */
-  async start(config: Config): Promise<string> {
-    return await invoke<string>('start_copy', { config });
+  async start(config: Config, vaultKey?: string): Promise<string> {
+    return await invoke<string>('start_copy', { config, vaultKey });
   },
 
   /**
@@ -95,6 +161,26 @@ export const taskApi = {
     return await invoke<TaskStatus>('get_task_status', { taskId });
   },
 
+  /**
+   * 获取任务的脱敏审计报告
+   * @param taskId 任务ID
+   * @param format 报告格式
+   * @returns 报告内容
+   */
+  async getReport(taskId: string, format: ReportFormat): Promise<string> {
+    return await invoke<string>('get_task_report', { taskId, format });
+  },
+
+  /**
+   * 导出任务的脱敏审计报告到文件
+   * @param taskId 任务ID
+   * @param format 报告格式
+   * @param filePath 文件路径
+   */
+  async exportReport(taskId: string, format: ReportFormat, filePath: string): Promise<void> {
+    await invoke('export_task_report', { taskId, format, filePath });
+  },
+
   /**
    * 停止任务
    * @param taskId 任务ID
@@ -112,12 +198,35 @@ export const databaseApi = {
   testConnection: async (config: DatabaseConfig) => {
     try {
       console.log('发送测试连接请求:', config);
-      return await invoke<string>('test_connection', { config });
+      return await invoke<ConnectionTestResult>('test_connection', { config });
     } catch (error) {
       console.error('测试连接失败:', error);
       throw error;
     }
   },
 
+  // 解析 URI 或键值对格式的连接串
+  parseConnectionString: async (connectionString: string) => {
+    return await invoke<DatabaseConfig>('parse_connection_string', { connectionString });
+  },
+
+  // 将数据库配置生成为连接串
+  formatConnectionString: async (config: DatabaseConfig, format: ConnectionStringFormat, includePassword: boolean) => {
+    return await invoke<string>('format_connection_string', { config, format, includePassword });
+  },
+
 };
 
+/**
+ * 令牌库相关API
+ */
+export const vaultApi = {
+  /**
+   * 查询令牌对应的原值
+   * @param vaultKey 令牌库密钥
+   * @param tokens 令牌列表
+   */
+  async lookup(vaultKey: string, tokens: string[]): Promise<TokenLookup[]> {
+    return await invoke<TokenLookup[]>('lookup_tokens', { vaultKey, tokens });
+  }
+};
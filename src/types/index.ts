@@ -1,11 +1,36 @@
+// SSL 模式，与 libpq 的 sslmode 一致
+export type SslMode = 'disable' | 'prefer' | 'require' | 'verify-ca' | 'verify-full';
+
+// 连接串格式：postgresql:// URI 或 key=value 键值对
+export type ConnectionStringFormat = 'uri' | 'dsn';
+
+// 数据库主机，host 以 / 开头时为 Unix 套接字目录
+export interface DbHost {
+  host: string;
+  port: number;
+}
+
+// 会话要求，与 libpq 的 target_session_attrs 一致
+export type TargetSessionAttrs = 'any' | 'read-write' | 'prefer-standby';
+
 // 数据库配置
 export interface DatabaseConfig {
+  // 主机名或 IP；以 / 开头时为 Unix 套接字目录，留空时使用默认套接字
   host: string;
   port: number;
+  // 在 host/port 之后依次尝试的其他主机
+  extra_hosts?: DbHost[];
+  target_session_attrs?: TargetSessionAttrs;
   database: string;
   username: string;
+  // 留空时从 ~/.pgpass 读取
   password: string;
-  ssl_mode?: string;
+  ssl_mode?: SslMode;
+  // 根证书（CA）文件路径
+  ssl_root_cert?: string;
+  // 客户端证书和私钥文件路径，用于双向认证
+  ssl_cert?: string;
+  ssl_key?: string;
   ssh_config?: SSHConfig;
 }
 
@@ -14,17 +39,109 @@ export interface SSHConfig {
   host: string;
   port: number;
   username: string;
-  auth_type: 'password' | 'private_key';
+  auth_type: 'password' | 'private_key' | 'agent';
   password?: string;
   private_key_path?: string;
+  // 内联的私钥内容，优先于 private_key_path
+  private_key?: string;
   passphrase?: string;
+  // 固定的主机密钥指纹（SHA256:...）
+  host_key_fingerprint?: string;
+  // 按顺序经过的跳板机（类似 ProxyJump），每一跳有自己的认证设置
+  jump_hosts?: SSHConfig[];
+}
+
+export type HostKeyStatus = 'pinned' | 'known_hosts' | 'unknown';
+
+// SSH 服务器的主机密钥信息
+export interface HostKeyInfo {
+  host: string;
+  port: number;
+  key_type: string;
+  fingerprint: string;
+  status: HostKeyStatus;
+}
+
+// 测试连接的结果
+export interface ConnectionTestResult {
+  // 遇到未被信任的 SSH 主机时为 false，此时没有发送凭据
+  connected: boolean;
+  message: string;
+  // 各跳的主机密钥，按跳板机顺序排列，最后一个为 SSH 主机；
+  // 未连接时到第一个未被信任的主机为止
+  ssh_host_keys: HostKeyInfo[];
 }
 
 export type TableInfoChangeStatus = 'Added' | 'Removed';
 
+export type HashEncoding = 'hex' | 'base64' | 'numeric';
+
+export interface HashOptions {
+  encoding?: HashEncoding;
+  length?: number;
+}
+
+export type MaskRuleType =
+  | 'hash'
+  | 'fixed'
+  | 'pattern'
+  | 'hmac'
+  | 'email'
+  | 'phone'
+  | 'bank_card'
+  | 'id_card'
+  | 'fake_name'
+  | 'fake_address'
+  | 'fake_company'
+  | 'fake_text'
+  | 'date_shift'
+  | 'noise'
+  | 'round'
+  | 'clamp'
+  | 'json_path'
+  | 'shuffle'
+  | 'token';
+
+export type FakeLocale = 'zh-CN' | 'en-US';
+
+export interface PerturbOptions {
+  days?: number;
+  percent?: number;
+  step?: number;
+  min?: number;
+  max?: number;
+  seed_column?: string;
+}
+
 export interface MaskRule {
-  rule_type: 'hash' | 'fixed' | 'pattern';
+  rule_type: MaskRuleType;
   pattern?: string;
+  hash_options?: HashOptions;
+  domain?: string;
+  locale?: FakeLocale;
+  perturb_options?: PerturbOptions;
+  json_options?: JsonMaskOptions;
+  shuffle_options?: ShuffleOptions;
+  token_options?: TokenOptions;
+}
+
+export interface ShuffleOptions {
+  group_by?: string;
+}
+
+export interface TokenOptions {
+  prefix?: string;
+  length?: number;
+}
+
+export interface TokenLookup {
+  token: string;
+  original?: string;
+}
+
+export interface JsonMaskOptions {
+  paths: string[];
+  rule: MaskRule;
 }
 
 export interface ColumnConfig {
@@ -32,6 +149,34 @@ export interface ColumnConfig {
   mask_rule?: MaskRule;
   selected: boolean;
   status?: TableInfoChangeStatus;
+  suggestion?: MaskSuggestion;
+  field_rules?: FieldMaskRule[];
+}
+
+// 复合类型字段的脱敏规则，嵌套字段用 `.` 分隔
+export interface FieldMaskRule {
+  field: string;
+  rule: MaskRule;
+}
+
+// 敏感数据类别
+export type PiiKind =
+  | 'email'
+  | 'phone'
+  | 'id_card'
+  | 'bank_card'
+  | 'name'
+  | 'address'
+  | 'credential'
+  | 'ip_address'
+  | 'birth_date';
+
+// 敏感数据检测给出的建议规则
+export interface MaskSuggestion {
+  kind: PiiKind;
+  rule: MaskRule;
+  confidence: number;
+  reasons: string[];
 }
 
 // 表配置
@@ -50,6 +195,8 @@ export interface Config {
   source_db: DatabaseConfig;
   target_db: DatabaseConfig;
   tables: TableConfig [];
+  mask_secret_scope?: string;
+  mask_policies?: string[];
 }
 
 export interface Progress {
@@ -67,11 +214,109 @@ export interface TaskStatus {
   end_time?: string;
   message?: string;
   progress?: Progress;
+  audit_report?: MaskAuditReport;
+}
+
+// 脱敏审计报告
+export interface AuditColumn {
+  column: string;
+  rule?: MaskRule;
+  policy?: string;
+}
+
+export interface AuditTable {
+  table: string;
+  structure_only: boolean;
+  rows?: number;
+  columns: AuditColumn[];
 }
 
+export interface UnmaskedColumn {
+  table: string;
+  column: string;
+  kind: PiiKind;
+  confidence: number;
+  reason: string;
+}
+
+export interface MaskAuditReport {
+  task_id: string;
+  config_name: string;
+  generated_at: string;
+  completed: boolean;
+  tables: AuditTable[];
+  unmasked_sensitive: UnmaskedColumn[];
+}
+
+export type ReportFormat = 'json' | 'html';
+
 // 任务
 export interface Task {
   id: string;
   config: Config;
   status: TaskStatus;
-} 
\ No newline at end of file
+}
+
+// 脱敏预览标记
+export type PreviewFlag = 'error' | 'source_length_overflow' | 'unchanged';
+
+// 单个值的脱敏前后对比
+export interface ValuePreview {
+  original?: string;
+  masked?: string;
+  flags: PreviewFlag[];
+  error?: string;
+}
+
+// 单列的脱敏预览
+export interface ColumnPreview {
+  column: string;
+  rule?: MaskRule;
+  field_rules?: FieldMaskRule[];
+  source_max_length?: number;
+  error?: string;
+  // 无法预览的原因，如令牌化规则
+  not_previewable?: string;
+  values: ValuePreview[];
+}
+
+// 表的脱敏预览
+export interface MaskPreview {
+  table: string;
+  row_count: number;
+  columns: ColumnPreview[];
+}
+
+// 脱敏策略中的一条规则，模式支持 * 和 ?，不区分大小写
+export interface PolicyRule {
+  table_pattern?: string;
+  column_pattern: string;
+  rule: MaskRule;
+}
+
+// 可在多个配置间复用的脱敏策略
+export interface MaskPolicy {
+  name: string;
+  description?: string;
+  rules: PolicyRule[];
+}
+
+// 列最终生效的脱敏规则
+export interface EffectiveMaskRule {
+  table: string;
+  column: string;
+  rule: MaskRule;
+  policy?: string;
+}
+
+// 配置摘要
+export interface ConfigSummary {
+  source_db: string;
+  target_db: string;
+  table_count: number;
+  total_columns: number;
+  has_source_ssh: boolean;
+  has_target_ssh: boolean;
+  mask_policies: string[];
+  mask_rules: EffectiveMaskRule[];
+}